@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use serde_aux::field_attributes::deserialize_number_from_string;
 
 use crate::errors::BackoffMpesaResult;
-use crate::{Mpesa, MpesaError, ResponseError};
+use crate::{Mpesa, MpesaError, ResponseError, redact};
 
 const AUTHENTICATION_URL: &str = "/oauth/v1/generate";
 
@@ -31,7 +31,13 @@ pub(crate) async fn auth(client: &Mpesa) -> BackoffMpesaResult<String> {
             .map_err(MpesaError::from)
             .map_err(MpesaError::to_retryable)?;
         let value: AuthenticationResponse = serde_json::from_str(&text)
-            .inspect_err(|e| log::error!("error decoding body err: {}: {}", e, text))
+            .inspect_err(|e| {
+                log::error!(
+                    "error decoding body err: {}: {}",
+                    redact::text(&e.to_string()),
+                    redact::body(&text)
+                )
+            })
             .map_err(MpesaError::from)
             .map_err(MpesaError::to_retryable)?;
         let access_token = value.access_token;
@@ -72,8 +78,8 @@ pub(crate) async fn auth(client: &Mpesa) -> BackoffMpesaResult<String> {
                     url,
                     status,
                     is_content_type_html,
-                    err,
-                    text
+                    redact::text(&err.to_string()),
+                    redact::body(&text)
                 );
                 MpesaError::to_retryable(MpesaError::from(err))
             }
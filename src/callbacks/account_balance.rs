@@ -0,0 +1,317 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+
+use super::ResultCallback;
+use crate::errors::{MpesaError, MpesaResult};
+
+/// Separates the accounts in the `AccountBalance` result parameter
+const ACCOUNT_SEPARATOR: char = '&';
+/// Separates the fields of a single account in the `AccountBalance` result parameter
+const FIELD_SEPARATOR: char = '|';
+
+/// The balances of all accounts of a shortcode, parsed from the `AccountBalance` result parameter.
+///
+/// M-Pesa encodes the balances as a string of `&` separated accounts, each with `|` separated fields in the order
+/// `name|currency|current|available|reserved|uncleared`
+/// e.g `Working Account|KES|1000.00|1000.00|0.00|0.00&Utility Account|KES|500.00|500.00|0.00|0.00`
+///
+/// # Example
+///
+/// ```rust
+/// use mpesa::callbacks::AccountBalances;
+///
+/// let balances: AccountBalances = "Working Account|KES|1000.00|1000.00|0.00|0.00&Utility Account|KES|500.00|500.00|0.00|0.00"
+///     .parse()
+///     .unwrap();
+///
+/// assert_eq!(balances.len(), 2);
+/// assert_eq!(balances.get("Utility Account").unwrap().available.to_string(), "500.00");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountBalances {
+    pub accounts: Vec<AccountBalanceEntry>,
+}
+
+/// The balance of a single account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountBalanceEntry {
+    /// The name of the account e.g `Working Account`
+    pub name: String,
+    /// The currency of the account e.g `KES`
+    pub currency: String,
+    /// The current, total balance of the account
+    pub total: Decimal,
+    /// Amount available for transactions
+    pub available: Decimal,
+    /// Amount reserved for pending transactions
+    pub reserved: Decimal,
+    /// Amount that is yet to be cleared
+    pub uncleared: Decimal,
+}
+
+/// Result parameters of an account balance query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountBalanceResult {
+    /// The balances of the shortcode's accounts
+    pub account_balance: Option<AccountBalances>,
+    /// When the query completed on M-Pesa
+    pub bo_completed_time: Option<NaiveDateTime>,
+}
+
+impl AccountBalances {
+    /// Gets the balance of the account with the given `name`, e.g `Working Account`
+    pub fn get(&self, name: &str) -> Option<&AccountBalanceEntry> {
+        self.accounts.iter().find(|account| account.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AccountBalanceEntry> {
+        self.accounts.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a AccountBalances {
+    type Item = &'a AccountBalanceEntry;
+    type IntoIter = std::slice::Iter<'a, AccountBalanceEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.accounts.iter()
+    }
+}
+
+fn parse_amount(account: &str, field: &str, value: &str) -> MpesaResult<Decimal> {
+    Decimal::from_str(value.trim())
+        .map_err(|e| MpesaError::InvalidAccountBalance(format!("{field} of `{account}` is not a valid amount: {e}")))
+}
+
+impl FromStr for AccountBalanceEntry {
+    type Err = MpesaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.split(FIELD_SEPARATOR).collect();
+        let [name, currency, total, available, reserved, uncleared] = fields[..] else {
+            return Err(MpesaError::InvalidAccountBalance(format!(
+                "expected 6 fields separated by `{FIELD_SEPARATOR}` but found {} in `{s}`",
+                fields.len()
+            )));
+        };
+
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(MpesaError::InvalidAccountBalance(format!(
+                "missing account name in `{s}`"
+            )));
+        }
+
+        Ok(AccountBalanceEntry {
+            name: name.to_owned(),
+            currency: currency.trim().to_owned(),
+            total: parse_amount(name, "total", total)?,
+            available: parse_amount(name, "available", available)?,
+            reserved: parse_amount(name, "reserved", reserved)?,
+            uncleared: parse_amount(name, "uncleared", uncleared)?,
+        })
+    }
+}
+
+impl FromStr for AccountBalances {
+    type Err = MpesaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let accounts = s
+            .split(ACCOUNT_SEPARATOR)
+            .filter(|account| !account.trim().is_empty())
+            .map(AccountBalanceEntry::from_str)
+            .collect::<MpesaResult<Vec<_>>>()?;
+
+        if accounts.is_empty() {
+            return Err(MpesaError::InvalidAccountBalance("no accounts found".to_owned()));
+        }
+
+        Ok(AccountBalances { accounts })
+    }
+}
+
+impl Display for AccountBalanceEntry {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "{}|{}|{}|{}|{}|{}",
+            self.name, self.currency, self.total, self.available, self.reserved, self.uncleared
+        )
+    }
+}
+
+impl Display for AccountBalances {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for (i, account) in self.accounts.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{ACCOUNT_SEPARATOR}")?;
+            }
+            write!(f, "{account}")?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&ResultCallback> for AccountBalanceResult {
+    type Error = MpesaError;
+
+    fn try_from(result: &ResultCallback) -> Result<Self, Self::Error> {
+        Ok(AccountBalanceResult {
+            account_balance: result
+                .string_parameter("AccountBalance")
+                .map(|balance| balance.parse())
+                .transpose()?,
+            bo_completed_time: result.datetime_parameter("BOCompletedTime"),
+        })
+    }
+}
+
+impl ResultCallback {
+    /// Typed view of the result parameters of an account balance query
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::InvalidAccountBalance` if the `AccountBalance` parameter is malformed
+    pub fn account_balance(&self) -> MpesaResult<AccountBalanceResult> {
+        AccountBalanceResult::try_from(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn decimal(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_account_balances() {
+        let balances: AccountBalances = "Working Account|KES|700000.00|700000.00|0.00|0.00&Float Account|KES|0.00|0.00|0.00|0.00&Utility Account|KES|228037.00|228037.00|0.00|0.00&Charges Paid Account|KES|-1540.00|-1540.00|0.00|0.00&Organization Settlement Account|KES|0.00|0.00|0.00|0.00"
+            .parse()
+            .unwrap();
+
+        assert_eq!(balances.len(), 5);
+        let working = balances.get("Working Account").unwrap();
+        assert_eq!(working.currency, "KES");
+        assert_eq!(working.total, decimal("700000.00"));
+        assert_eq!(working.available, decimal("700000.00"));
+        assert_eq!(working.reserved, Decimal::ZERO);
+        assert_eq!(working.uncleared, Decimal::ZERO);
+        let charges = balances.get("Charges Paid Account").unwrap();
+        assert_eq!(charges.total, decimal("-1540.00"));
+        assert_eq!(charges.available, decimal("-1540.00"));
+        assert!(balances.get("Savings Account").is_none());
+    }
+
+    #[test]
+    fn test_amounts_are_precise() {
+        let balances: AccountBalances = "Utility Account|KES|0.30|0.10|0.15|0.05".parse().unwrap();
+        let utility = balances.get("Utility Account").unwrap();
+        assert_eq!(utility.total, decimal("0.30"));
+        assert_eq!(utility.available, decimal("0.10"));
+        assert_eq!(utility.reserved, decimal("0.15"));
+        assert_eq!(utility.uncleared, decimal("0.05"));
+        // no floating point error creeps into arithmetic on the amounts
+        assert_eq!(utility.total - utility.available, decimal("0.20"));
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        let raw = "Working Account|KES|1000.00|1000.00|0.00|0.00&Utility Account|KES|500.50|500.50|0.00|0.00";
+        let balances: AccountBalances = raw.parse().unwrap();
+        assert_eq!(balances.to_string(), raw);
+    }
+
+    #[test]
+    fn test_invalid_account_balances_fail() {
+        assert!("".parse::<AccountBalances>().is_err());
+        assert!("Working Account|KES|1000.00".parse::<AccountBalances>().is_err());
+        assert!(
+            "Working Account|KES|1000.00|1000.00|0.00|0.00|0.00"
+                .parse::<AccountBalances>()
+                .is_err()
+        );
+        assert!(
+            "Working Account|KES|abc|1000.00|0.00|0.00"
+                .parse::<AccountBalances>()
+                .is_err()
+        );
+        assert!("|KES|1000.00|1000.00|0.00|0.00".parse::<AccountBalances>().is_err());
+
+        let err = "Working Account|KES|1000.00|1000.00|0.00|0.00&Utility Account|KES|1,000|0.00|0.00|0.00"
+            .parse::<AccountBalances>()
+            .unwrap_err();
+        assert!(matches!(err, MpesaError::InvalidAccountBalance(_)));
+    }
+
+    #[test]
+    fn test_account_balance_result() {
+        let body = r#"{
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": "16917-22577599-3",
+                "ConversationID": "AG_20200206_00005e091a8ec6b9eac5",
+                "TransactionID": "OA90000000",
+                "ResultParameters": {
+                    "ResultParameter": [
+                        {
+                            "Key": "AccountBalance",
+                            "Value": "Working Account|KES|700000.00|700000.00|0.00|0.00&Utility Account|KES|228037.00|228037.00|0.00|0.00"
+                        },
+                        { "Key": "BOCompletedTime", "Value": 20200109125710 }
+                    ]
+                },
+                "ReferenceData": {
+                    "ReferenceItem": {
+                        "Key": "QueueTimeoutURL",
+                        "Value": "https://internalsandbox.safaricom.co.ke/mpesa/abresults/v1/submit"
+                    }
+                }
+            }
+        }"#;
+
+        let result = ResultCallback::from_json(body).unwrap().account_balance().unwrap();
+        let balances = result.account_balance.unwrap();
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances.get("Utility Account").unwrap().available, decimal("228037.00"));
+        assert_eq!(
+            result.bo_completed_time,
+            NaiveDate::from_ymd_opt(2020, 1, 9).and_then(|d| d.and_hms_opt(12, 57, 10))
+        );
+    }
+
+    #[test]
+    fn test_account_balance_result_with_malformed_balance_fails() {
+        let body = r#"{
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": "16917-22577599-3",
+                "ConversationID": "AG_20200206_00005e091a8ec6b9eac5",
+                "ResultParameters": {
+                    "ResultParameter": { "Key": "AccountBalance", "Value": "Working Account|KES" }
+                }
+            }
+        }"#;
+
+        let result = ResultCallback::from_json(body).unwrap();
+        assert!(result.account_balance().is_err());
+    }
+}
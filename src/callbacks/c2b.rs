@@ -0,0 +1,294 @@
+use std::fmt::{self, Display, Formatter, Result as FmtResult};
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize, Serializer};
+
+use super::deserialize_decimal;
+use crate::errors::MpesaResult;
+use crate::redact;
+
+/// Format of the `TransTime` field, e.g `20191122063845`
+const TRANS_TIME_FORMAT: &str = "%Y%m%d%H%M%S";
+
+/// A C2B payment posted by M-Pesa to the `ValidationURL` and `ConfirmationURL`
+/// registered through the C2B Register API
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct C2bTransaction {
+    /// The transaction type, e.g `Pay Bill` or `Buy Goods`
+    #[serde(default)]
+    pub transaction_type: String,
+    /// The unique M-Pesa transaction ID
+    #[serde(rename = "TransID")]
+    pub trans_id: String,
+    /// Timestamp of the transaction in the format `YYYYMMDDHHmmss`
+    pub trans_time: String,
+    /// The amount transacted
+    #[serde(deserialize_with = "deserialize_decimal")]
+    pub trans_amount: Decimal,
+    /// The shortcode receiving the payment
+    pub business_short_code: String,
+    /// The account number entered by the customer, only sent for paybill payments
+    #[serde(default)]
+    pub bill_ref_number: String,
+    #[serde(default)]
+    pub invoice_number: String,
+    /// The new balance of the shortcode, only sent to the `ConfirmationURL`
+    #[serde(default)]
+    pub org_account_balance: String,
+    /// Transaction ID that the partner can send back in the validation response
+    #[serde(rename = "ThirdPartyTransID", default)]
+    pub third_party_trans_id: String,
+    /// The (masked) phone number of the customer making the payment
+    #[serde(rename = "MSISDN", default)]
+    pub msisdn: String,
+    #[serde(default)]
+    pub first_name: String,
+    #[serde(default)]
+    pub middle_name: String,
+    #[serde(default)]
+    pub last_name: String,
+}
+
+impl fmt::Debug for C2bTransaction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("C2bTransaction")
+            .field("transaction_type", &self.transaction_type)
+            .field("trans_id", &self.trans_id)
+            .field("trans_time", &self.trans_time)
+            .field("trans_amount", &self.trans_amount)
+            .field("business_short_code", &self.business_short_code)
+            .field("bill_ref_number", &self.bill_ref_number)
+            .field("invoice_number", &self.invoice_number)
+            .field("org_account_balance", &self.org_account_balance)
+            .field("third_party_trans_id", &self.third_party_trans_id)
+            .field("msisdn", &redact::Msisdn(&self.msisdn))
+            .field("first_name", &redact::Secret)
+            .field("middle_name", &redact::Secret)
+            .field("last_name", &redact::Secret)
+            .finish()
+    }
+}
+
+/// The payment M-Pesa asks you to accept or reject on the `ValidationURL`
+pub type C2bValidationRequest = C2bTransaction;
+
+/// The completed payment M-Pesa notifies you of on the `ConfirmationURL`
+pub type C2bConfirmationRequest = C2bTransaction;
+
+impl C2bTransaction {
+    /// Parses the raw JSON body posted to the `ValidationURL` or `ConfirmationURL`
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::ParseError` if the body is not a valid C2B request
+    pub fn from_json(body: &str) -> MpesaResult<C2bTransaction> {
+        Ok(serde_json::from_str(body)?)
+    }
+
+    /// The parsed `TransTime`, if valid
+    pub fn transaction_time(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(self.trans_time.trim(), TRANS_TIME_FORMAT).ok()
+    }
+}
+
+/// Reasons M-Pesa accepts for rejecting a C2B payment on validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum C2bRejectionCode {
+    /// `C2B00011`
+    InvalidMsisdn,
+    /// `C2B00012`
+    InvalidAccountNumber,
+    /// `C2B00013`
+    InvalidAmount,
+    /// `C2B00014`
+    InvalidKycDetails,
+    /// `C2B00015`
+    InvalidShortcode,
+    /// `C2B00016`
+    OtherError,
+}
+
+impl C2bRejectionCode {
+    /// The result code sent back to M-Pesa
+    pub fn code(&self) -> &'static str {
+        match self {
+            C2bRejectionCode::InvalidMsisdn => "C2B00011",
+            C2bRejectionCode::InvalidAccountNumber => "C2B00012",
+            C2bRejectionCode::InvalidAmount => "C2B00013",
+            C2bRejectionCode::InvalidKycDetails => "C2B00014",
+            C2bRejectionCode::InvalidShortcode => "C2B00015",
+            C2bRejectionCode::OtherError => "C2B00016",
+        }
+    }
+}
+
+impl Display for C2bRejectionCode {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// The reply to a C2B validation request.
+///
+/// Serializes to the exact body M-Pesa expects, e.g
+/// `{"ResultCode":"0","ResultDesc":"Accepted"}` or `{"ResultCode":"C2B00012","ResultDesc":"Rejected"}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum C2bValidationResponse {
+    /// Completes the payment
+    Accept,
+    /// Cancels the payment with the given reason
+    Reject(C2bRejectionCode),
+}
+
+impl C2bValidationResponse {
+    pub fn result_code(&self) -> &'static str {
+        match self {
+            C2bValidationResponse::Accept => "0",
+            C2bValidationResponse::Reject(code) => code.code(),
+        }
+    }
+
+    pub fn result_desc(&self) -> &'static str {
+        match self {
+            C2bValidationResponse::Accept => "Accepted",
+            C2bValidationResponse::Reject(_) => "Rejected",
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct C2bResponseBody<'a> {
+    result_code: &'a str,
+    result_desc: &'a str,
+}
+
+impl Serialize for C2bValidationResponse {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        C2bResponseBody {
+            result_code: self.result_code(),
+            result_desc: self.result_desc(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// The acknowledgement of a C2B confirmation request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct C2bConfirmationResponse;
+
+impl Serialize for C2bConfirmationResponse {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        C2bResponseBody {
+            result_code: "0",
+            result_desc: "Success",
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_c2b_request() {
+        let body = r#"{
+            "TransactionType": "Pay Bill",
+            "TransID": "RKTQDM7W6S",
+            "TransTime": "20191122063845",
+            "TransAmount": "10",
+            "BusinessShortCode": "600638",
+            "BillRefNumber": "invoice008",
+            "InvoiceNumber": "",
+            "OrgAccountBalance": "",
+            "ThirdPartyTransID": "",
+            "MSISDN": "25470****149",
+            "FirstName": "John",
+            "MiddleName": "",
+            "LastName": "Doe"
+        }"#;
+
+        let request = C2bValidationRequest::from_json(body).unwrap();
+        assert_eq!(request.transaction_type, "Pay Bill");
+        assert_eq!(request.trans_id, "RKTQDM7W6S");
+        assert_eq!(request.trans_amount, Decimal::from(10));
+        assert_eq!(request.business_short_code, "600638");
+        assert_eq!(request.bill_ref_number, "invoice008");
+        assert_eq!(request.msisdn, "25470****149");
+        assert_eq!(request.first_name, "John");
+        assert_eq!(
+            request.transaction_time(),
+            NaiveDate::from_ymd_opt(2019, 11, 22).and_then(|d| d.and_hms_opt(6, 38, 45))
+        );
+    }
+
+    #[test]
+    fn test_parse_c2b_request_with_missing_optional_fields() {
+        let body = r#"{
+            "TransID": "RKTQDM7W6S",
+            "TransTime": "20191122063845",
+            "TransAmount": 10.5,
+            "BusinessShortCode": "600638",
+            "OrgAccountBalance": "49197.00"
+        }"#;
+
+        let request = C2bConfirmationRequest::from_json(body).unwrap();
+        assert_eq!(request.trans_amount, Decimal::new(105, 1));
+        assert_eq!(request.org_account_balance, "49197.00");
+        assert!(request.msisdn.is_empty());
+    }
+
+    #[test]
+    fn test_debug_masks_customer_details() {
+        let body = r#"{
+            "TransactionType": "Pay Bill",
+            "TransID": "RKTQDM7W6S",
+            "TransTime": "20191122063845",
+            "TransAmount": "10",
+            "BusinessShortCode": "600638",
+            "BillRefNumber": "invoice008",
+            "OrgAccountBalance": "49197.00",
+            "MSISDN": "254708374149",
+            "FirstName": "John",
+            "LastName": "Doe"
+        }"#;
+
+        let debug = format!("{:?}", C2bConfirmationRequest::from_json(body).unwrap());
+        assert!(!debug.contains("254708374149"), "{debug}");
+        assert!(debug.contains("254******149"), "{debug}");
+        assert!(!debug.contains("John") && !debug.contains("Doe"), "{debug}");
+    }
+
+    #[test]
+    fn test_validation_response_serialization() {
+        assert_eq!(
+            serde_json::to_value(C2bValidationResponse::Accept).unwrap(),
+            json!({ "ResultCode": "0", "ResultDesc": "Accepted" })
+        );
+        assert_eq!(
+            serde_json::to_value(C2bValidationResponse::Reject(C2bRejectionCode::InvalidMsisdn)).unwrap(),
+            json!({ "ResultCode": "C2B00011", "ResultDesc": "Rejected" })
+        );
+        assert_eq!(
+            serde_json::to_value(C2bValidationResponse::Reject(C2bRejectionCode::InvalidAccountNumber)).unwrap(),
+            json!({ "ResultCode": "C2B00012", "ResultDesc": "Rejected" })
+        );
+        assert_eq!(
+            serde_json::to_value(C2bValidationResponse::Reject(C2bRejectionCode::InvalidAmount)).unwrap(),
+            json!({ "ResultCode": "C2B00013", "ResultDesc": "Rejected" })
+        );
+    }
+
+    #[test]
+    fn test_confirmation_response_serialization() {
+        assert_eq!(
+            serde_json::to_value(C2bConfirmationResponse).unwrap(),
+            json!({ "ResultCode": "0", "ResultDesc": "Success" })
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use std::fmt;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{value_as_datetime, value_as_decimal, value_as_string};
+use crate::constants::MpesaResponseCode;
+use crate::errors::MpesaResult;
+use crate::redact;
+use crate::services::StkPushOutcome;
+
+/// Format of the `TransactionDate` item, e.g `20191219102115`
+const TRANSACTION_DATE_FORMAT: &str = "%Y%m%d%H%M%S";
+
+/// The envelope posted by M-Pesa to the `CallBackURL` of an STK push request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StkCallbackPayload {
+    #[serde(rename = "Body")]
+    pub body: StkCallbackBody,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StkCallbackBody {
+    #[serde(rename = "stkCallback")]
+    pub stk_callback: StkCallback,
+}
+
+/// The result of an STK push request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StkCallback {
+    /// This is a global unique Identifier for any submitted payment request.
+    #[serde(rename = "MerchantRequestID")]
+    pub merchant_request_id: String,
+    /// This is a global unique identifier of the processed checkout transaction
+    /// request.
+    #[serde(rename = "CheckoutRequestID")]
+    pub checkout_request_id: String,
+    /// This is a numeric status code that indicates the status of the
+    /// transaction processing. 0 means successful processing and any other
+    /// code means an error occurred or the transaction failed.
+    pub result_code: MpesaResponseCode,
+    /// Result description is a message from the API that gives the status of
+    /// the request processing, usually maps to a specific `ResultCode` value.
+    pub result_desc: String,
+    /// Only sent for successful transactions, contains the transaction details
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_metadata: Option<CallbackMetadata>,
+}
+
+/// Transaction details sent as an array of name/ value pairs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CallbackMetadata {
+    #[serde(rename = "Item", default)]
+    pub items: Vec<CallbackItem>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CallbackItem {
+    #[serde(rename = "Name")]
+    pub name: String,
+    /// Some items, e.g `Balance`, are sent without a value
+    #[serde(rename = "Value", default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+impl fmt::Debug for CallbackItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackItem")
+            .field("name", &self.name)
+            .field(
+                "value",
+                &self.value.as_ref().map(|value| redact::pair_value(&self.name, value)),
+            )
+            .finish()
+    }
+}
+
+/// Typed view of the `CallbackMetadata` items of a successful STK push
+#[derive(Clone, Default, PartialEq)]
+pub struct StkCallbackMetadata {
+    /// The amount that was transacted
+    pub amount: Option<Decimal>,
+    /// The unique M-Pesa transaction ID for the payment request
+    pub mpesa_receipt_number: Option<String>,
+    /// The balance of the account for the shortcode used as partyB
+    pub balance: Option<Decimal>,
+    /// The date and time that the transaction completed M-Pesa processing
+    pub transaction_date: Option<NaiveDateTime>,
+    /// The number of the customer who made the payment
+    pub phone_number: Option<String>,
+}
+
+impl fmt::Debug for StkCallbackMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StkCallbackMetadata")
+            .field("amount", &self.amount)
+            .field("mpesa_receipt_number", &self.mpesa_receipt_number)
+            .field("balance", &self.balance)
+            .field("transaction_date", &self.transaction_date)
+            .field("phone_number", &redact::optional_msisdn(&self.phone_number))
+            .finish()
+    }
+}
+
+impl CallbackMetadata {
+    /// Gets the value of the item with the given `name`, if present
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.items
+            .iter()
+            .find(|item| item.name == name)
+            .and_then(|item| item.value.as_ref())
+    }
+}
+
+impl From<&CallbackMetadata> for StkCallbackMetadata {
+    fn from(metadata: &CallbackMetadata) -> Self {
+        StkCallbackMetadata {
+            amount: metadata.get("Amount").and_then(value_as_decimal),
+            mpesa_receipt_number: metadata.get("MpesaReceiptNumber").and_then(value_as_string),
+            balance: metadata.get("Balance").and_then(value_as_decimal),
+            transaction_date: metadata
+                .get("TransactionDate")
+                .and_then(|v| value_as_datetime(v, &[TRANSACTION_DATE_FORMAT])),
+            phone_number: metadata.get("PhoneNumber").and_then(value_as_string),
+        }
+    }
+}
+
+impl StkCallback {
+    /// Parses the raw JSON body posted to the `CallBackURL`
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::ParseError` if the body is not a valid STK push callback
+    pub fn from_json(body: &str) -> MpesaResult<StkCallback> {
+        let payload: StkCallbackPayload = serde_json::from_str(body)?;
+        Ok(payload.body.stk_callback)
+    }
+
+    /// Whether the customer completed the payment
+    pub fn is_success(&self) -> bool {
+        self.result_code.is_success()
+    }
+
+    /// The final state of the STK push
+    pub fn outcome(&self) -> StkPushOutcome {
+        StkPushOutcome::from_result(self.result_code, &self.result_desc)
+    }
+
+    /// Flattens the `CallbackMetadata` items into typed fields.
+    ///
+    /// All fields will be `None` if the transaction was not successful
+    pub fn metadata(&self) -> StkCallbackMetadata {
+        self.callback_metadata
+            .as_ref()
+            .map(StkCallbackMetadata::from)
+            .unwrap_or_default()
+    }
+}
+
+impl From<StkCallbackPayload> for StkCallback {
+    fn from(payload: StkCallbackPayload) -> Self {
+        payload.body.stk_callback
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_successful_callback() {
+        let body = r#"{
+            "Body": {
+                "stkCallback": {
+                    "MerchantRequestID": "29115-34620561-1",
+                    "CheckoutRequestID": "ws_CO_191220191020363925",
+                    "ResultCode": 0,
+                    "ResultDesc": "The service request is processed successfully.",
+                    "CallbackMetadata": {
+                        "Item": [
+                            { "Name": "Amount", "Value": 1.00 },
+                            { "Name": "MpesaReceiptNumber", "Value": "NLJ7RT61SV" },
+                            { "Name": "Balance" },
+                            { "Name": "TransactionDate", "Value": 20191219102115 },
+                            { "Name": "PhoneNumber", "Value": 254708374149 }
+                        ]
+                    }
+                }
+            }
+        }"#;
+
+        let callback = StkCallback::from_json(body).unwrap();
+        assert!(callback.is_success());
+        assert_eq!(callback.merchant_request_id, "29115-34620561-1");
+        assert_eq!(callback.checkout_request_id, "ws_CO_191220191020363925");
+
+        let metadata = callback.metadata();
+        assert_eq!(metadata.amount, Some(Decimal::ONE));
+        assert_eq!(metadata.mpesa_receipt_number.as_deref(), Some("NLJ7RT61SV"));
+        assert_eq!(metadata.balance, None);
+        assert_eq!(
+            metadata.transaction_date,
+            NaiveDate::from_ymd_opt(2019, 12, 19).and_then(|d| d.and_hms_opt(10, 21, 15))
+        );
+        assert_eq!(metadata.phone_number.as_deref(), Some("254708374149"));
+    }
+
+    #[test]
+    fn test_parse_cancelled_callback() {
+        let body = r#"{
+            "Body": {
+                "stkCallback": {
+                    "MerchantRequestID": "29115-34620561-1",
+                    "CheckoutRequestID": "ws_CO_191220191020363925",
+                    "ResultCode": 1032,
+                    "ResultDesc": "Request cancelled by user."
+                }
+            }
+        }"#;
+
+        let callback = StkCallback::from_json(body).unwrap();
+        assert!(!callback.is_success());
+        assert_eq!(callback.outcome(), StkPushOutcome::CancelledByUser);
+        assert_eq!(callback.result_code, MpesaResponseCode::RequestCancelledByUser);
+        assert!(callback.callback_metadata.is_none());
+        assert_eq!(callback.metadata(), StkCallbackMetadata::default());
+    }
+
+    #[test]
+    fn test_parse_callback_with_string_values() {
+        let body = r#"{
+            "Body": {
+                "stkCallback": {
+                    "MerchantRequestID": "29115-34620561-1",
+                    "CheckoutRequestID": "ws_CO_191220191020363925",
+                    "ResultCode": "0",
+                    "ResultDesc": "The service request is processed successfully.",
+                    "CallbackMetadata": {
+                        "Item": [
+                            { "Name": "Amount", "Value": "150.50" },
+                            { "Name": "TransactionDate", "Value": "20191219102115" },
+                            { "Name": "PhoneNumber", "Value": "254708374149" },
+                            { "Name": "SomethingNew", "Value": "ignored" }
+                        ]
+                    }
+                }
+            }
+        }"#;
+
+        let callback = StkCallback::from_json(body).unwrap();
+        assert!(callback.is_success());
+        let metadata = callback.metadata();
+        assert_eq!(metadata.amount, Some(Decimal::new(15050, 2)));
+        assert_eq!(metadata.mpesa_receipt_number, None);
+        assert!(metadata.transaction_date.is_some());
+        assert_eq!(metadata.phone_number.as_deref(), Some("254708374149"));
+    }
+
+    #[test]
+    fn test_debug_masks_phone_number() {
+        let body = r#"{
+            "Body": {
+                "stkCallback": {
+                    "MerchantRequestID": "29115-34620561-1",
+                    "CheckoutRequestID": "ws_CO_191220191020363925",
+                    "ResultCode": 0,
+                    "ResultDesc": "The service request is processed successfully.",
+                    "CallbackMetadata": {
+                        "Item": [
+                            { "Name": "Amount", "Value": 1.00 },
+                            { "Name": "PhoneNumber", "Value": 254708374149 }
+                        ]
+                    }
+                }
+            }
+        }"#;
+
+        let callback = StkCallback::from_json(body).unwrap();
+        for debug in [format!("{callback:?}"), format!("{:?}", callback.metadata())] {
+            assert!(!debug.contains("254708374149"), "{debug}");
+            assert!(debug.contains("254******149"), "{debug}");
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_callback_fails() {
+        assert!(StkCallback::from_json(r#"{"Body": {}}"#).is_err());
+        assert!(StkCallback::from_json("not json").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//!# MPESA Callbacks
+//!
+//! Typed models for the payloads Safaricom posts back to the callback, result and
+//! confirmation URLs that are supplied when sending requests through the
+//! [services](crate::services).
+//!
+//! Here are the currently supported callbacks:
+//! 1. [Mpesa Express/ STK Push](https://developer.safaricom.co.ke/APIs/MpesaExpressSimulate) callback
+//! 2. Results of the [Account Balance](https://developer.safaricom.co.ke/APIs/AccountBalance),
+//!    [B2C](https://developer.safaricom.co.ke/APIs/BusinessToCustomer),
+//!    [B2B](https://developer.safaricom.co.ke/APIs/BusinessPayBill),
+//!    [Transaction Reversal](https://developer.safaricom.co.ke/APIs/Reversal) and
+//!    [Transaction Status](https://developer.safaricom.co.ke/APIs/TransactionStatus) APIs
+//! 3. [C2B](https://developer.safaricom.co.ke/APIs/CustomerToBusinessRegisterURL) validation and confirmation
+//!    requests, along with the replies M-Pesa expects
+//!
+//! The [`CallbackRegistry`] of a client correlates callbacks with the requests that triggered them, which
+//! allows awaiting the final result of a request e.g with [`B2cBuilder::send_and_wait`](crate::services::B2cBuilder::send_and_wait).
+//!
+//! With the `callbacks_server` feature enabled, the [server] module provides an embedded HTTP
+//! server that decodes these callbacks and routes them to a [`server::CallbackHandler`].
+
+#[cfg(feature = "account_balance")]
+#[cfg_attr(docsrs, doc(cfg(feature = "account_balance")))]
+mod account_balance;
+#[cfg(feature = "c2b_register")]
+#[cfg_attr(docsrs, doc(cfg(feature = "c2b_register")))]
+mod c2b;
+#[cfg(feature = "express")]
+#[cfg_attr(docsrs, doc(cfg(feature = "express")))]
+mod express;
+mod registry;
+mod result;
+#[cfg(feature = "callbacks_server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "callbacks_server")))]
+pub mod server;
+
+#[cfg(feature = "account_balance")]
+pub use account_balance::{AccountBalanceEntry, AccountBalanceResult, AccountBalances};
+#[cfg(feature = "c2b_register")]
+pub use c2b::{
+    C2bConfirmationRequest, C2bConfirmationResponse, C2bRejectionCode, C2bTransaction, C2bValidationRequest,
+    C2bValidationResponse,
+};
+#[cfg(feature = "express")]
+pub use express::{
+    CallbackItem, CallbackMetadata, StkCallback, StkCallbackBody, StkCallbackMetadata, StkCallbackPayload,
+};
+pub use registry::CallbackRegistry;
+#[cfg(feature = "b2b")]
+pub use result::B2bResult;
+#[cfg(feature = "b2c")]
+pub use result::B2cResult;
+#[cfg(feature = "transaction_reversal")]
+pub use result::TransactionReversalResult;
+#[cfg(feature = "transaction_status")]
+pub use result::TransactionStatusResult;
+pub use result::{
+    ReferenceData, ReferenceItem, ResultCallback, ResultCallbackPayload, ResultParameter, ResultParameters,
+};
+
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+/// Reads a callback value as an exact decimal amount.
+///
+/// M-Pesa is not consistent with the types it sends, so numeric strings are accepted too.
+pub(crate) fn value_as_decimal(value: &Value) -> Option<Decimal> {
+    let value = value_as_string(value)?;
+    let value = value.trim();
+    Decimal::from_str(value)
+        .or_else(|_| Decimal::from_scientific(value))
+        .ok()
+}
+
+/// Reads a callback value as a string, stringifying numbers and booleans.
+pub(crate) fn value_as_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.to_owned()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Reads a callback value as a timestamp in any of the given `chrono` formats.
+pub(crate) fn value_as_datetime(value: &Value, formats: &[&str]) -> Option<NaiveDateTime> {
+    let value = value_as_string(value)?;
+    formats
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value.trim(), format).ok())
+}
+
+/// Deserializes an amount that M-Pesa sends either as a number or as a numeric string into an exact decimal
+#[cfg(feature = "c2b_register")]
+pub(crate) fn deserialize_decimal<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    value_as_decimal(&value).ok_or_else(|| serde::de::Error::custom(format!("invalid amount: {value}")))
+}
+
+/// Deserializes a list that M-Pesa sends as a single object when it only has one element
+pub(crate) fn deserialize_one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+
+    match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(item) => Ok(vec![item]),
+        OneOrMany::Many(items) => Ok(items),
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::oneshot;
+
+use super::ResultCallback;
+#[cfg(feature = "express")]
+use super::StkCallback;
+use crate::errors::{MpesaError, MpesaResult};
+
+/// How long a callback that arrived before anyone waited for it is kept around
+const BUFFER_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone)]
+pub(crate) enum Callback {
+    #[cfg(feature = "express")]
+    Stk(StkCallback),
+    Result(ResultCallback),
+}
+
+/// The kinds of callback a request can wait for
+pub(crate) trait FromCallback: Sized {
+    fn from_callback(callback: Callback) -> MpesaResult<Self>;
+}
+
+impl FromCallback for ResultCallback {
+    fn from_callback(callback: Callback) -> MpesaResult<Self> {
+        match callback {
+            Callback::Result(result) => Ok(result),
+            #[cfg(feature = "express")]
+            Callback::Stk(_) => Err(MpesaError::Message(
+                "expected a result callback but found an STK callback",
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "express")]
+impl FromCallback for StkCallback {
+    fn from_callback(callback: Callback) -> MpesaResult<Self> {
+        match callback {
+            Callback::Stk(callback) => Ok(callback),
+            Callback::Result(_) => Err(MpesaError::Message(
+                "expected an STK callback but found a result callback",
+            )),
+        }
+    }
+}
+
+impl Callback {
+    /// The ids the callback can be correlated with
+    fn keys(&self) -> Vec<&str> {
+        let keys = match self {
+            #[cfg(feature = "express")]
+            Callback::Stk(callback) => vec![callback.checkout_request_id.as_str()],
+            Callback::Result(result) => vec![
+                result.conversation_id.as_str(),
+                result.originator_conversation_id.as_str(),
+            ],
+        };
+        keys.into_iter().filter(|key| !key.is_empty()).collect()
+    }
+}
+
+#[derive(Debug)]
+enum Slot {
+    Waiting(oneshot::Sender<Callback>),
+    Arrived(Callback, Instant),
+}
+
+/// Correlates the callbacks M-Pesa posts to the result and callback URLs with the requests
+/// that triggered them.
+///
+/// Results are keyed by their `ConversationID` and `OriginatorConversationID`, and
+/// Mpesa Express/ STK Push callbacks by their `CheckoutRequestID`. A callback that arrives
+/// before anyone waits for it is buffered, so the order in which the acknowledgement and the
+/// callback arrive does not matter.
+///
+/// Every `Mpesa` client owns a registry, see [`Mpesa::callbacks`](crate::Mpesa::callbacks).
+/// The registry is fed by the [callbacks server](crate::callbacks::server) when the
+/// `callbacks_server` feature is enabled, or by calling [`CallbackRegistry::deliver_result`] and
+/// [`CallbackRegistry::deliver_stk`] from your own endpoints.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// use mpesa::Mpesa;
+/// use mpesa::callbacks::ResultCallback;
+///
+/// # async fn run(client: Mpesa, body: &str) -> mpesa::MpesaResult<()> {
+/// // On the result URL endpoint
+/// client.callbacks().deliver_result(ResultCallback::from_json(body)?);
+///
+/// // Elsewhere, with the `ConversationID` of the acknowledgement
+/// let result = client
+///     .callbacks()
+///     .wait_for_result("AG_20191219_00005797af5d7d75f652", Duration::from_secs(60))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CallbackRegistry {
+    slots: Arc<Mutex<HashMap<String, Slot>>>,
+}
+
+impl CallbackRegistry {
+    pub fn new() -> CallbackRegistry {
+        CallbackRegistry::default()
+    }
+
+    /// Hands the result of a B2C, B2B, Transaction Reversal, Transaction Status or
+    /// Account Balance request to whoever is waiting for it
+    pub fn deliver_result(&self, result: ResultCallback) {
+        self.deliver(Callback::Result(result))
+    }
+
+    /// Hands an Mpesa Express/ STK Push callback to whoever is waiting for it
+    #[cfg(feature = "express")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "express")))]
+    pub fn deliver_stk(&self, callback: StkCallback) {
+        self.deliver(Callback::Stk(callback))
+    }
+
+    /// Waits for the result with the given `ConversationID` or `OriginatorConversationID`
+    ///
+    /// Only one task can wait for a given id at a time.
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::CallbackTimeout` if the result does not arrive within `timeout`, or a
+    /// `MpesaError::AlreadyWaiting` if another task is already waiting for `id`
+    pub async fn wait_for_result(&self, id: &str, timeout: Duration) -> MpesaResult<ResultCallback> {
+        ResultCallback::from_callback(self.wait(id, timeout).await?)
+    }
+
+    /// Waits for the Mpesa Express/ STK Push callback with the given `CheckoutRequestID`
+    ///
+    /// Only one task can wait for a given id at a time.
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::CallbackTimeout` if the callback does not arrive within `timeout`, or a
+    /// `MpesaError::AlreadyWaiting` if another task is already waiting for `checkout_request_id`
+    #[cfg(feature = "express")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "express")))]
+    pub async fn wait_for_stk(&self, checkout_request_id: &str, timeout: Duration) -> MpesaResult<StkCallback> {
+        StkCallback::from_callback(self.wait(checkout_request_id, timeout).await?)
+    }
+
+    /// Sends a request and waits for the callback correlated with the id `correlation_id` reads from its
+    /// response, e.g the `ConversationID` of a B2C acknowledgement.
+    ///
+    /// This backs the `send_and_wait` methods of the request builders
+    pub(crate) async fn send_and_wait<R, C>(
+        &self,
+        request: impl Future<Output = MpesaResult<R>>,
+        correlation_id: impl FnOnce(&R) -> &str,
+        timeout: Duration,
+    ) -> MpesaResult<C>
+    where
+        C: FromCallback,
+    {
+        let response = request.await?;
+        C::from_callback(self.wait(correlation_id(&response), timeout).await?)
+    }
+
+    fn deliver(&self, callback: Callback) {
+        let mut slots = self.slots.lock().unwrap();
+        slots.retain(|_, slot| match slot {
+            Slot::Waiting(sender) => !sender.is_closed(),
+            Slot::Arrived(_, arrived_at) => arrived_at.elapsed() < BUFFER_TTL,
+        });
+
+        let keys: Vec<String> = callback.keys().into_iter().map(str::to_owned).collect();
+        let mut callback = callback;
+        for key in &keys {
+            if matches!(slots.get(key), Some(Slot::Waiting(_)))
+                && let Some(Slot::Waiting(sender)) = slots.remove(key)
+            {
+                match sender.send(callback) {
+                    Ok(()) => return,
+                    // The waiter went away in the meantime, keep the callback for the next one
+                    Err(returned) => callback = returned,
+                }
+            }
+        }
+
+        let now = Instant::now();
+        for key in keys {
+            slots.insert(key, Slot::Arrived(callback.clone(), now));
+        }
+    }
+
+    /// Waits for the callback with the given id, taking it from the buffer if it already arrived.
+    ///
+    /// A second waiter on an id fails with `MpesaError::AlreadyWaiting` instead of taking the callback from the
+    /// first one. A waiter that timed out or was dropped no longer counts.
+    async fn wait(&self, key: &str, timeout: Duration) -> MpesaResult<Callback> {
+        let mut receiver = {
+            let mut slots = self.slots.lock().unwrap();
+            if matches!(slots.get(key), Some(Slot::Arrived(..)))
+                && let Some(Slot::Arrived(callback, _)) = slots.remove(key)
+            {
+                for sibling in callback.keys() {
+                    if matches!(slots.get(sibling), Some(Slot::Arrived(..))) {
+                        slots.remove(sibling);
+                    }
+                }
+                return Ok(callback);
+            }
+
+            if let Some(Slot::Waiting(sender)) = slots.get(key)
+                && !sender.is_closed()
+            {
+                return Err(MpesaError::AlreadyWaiting(key.to_owned()));
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            slots.insert(key.to_owned(), Slot::Waiting(sender));
+            receiver
+        };
+
+        match tokio::time::timeout(timeout, &mut receiver).await {
+            Ok(Ok(callback)) => Ok(callback),
+            // Senders are only dropped without sending once their receiver is closed
+            Ok(Err(_)) => Err(MpesaError::CallbackTimeout(key.to_owned())),
+            Err(_) => {
+                receiver.close();
+                let mut slots = self.slots.lock().unwrap();
+                if let Some(Slot::Waiting(sender)) = slots.get(key)
+                    && sender.is_closed()
+                {
+                    slots.remove(key);
+                }
+                drop(slots);
+                // The callback may have been sent right as the timeout elapsed
+                receiver
+                    .try_recv()
+                    .map_err(|_| MpesaError::CallbackTimeout(key.to_owned()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(conversation_id: &str, originator_conversation_id: &str) -> ResultCallback {
+        ResultCallback::from_json(&format!(
+            r#"{{
+                "Result": {{
+                    "ResultType": 0,
+                    "ResultCode": 0,
+                    "ResultDesc": "The service request is processed successfully.",
+                    "OriginatorConversationID": "{originator_conversation_id}",
+                    "ConversationID": "{conversation_id}"
+                }}
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_waiter_receives_result_delivered_later() {
+        let registry = CallbackRegistry::new();
+        let delivering = registry.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            delivering.deliver_result(result("AG_1", "29115-1"));
+        });
+
+        let received = registry.wait_for_result("AG_1", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(received.originator_conversation_id, "29115-1");
+    }
+
+    #[tokio::test]
+    async fn test_early_result_is_buffered_under_both_ids() {
+        let registry = CallbackRegistry::new();
+        registry.deliver_result(result("AG_2", "29115-2"));
+
+        let received = registry
+            .wait_for_result("29115-2", Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert_eq!(received.conversation_id, "AG_2");
+
+        // Claiming the result under one id releases it under the other
+        assert!(matches!(
+            registry.wait_for_result("AG_2", Duration::from_millis(10)).await,
+            Err(MpesaError::CallbackTimeout(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_wait_times_out() {
+        let registry = CallbackRegistry::new();
+        let err = registry
+            .wait_for_result("AG_3", Duration::from_millis(10))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MpesaError::CallbackTimeout(id) if id == "AG_3"));
+        assert!(registry.slots.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_second_waiter_on_the_same_id_fails() {
+        let registry = CallbackRegistry::new();
+        let waiting = registry.clone();
+        let first = tokio::spawn(async move { waiting.wait_for_result("AG_4", Duration::from_secs(5)).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let err = registry
+            .wait_for_result("AG_4", Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MpesaError::AlreadyWaiting(id) if id == "AG_4"));
+
+        registry.deliver_result(result("AG_4", "29115-4"));
+        let received = first.await.unwrap().unwrap();
+        assert_eq!(received.originator_conversation_id, "29115-4");
+
+        // Once the first waiter is done, the id can be waited for again
+        let err = registry
+            .wait_for_result("AG_4", Duration::from_millis(10))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MpesaError::CallbackTimeout(id) if id == "AG_4"));
+    }
+
+    #[cfg(feature = "express")]
+    #[tokio::test]
+    async fn test_stk_callback_is_correlated_by_checkout_request_id() {
+        let registry = CallbackRegistry::new();
+        let callback = StkCallback::from_json(
+            r#"{
+                "Body": {
+                    "stkCallback": {
+                        "MerchantRequestID": "29115-34620561-1",
+                        "CheckoutRequestID": "ws_CO_191220191020363925",
+                        "ResultCode": 1032,
+                        "ResultDesc": "Request cancelled by user."
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        registry.deliver_stk(callback);
+
+        let received = registry
+            .wait_for_stk("ws_CO_191220191020363925", Duration::from_millis(10))
+            .await
+            .unwrap();
+        assert_eq!(received.result_code, crate::MpesaResponseCode::RequestCancelledByUser);
+    }
+}
@@ -0,0 +1,615 @@
+use std::fmt;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_aux::field_attributes::deserialize_number_from_string;
+use serde_json::Value;
+
+use super::{deserialize_one_or_many, value_as_datetime, value_as_decimal, value_as_string};
+use crate::constants::MpesaResponseCode;
+use crate::errors::MpesaResult;
+use crate::redact;
+
+/// Formats of the completion timestamps found in result parameters,
+/// e.g `19.12.2019 11:45:50` and `20191219125207`
+const RESULT_DATE_FORMATS: [&str; 2] = ["%d.%m.%Y %H:%M:%S", "%Y%m%d%H%M%S"];
+
+/// The envelope posted by M-Pesa to the `ResultURL` (and `QueueTimeOutURL`) of the
+/// B2C, B2B, Account Balance, Transaction Reversal and Transaction Status APIs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultCallbackPayload {
+    #[serde(rename = "Result")]
+    pub result: ResultCallback,
+}
+
+/// The asynchronous result of a request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ResultCallback {
+    /// Status code indicating whether the transaction was already sent to your listener.
+    /// Usually 0 for the first notification
+    #[serde(default, deserialize_with = "deserialize_number_from_string")]
+    pub result_type: i64,
+    /// Numeric status code indicating the status of the transaction processing.
+    /// 0 means success and any other code means an error occurred or the transaction failed.
+    pub result_code: MpesaResponseCode,
+    /// Message explaining the result of the transaction
+    pub result_desc: String,
+    /// The unique request ID returned by M-Pesa when the request was accepted.
+    /// Also returned as `OriginatorConversationID` in the acknowledgement response
+    #[serde(rename = "OriginatorConversationID")]
+    pub originator_conversation_id: String,
+    /// The unique request ID returned by M-Pesa in the acknowledgement response
+    #[serde(rename = "ConversationID")]
+    pub conversation_id: String,
+    /// The unique M-Pesa transaction ID, also sent to the customer by SMS
+    #[serde(rename = "TransactionID", default, skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<String>,
+    /// Additional details of the transaction, only sent for successful transactions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_parameters: Option<ResultParameters>,
+    /// Additional details echoed back from the original request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_data: Option<ReferenceData>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResultParameters {
+    /// M-Pesa sends a single object instead of an array when there is only one parameter
+    #[serde(rename = "ResultParameter", default, deserialize_with = "deserialize_one_or_many")]
+    pub result_parameter: Vec<ResultParameter>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReferenceData {
+    /// M-Pesa sends a single object instead of an array when there is only one item
+    #[serde(rename = "ReferenceItem", default, deserialize_with = "deserialize_one_or_many")]
+    pub reference_item: Vec<ReferenceItem>,
+}
+
+/// A key/ value pair in the `ResultParameters` or `ReferenceData`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ResultParameter {
+    #[serde(rename = "Key")]
+    pub key: String,
+    #[serde(rename = "Value", default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+impl fmt::Debug for ResultParameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResultParameter")
+            .field("key", &self.key)
+            .field(
+                "value",
+                &self.value.as_ref().map(|value| redact::pair_value(&self.key, value)),
+            )
+            .finish()
+    }
+}
+
+pub type ReferenceItem = ResultParameter;
+
+fn find<'a>(items: &'a [ResultParameter], key: &str) -> Option<&'a Value> {
+    items
+        .iter()
+        .find(|item| item.key == key)
+        .and_then(|item| item.value.as_ref())
+}
+
+impl ResultCallback {
+    /// Parses the raw JSON body posted to the `ResultURL` or `QueueTimeOutURL`
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::ParseError` if the body is not a valid result callback
+    pub fn from_json(body: &str) -> MpesaResult<ResultCallback> {
+        let payload: ResultCallbackPayload = serde_json::from_str(body)?;
+        Ok(payload.result)
+    }
+
+    /// Whether the transaction was processed successfully
+    pub fn is_success(&self) -> bool {
+        self.result_code.is_success()
+    }
+
+    /// Gets the value of the result parameter with the given `key`, if present
+    pub fn parameter(&self, key: &str) -> Option<&Value> {
+        self.result_parameters
+            .as_ref()
+            .and_then(|params| find(&params.result_parameter, key))
+    }
+
+    /// Gets the value of the reference item with the given `key`, if present
+    pub fn reference(&self, key: &str) -> Option<&Value> {
+        self.reference_data
+            .as_ref()
+            .and_then(|data| find(&data.reference_item, key))
+    }
+
+    /// Reads the result parameter with the given `key` as an exact decimal amount, accepting numeric strings
+    pub fn decimal_parameter(&self, key: &str) -> Option<Decimal> {
+        self.parameter(key).and_then(value_as_decimal)
+    }
+
+    /// Reads the result parameter with the given `key` as a string, stringifying numbers
+    pub fn string_parameter(&self, key: &str) -> Option<String> {
+        self.parameter(key).and_then(value_as_string)
+    }
+
+    /// Reads the result parameter with the given `key` as a timestamp.
+    ///
+    /// Both the `dd.mm.YYYY HH:MM:SS` and `YYYYmmddHHMMSS` formats used by M-Pesa are accepted
+    pub fn datetime_parameter(&self, key: &str) -> Option<NaiveDateTime> {
+        self.parameter(key)
+            .and_then(|value| value_as_datetime(value, &RESULT_DATE_FORMATS))
+    }
+
+    /// Typed view of the result parameters of a B2C transaction
+    #[cfg(feature = "b2c")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "b2c")))]
+    pub fn b2c(&self) -> B2cResult {
+        B2cResult::from(self)
+    }
+
+    /// Typed view of the result parameters of a B2B transaction
+    #[cfg(feature = "b2b")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "b2b")))]
+    pub fn b2b(&self) -> B2bResult {
+        B2bResult::from(self)
+    }
+
+    /// Typed view of the result parameters of a transaction reversal
+    #[cfg(feature = "transaction_reversal")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "transaction_reversal")))]
+    pub fn transaction_reversal(&self) -> TransactionReversalResult {
+        TransactionReversalResult::from(self)
+    }
+
+    /// Typed view of the result parameters of a transaction status query
+    #[cfg(feature = "transaction_status")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "transaction_status")))]
+    pub fn transaction_status(&self) -> TransactionStatusResult {
+        TransactionStatusResult::from(self)
+    }
+}
+
+impl From<ResultCallbackPayload> for ResultCallback {
+    fn from(payload: ResultCallbackPayload) -> Self {
+        payload.result
+    }
+}
+
+/// Result parameters of a B2C transaction
+#[cfg(feature = "b2c")]
+#[derive(Clone, Default, PartialEq)]
+pub struct B2cResult {
+    /// The amount that was transacted
+    pub transaction_amount: Option<Decimal>,
+    /// The unique M-Pesa transaction ID
+    pub transaction_receipt: Option<String>,
+    /// Whether the recipient is a registered M-Pesa customer (`Y` or `N`)
+    pub recipient_is_registered_customer: Option<bool>,
+    /// Available balance of the charges paid account
+    pub charges_paid_account_available_funds: Option<Decimal>,
+    /// The phone number and name of the customer who received the payment
+    pub receiver_party_public_name: Option<String>,
+    /// When the transaction completed on M-Pesa
+    pub transaction_completed_date_time: Option<NaiveDateTime>,
+    /// Available balance of the utility account
+    pub utility_account_available_funds: Option<Decimal>,
+    /// Available balance of the working account
+    pub working_account_available_funds: Option<Decimal>,
+}
+
+#[cfg(feature = "b2c")]
+impl fmt::Debug for B2cResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("B2cResult")
+            .field("transaction_amount", &self.transaction_amount)
+            .field("transaction_receipt", &self.transaction_receipt)
+            .field(
+                "recipient_is_registered_customer",
+                &self.recipient_is_registered_customer,
+            )
+            .field(
+                "charges_paid_account_available_funds",
+                &self.charges_paid_account_available_funds,
+            )
+            .field(
+                "receiver_party_public_name",
+                &redact::secret(&self.receiver_party_public_name),
+            )
+            .field("transaction_completed_date_time", &self.transaction_completed_date_time)
+            .field("utility_account_available_funds", &self.utility_account_available_funds)
+            .field("working_account_available_funds", &self.working_account_available_funds)
+            .finish()
+    }
+}
+
+#[cfg(feature = "b2c")]
+impl From<&ResultCallback> for B2cResult {
+    fn from(result: &ResultCallback) -> Self {
+        B2cResult {
+            transaction_amount: result.decimal_parameter("TransactionAmount"),
+            transaction_receipt: result.string_parameter("TransactionReceipt"),
+            recipient_is_registered_customer: result
+                .string_parameter("B2CRecipientIsRegisteredCustomer")
+                .map(|v| v.eq_ignore_ascii_case("Y")),
+            charges_paid_account_available_funds: result.decimal_parameter("B2CChargesPaidAccountAvailableFunds"),
+            receiver_party_public_name: result.string_parameter("ReceiverPartyPublicName"),
+            transaction_completed_date_time: result.datetime_parameter("TransactionCompletedDateTime"),
+            utility_account_available_funds: result.decimal_parameter("B2CUtilityAccountAvailableFunds"),
+            working_account_available_funds: result.decimal_parameter("B2CWorkingAccountAvailableFunds"),
+        }
+    }
+}
+
+/// Result parameters of a B2B transaction
+#[cfg(feature = "b2b")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct B2bResult {
+    /// The amount that was transacted
+    pub amount: Option<Decimal>,
+    /// Balance of the debited account, as an encoded string
+    pub debit_account_balance: Option<String>,
+    /// Balance of the affected debit party account, as an encoded string
+    pub debit_party_affected_account_balance: Option<String>,
+    /// When the transaction completed on M-Pesa
+    pub trans_completed_time: Option<NaiveDateTime>,
+    /// Charges paid by the debit party
+    pub debit_party_charges: Option<String>,
+    /// The shortcode and name of the organization that received the payment
+    pub receiver_party_public_name: Option<String>,
+    /// The currency of the transaction
+    pub currency: Option<String>,
+    /// Balance of the initiator account, as an encoded string
+    pub initiator_account_current_balance: Option<String>,
+}
+
+#[cfg(feature = "b2b")]
+impl From<&ResultCallback> for B2bResult {
+    fn from(result: &ResultCallback) -> Self {
+        B2bResult {
+            amount: result.decimal_parameter("Amount"),
+            debit_account_balance: result.string_parameter("DebitAccountBalance"),
+            debit_party_affected_account_balance: result.string_parameter("DebitPartyAffectedAccountBalance"),
+            trans_completed_time: result.datetime_parameter("TransCompletedTime"),
+            debit_party_charges: result.string_parameter("DebitPartyCharges"),
+            receiver_party_public_name: result.string_parameter("ReceiverPartyPublicName"),
+            currency: result.string_parameter("Currency"),
+            initiator_account_current_balance: result.string_parameter("InitiatorAccountCurrentBalance"),
+        }
+    }
+}
+
+/// Result parameters of a transaction reversal
+#[cfg(feature = "transaction_reversal")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransactionReversalResult {
+    /// The amount that was reversed
+    pub amount: Option<Decimal>,
+    /// Balance of the debited account, as an encoded string
+    pub debit_account_balance: Option<String>,
+    /// When the reversal completed on M-Pesa
+    pub trans_completed_time: Option<NaiveDateTime>,
+    /// The M-Pesa transaction ID of the transaction that was reversed
+    pub original_transaction_id: Option<String>,
+    /// Charges applied to the reversal
+    pub charge: Option<Decimal>,
+    /// The name of the party that was credited
+    pub credit_party_public_name: Option<String>,
+    /// The name of the party that was debited
+    pub debit_party_public_name: Option<String>,
+}
+
+#[cfg(feature = "transaction_reversal")]
+impl From<&ResultCallback> for TransactionReversalResult {
+    fn from(result: &ResultCallback) -> Self {
+        TransactionReversalResult {
+            amount: result.decimal_parameter("Amount"),
+            debit_account_balance: result.string_parameter("DebitAccountBalance"),
+            trans_completed_time: result.datetime_parameter("TransCompletedTime"),
+            original_transaction_id: result.string_parameter("OriginalTransactionID"),
+            charge: result.decimal_parameter("Charge"),
+            credit_party_public_name: result.string_parameter("CreditPartyPublicName"),
+            debit_party_public_name: result.string_parameter("DebitPartyPublicName"),
+        }
+    }
+}
+
+/// Result parameters of a transaction status query
+#[cfg(feature = "transaction_status")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransactionStatusResult {
+    /// The M-Pesa transaction ID of the queried transaction
+    pub receipt_no: Option<String>,
+    /// The amount that was transacted
+    pub amount: Option<Decimal>,
+    /// The status of the transaction, e.g `Completed`
+    pub transaction_status: Option<String>,
+    /// The reason for the transaction
+    pub transaction_reason: Option<String>,
+    /// The type of the transaction, e.g `Pay Utility via API`
+    pub reason_type: Option<String>,
+    /// When the transaction was initiated
+    pub initiated_time: Option<NaiveDateTime>,
+    /// When the transaction completed on M-Pesa
+    pub finalised_time: Option<NaiveDateTime>,
+    /// The shortcode and name of the party that was debited
+    pub debit_party_name: Option<String>,
+    /// The shortcode and name of the party that was credited
+    pub credit_party_name: Option<String>,
+    /// The type of account that was debited
+    pub debit_account_type: Option<String>,
+    /// Charges paid by the debit party
+    pub debit_party_charges: Option<String>,
+    /// The conversation ID of the queried transaction
+    pub conversation_id: Option<String>,
+    /// The originator conversation ID of the queried transaction
+    pub originator_conversation_id: Option<String>,
+}
+
+#[cfg(feature = "transaction_status")]
+impl From<&ResultCallback> for TransactionStatusResult {
+    fn from(result: &ResultCallback) -> Self {
+        TransactionStatusResult {
+            receipt_no: result.string_parameter("ReceiptNo"),
+            amount: result.decimal_parameter("Amount"),
+            transaction_status: result.string_parameter("TransactionStatus"),
+            transaction_reason: result.string_parameter("TransactionReason"),
+            reason_type: result.string_parameter("ReasonType"),
+            initiated_time: result.datetime_parameter("InitiatedTime"),
+            finalised_time: result.datetime_parameter("FinalisedTime"),
+            debit_party_name: result.string_parameter("DebitPartyName"),
+            credit_party_name: result.string_parameter("CreditPartyName"),
+            debit_account_type: result.string_parameter("DebitAccountType"),
+            debit_party_charges: result.string_parameter("DebitPartyCharges"),
+            conversation_id: result.string_parameter("ConversationID"),
+            originator_conversation_id: result.string_parameter("OriginatorConversationID"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn datetime(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(y, m, d).and_then(|date| date.and_hms_opt(h, min, s))
+    }
+
+    #[test]
+    fn test_parse_failed_result_without_parameters() {
+        let body = r#"{
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 2001,
+                "ResultDesc": "The initiator information is invalid.",
+                "OriginatorConversationID": "29112-34801843-1",
+                "ConversationID": "AG_20191219_00006c6fddb15123addf",
+                "TransactionID": "NLJ0000000",
+                "ReferenceData": {
+                    "ReferenceItem": {
+                        "Key": "QueueTimeoutURL",
+                        "Value": "https://internalsandbox.safaricom.co.ke/mpesa/b2cresults/v1/submit"
+                    }
+                }
+            }
+        }"#;
+
+        let result = ResultCallback::from_json(body).unwrap();
+        assert!(!result.is_success());
+        assert_eq!(result.result_code, MpesaResponseCode::InvalidInitiatorInformation);
+        assert_eq!(result.conversation_id, "AG_20191219_00006c6fddb15123addf");
+        assert!(result.result_parameters.is_none());
+        assert_eq!(
+            result.reference("QueueTimeoutURL").and_then(value_as_string).as_deref(),
+            Some("https://internalsandbox.safaricom.co.ke/mpesa/b2cresults/v1/submit")
+        );
+        assert!(result.parameter("TransactionAmount").is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid_result_fails() {
+        assert!(ResultCallback::from_json(r#"{"Result": {"ResultCode": 0}}"#).is_err());
+        assert!(ResultCallback::from_json("not json").is_err());
+    }
+
+    #[cfg(feature = "b2c")]
+    #[test]
+    fn test_parse_b2c_result() {
+        let body = r#"{
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": "10571-7910404-1",
+                "ConversationID": "AG_20191219_00004e48cf7e3533f581",
+                "TransactionID": "NLJ41HAY6Q",
+                "ResultParameters": {
+                    "ResultParameter": [
+                        { "Key": "TransactionAmount", "Value": 10 },
+                        { "Key": "TransactionReceipt", "Value": "NLJ41HAY6Q" },
+                        { "Key": "B2CRecipientIsRegisteredCustomer", "Value": "Y" },
+                        { "Key": "B2CChargesPaidAccountAvailableFunds", "Value": -4510.00 },
+                        { "Key": "ReceiverPartyPublicName", "Value": "254708374149 - John Doe" },
+                        { "Key": "TransactionCompletedDateTime", "Value": "19.12.2019 11:45:50" },
+                        { "Key": "B2CUtilityAccountAvailableFunds", "Value": 10116.00 },
+                        { "Key": "B2CWorkingAccountAvailableFunds", "Value": 900000.00 },
+                        { "Key": "SomeNewParameter", "Value": "ignored" }
+                    ]
+                },
+                "ReferenceData": {
+                    "ReferenceItem": {
+                        "Key": "QueueTimeoutURL",
+                        "Value": "https://internalsandbox.safaricom.co.ke/mpesa/b2cresults/v1/submit"
+                    }
+                }
+            }
+        }"#;
+
+        let result = ResultCallback::from_json(body).unwrap();
+        assert!(result.is_success());
+        assert_eq!(result.transaction_id.as_deref(), Some("NLJ41HAY6Q"));
+
+        let b2c = result.b2c();
+        assert_eq!(b2c.transaction_amount, Some(Decimal::from(10)));
+        assert_eq!(b2c.transaction_receipt.as_deref(), Some("NLJ41HAY6Q"));
+        assert_eq!(b2c.recipient_is_registered_customer, Some(true));
+        assert_eq!(b2c.charges_paid_account_available_funds, Some(Decimal::from(-4510)));
+        assert_eq!(
+            b2c.receiver_party_public_name.as_deref(),
+            Some("254708374149 - John Doe")
+        );
+        assert_eq!(b2c.transaction_completed_date_time, datetime(2019, 12, 19, 11, 45, 50));
+        assert_eq!(b2c.utility_account_available_funds, Some(Decimal::from(10116)));
+        assert_eq!(b2c.working_account_available_funds, Some(Decimal::from(900000)));
+
+        for debug in [format!("{result:?}"), format!("{b2c:?}")] {
+            assert!(
+                !debug.contains("254708374149") && !debug.contains("John Doe"),
+                "{debug}"
+            );
+        }
+    }
+
+    #[cfg(feature = "b2c")]
+    #[test]
+    fn test_b2c_view_tolerates_missing_parameters() {
+        let body = r#"{
+            "Result": {
+                "ResultType": "0",
+                "ResultCode": "0",
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": "10571-7910404-1",
+                "ConversationID": "AG_20191219_00004e48cf7e3533f581",
+                "ResultParameters": {
+                    "ResultParameter": { "Key": "TransactionAmount", "Value": "10.50" }
+                }
+            }
+        }"#;
+
+        let b2c = ResultCallback::from_json(body).unwrap().b2c();
+        assert_eq!(
+            b2c,
+            B2cResult {
+                transaction_amount: Some(Decimal::new(1050, 2)),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[cfg(feature = "b2b")]
+    #[test]
+    fn test_parse_b2b_result() {
+        let body = r#"{
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": "626f6ddf-ab37-4650-b882-b1de92ec9aa4",
+                "ConversationID": "12345677dfdf89099B3",
+                "TransactionID": "QKA81LK5CY",
+                "ResultParameters": {
+                    "ResultParameter": [
+                        { "Key": "DebitAccountBalance", "Value": "{Amount={CurrencyCode=KES, MinimumAmount=618683, BasicAmount=6186.83}}" },
+                        { "Key": "Amount", "Value": "190.00" },
+                        { "Key": "DebitPartyAffectedAccountBalance", "Value": "Working Account|KES|346568.83|6186.83|340382.00|0.00" },
+                        { "Key": "TransCompletedTime", "Value": "20221110110717" },
+                        { "Key": "DebitPartyCharges", "Value": "" },
+                        { "Key": "ReceiverPartyPublicName", "Value": "000000– Biller Companty" },
+                        { "Key": "Currency", "Value": "KES" },
+                        { "Key": "InitiatorAccountCurrentBalance", "Value": "{Amount={CurrencyCode=KES, MinimumAmount=618683, BasicAmount=6186.83}}" }
+                    ]
+                }
+            }
+        }"#;
+
+        let b2b = ResultCallback::from_json(body).unwrap().b2b();
+        assert_eq!(b2b.amount, Some(Decimal::from(190)));
+        assert_eq!(b2b.trans_completed_time, datetime(2022, 11, 10, 11, 7, 17));
+        assert_eq!(b2b.currency.as_deref(), Some("KES"));
+        assert_eq!(
+            b2b.debit_party_affected_account_balance.as_deref(),
+            Some("Working Account|KES|346568.83|6186.83|340382.00|0.00")
+        );
+    }
+
+    #[cfg(feature = "transaction_reversal")]
+    #[test]
+    fn test_parse_transaction_reversal_result() {
+        let body = r#"{
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": "8521-4298025-1",
+                "ConversationID": "AG_20181005_00004d7ee675c0c7ee0b",
+                "TransactionID": "MJ561H6X5O",
+                "ResultParameters": {
+                    "ResultParameter": [
+                        { "Key": "DebitAccountBalance", "Value": "Utility Account|KES|51661.00|51661.00|0.00|0.00" },
+                        { "Key": "Amount", "Value": 100 },
+                        { "Key": "TransCompletedTime", "Value": 20181005153225 },
+                        { "Key": "OriginalTransactionID", "Value": "MJ551H6X5D" },
+                        { "Key": "Charge", "Value": 0 },
+                        { "Key": "CreditPartyPublicName", "Value": "254708374149 - John Doe" },
+                        { "Key": "DebitPartyPublicName", "Value": "601315 - Safaricom1338" }
+                    ]
+                }
+            }
+        }"#;
+
+        let reversal = ResultCallback::from_json(body).unwrap().transaction_reversal();
+        assert_eq!(reversal.amount, Some(Decimal::from(100)));
+        assert_eq!(reversal.trans_completed_time, datetime(2018, 10, 5, 15, 32, 25));
+        assert_eq!(reversal.original_transaction_id.as_deref(), Some("MJ551H6X5D"));
+        assert_eq!(reversal.charge, Some(Decimal::ZERO));
+    }
+
+    #[cfg(feature = "transaction_status")]
+    #[test]
+    fn test_parse_transaction_status_result() {
+        let body = r#"{
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": "10816-694520-2",
+                "ConversationID": "AG_20200927_0000466c3b4ea3f1e2e4",
+                "TransactionID": "NLJ0000000",
+                "ResultParameters": {
+                    "ResultParameter": [
+                        { "Key": "DebitPartyName", "Value": "600310 - Safaricom333" },
+                        { "Key": "CreditPartyName", "Value": "254708374149 - John Doe" },
+                        { "Key": "OriginatorConversationID", "Value": "3211-416020-3" },
+                        { "Key": "InitiatedTime", "Value": 20200927184800 },
+                        { "Key": "DebitAccountType", "Value": "Utility Account" },
+                        { "Key": "DebitPartyCharges", "Value": "Fee For B2C Payment|KES|22.40" },
+                        { "Key": "TransactionReason" },
+                        { "Key": "ReasonType", "Value": "Business Payment to Customer via API" },
+                        { "Key": "TransactionStatus", "Value": "Completed" },
+                        { "Key": "FinalisedTime", "Value": 20200927184800 },
+                        { "Key": "Amount", "Value": 10 },
+                        { "Key": "ConversationID", "Value": "AG_20200927_00004ab8c2f0c8a9bd4f" },
+                        { "Key": "ReceiptNo", "Value": "NLJ41HAY6Q" }
+                    ]
+                }
+            }
+        }"#;
+
+        let status = ResultCallback::from_json(body).unwrap().transaction_status();
+        assert_eq!(status.receipt_no.as_deref(), Some("NLJ41HAY6Q"));
+        assert_eq!(status.amount, Some(Decimal::from(10)));
+        assert_eq!(status.transaction_status.as_deref(), Some("Completed"));
+        assert_eq!(status.transaction_reason, None);
+        assert_eq!(status.initiated_time, datetime(2020, 9, 27, 18, 48, 0));
+        assert_eq!(
+            status.conversation_id.as_deref(),
+            Some("AG_20200927_00004ab8c2f0c8a9bd4f")
+        );
+    }
+}
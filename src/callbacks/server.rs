@@ -0,0 +1,336 @@
+//! An embedded HTTP server that receives M-Pesa callbacks.
+//!
+//! Incoming callbacks are decoded into the typed models in [`crate::callbacks`] and routed to
+//! a user supplied [`CallbackHandler`]. The server replies with the acknowledgement body M-Pesa
+//! expects, so the handler only has to deal with the payload.
+//!
+//! The server listens on the following fixed paths, which should be appended to the public URL
+//! the server is reachable at when sending requests:
+//!
+//! | Callback                              | Path                                    |
+//! |---------------------------------------|-----------------------------------------|
+//! | Mpesa Express/ STK Push               | `/stk/callback`                         |
+//! | C2B validation                        | `/c2b/validation`                       |
+//! | C2B confirmation                      | `/c2b/confirmation`                     |
+//! | B2C result/ timeout                   | `/b2c/result`, `/b2c/timeout`           |
+//! | B2B result/ timeout                   | `/b2b/result`, `/b2b/timeout`           |
+//! | Transaction Reversal result/ timeout  | `/reversal/result`, `/reversal/timeout` |
+//! | Transaction Status result/ timeout    | `/status/result`, `/status/timeout`     |
+//! | Account Balance result/ timeout       | `/balance/result`, `/balance/timeout`   |
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use mpesa::callbacks::server::{CallbackHandler, CallbackServer, ResultKind, async_trait};
+//! use mpesa::callbacks::{ResultCallback, StkCallback};
+//!
+//! struct Handler;
+//!
+//! #[async_trait]
+//! impl CallbackHandler for Handler {
+//!     async fn on_stk_callback(&self, callback: StkCallback) {
+//!         println!("STK push {} completed: {}", callback.checkout_request_id, callback.result_desc);
+//!     }
+//!
+//!     async fn on_result(&self, kind: ResultKind, result: ResultCallback) {
+//!         println!("{kind:?} result: {}", result.result_desc);
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> mpesa::MpesaResult<()> {
+//!     let server = CallbackServer::bind("127.0.0.1:8080", Handler).await?;
+//!     server.run().await
+//! }
+//! ```
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+pub use async_trait::async_trait;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{CONTENT_TYPE, HeaderValue};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use serde_json::{Value, json};
+use tokio::net::{TcpListener, ToSocketAddrs};
+
+#[cfg(feature = "express")]
+use super::StkCallback;
+#[cfg(feature = "c2b_register")]
+use super::{C2bConfirmationRequest, C2bConfirmationResponse, C2bValidationRequest, C2bValidationResponse};
+use super::{CallbackRegistry, ResultCallback};
+use crate::errors::MpesaResult;
+use crate::redact;
+
+/// Path of the Mpesa Express/ STK Push callback
+pub const STK_CALLBACK_PATH: &str = "/stk/callback";
+/// Path of the C2B validation request
+pub const C2B_VALIDATION_PATH: &str = "/c2b/validation";
+/// Path of the C2B confirmation request
+pub const C2B_CONFIRMATION_PATH: &str = "/c2b/confirmation";
+
+/// Callbacks bodies are small, anything larger is not from M-Pesa
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// The APIs that post their result to a `ResultURL` and `QueueTimeOutURL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResultKind {
+    B2c,
+    B2b,
+    TransactionReversal,
+    TransactionStatus,
+    AccountBalance,
+}
+
+impl ResultKind {
+    const ALL: [ResultKind; 5] = [
+        ResultKind::B2c,
+        ResultKind::B2b,
+        ResultKind::TransactionReversal,
+        ResultKind::TransactionStatus,
+        ResultKind::AccountBalance,
+    ];
+
+    /// Path of the `ResultURL` of this API
+    pub fn result_path(&self) -> &'static str {
+        match self {
+            ResultKind::B2c => "/b2c/result",
+            ResultKind::B2b => "/b2b/result",
+            ResultKind::TransactionReversal => "/reversal/result",
+            ResultKind::TransactionStatus => "/status/result",
+            ResultKind::AccountBalance => "/balance/result",
+        }
+    }
+
+    /// Path of the `QueueTimeOutURL` of this API
+    pub fn timeout_path(&self) -> &'static str {
+        match self {
+            ResultKind::B2c => "/b2c/timeout",
+            ResultKind::B2b => "/b2b/timeout",
+            ResultKind::TransactionReversal => "/reversal/timeout",
+            ResultKind::TransactionStatus => "/status/timeout",
+            ResultKind::AccountBalance => "/balance/timeout",
+        }
+    }
+}
+
+/// Receives the callbacks decoded by the [`CallbackServer`].
+///
+/// Every method has a default implementation that accepts and drops the callback,
+/// so only the callbacks of interest need to be implemented.
+#[async_trait]
+pub trait CallbackHandler: Send + Sync + 'static {
+    /// Called with the result of an Mpesa Express/ STK Push request
+    #[cfg(feature = "express")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "express")))]
+    async fn on_stk_callback(&self, _callback: StkCallback) {}
+
+    /// Called when M-Pesa asks whether a C2B payment should be completed.
+    ///
+    /// Accepts every payment by default
+    #[cfg(feature = "c2b_register")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "c2b_register")))]
+    async fn on_c2b_validation(&self, _request: C2bValidationRequest) -> C2bValidationResponse {
+        C2bValidationResponse::Accept
+    }
+
+    /// Called when a C2B payment has been completed
+    #[cfg(feature = "c2b_register")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "c2b_register")))]
+    async fn on_c2b_confirmation(&self, _request: C2bConfirmationRequest) {}
+
+    /// Called with the result of a B2C, B2B, Transaction Reversal, Transaction Status
+    /// or Account Balance request
+    async fn on_result(&self, _kind: ResultKind, _result: ResultCallback) {}
+
+    /// Called when a request timed out in the M-Pesa queue.
+    ///
+    /// M-Pesa does not document the body of the timeout notification, so it is passed as is
+    async fn on_timeout(&self, _kind: ResultKind, _body: Value) {}
+}
+
+/// A handler that drops every callback, for servers that only feed a [`CallbackRegistry`]
+impl CallbackHandler for () {}
+
+/// An HTTP server that routes M-Pesa callbacks to a [`CallbackHandler`]
+#[derive(Debug)]
+pub struct CallbackServer<H> {
+    listener: TcpListener,
+    handler: Arc<H>,
+    registry: Option<CallbackRegistry>,
+}
+
+impl<H: CallbackHandler> CallbackServer<H> {
+    /// Binds the server to the given address, e.g `127.0.0.1:8080`.
+    ///
+    /// Binding to port `0` picks a free port, which can be read from [`CallbackServer::local_addr`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::CallbackServerError` if the address can not be bound
+    pub async fn bind(addr: impl ToSocketAddrs, handler: H) -> MpesaResult<CallbackServer<H>> {
+        let listener = TcpListener::bind(addr).await?;
+        Ok(CallbackServer {
+            listener,
+            handler: Arc::new(handler),
+            registry: None,
+        })
+    }
+
+    /// Feeds every STK callback and result received to the given registry, before it is handed to
+    /// the handler.
+    ///
+    /// Pass the registry of a client, see [`Mpesa::callbacks`](crate::Mpesa::callbacks), so that
+    /// `send_and_wait` on that client resolves with the callbacks received by this server
+    pub fn with_registry(mut self, registry: CallbackRegistry) -> CallbackServer<H> {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// The address the server is listening on
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::CallbackServerError` if the address can not be read from the socket
+    pub fn local_addr(&self) -> MpesaResult<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Serves callbacks until the listener fails
+    pub async fn run(self) -> MpesaResult<()> {
+        self.run_until(std::future::pending()).await
+    }
+
+    /// Serves callbacks until the `shutdown` future completes.
+    ///
+    /// Connections that are already open are served to completion in the background
+    pub async fn run_until(self, shutdown: impl Future<Output = ()>) -> MpesaResult<()> {
+        tokio::pin!(shutdown);
+
+        loop {
+            let (stream, _) = tokio::select! {
+                _ = &mut shutdown => return Ok(()),
+                accepted = self.listener.accept() => accepted?,
+            };
+
+            let handler = Arc::clone(&self.handler);
+            let registry = self.registry.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |request| {
+                    let handler = Arc::clone(&handler);
+                    let registry = registry.clone();
+                    async move { Ok::<_, Infallible>(route(handler.as_ref(), registry.as_ref(), request).await) }
+                });
+
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    log::error!("Error serving callback connection: {e}");
+                }
+            });
+        }
+    }
+}
+
+fn respond(status: StatusCode, body: &impl Serialize) -> Response<Full<Bytes>> {
+    let body = serde_json::to_vec(body).unwrap_or_default();
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+fn accepted() -> Response<Full<Bytes>> {
+    respond(StatusCode::OK, &json!({ "ResultCode": "0", "ResultDesc": "Accepted" }))
+}
+
+fn rejected(status: StatusCode, desc: &str) -> Response<Full<Bytes>> {
+    respond(status, &json!({ "ResultCode": "1", "ResultDesc": desc }))
+}
+
+async fn route<H: CallbackHandler>(
+    handler: &H,
+    registry: Option<&CallbackRegistry>,
+    request: Request<Incoming>,
+) -> Response<Full<Bytes>> {
+    if request.method() != Method::POST {
+        return rejected(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed");
+    }
+
+    let path = request.uri().path().trim_end_matches('/').to_owned();
+    let body = match Limited::new(request.into_body(), MAX_BODY_SIZE).collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(e) => {
+            log::error!("Error reading callback body on {path}: {e}");
+            return rejected(StatusCode::BAD_REQUEST, "Invalid body");
+        }
+    };
+    let body = String::from_utf8_lossy(&body);
+
+    macro_rules! parse {
+        ($parser:expr) => {
+            match $parser(&body) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    log::error!(
+                        "Error parsing callback on {path}: {}, body: {}",
+                        redact::text(&e.to_string()),
+                        redact::body(&body)
+                    );
+                    return rejected(StatusCode::BAD_REQUEST, "Invalid payload");
+                }
+            }
+        };
+    }
+
+    #[cfg(feature = "express")]
+    if path == STK_CALLBACK_PATH {
+        let callback = parse!(StkCallback::from_json);
+        if let Some(registry) = registry {
+            registry.deliver_stk(callback.clone());
+        }
+        handler.on_stk_callback(callback).await;
+        return accepted();
+    }
+
+    #[cfg(feature = "c2b_register")]
+    if path == C2B_VALIDATION_PATH {
+        let response = handler.on_c2b_validation(parse!(C2bValidationRequest::from_json)).await;
+        return respond(StatusCode::OK, &response);
+    }
+
+    #[cfg(feature = "c2b_register")]
+    if path == C2B_CONFIRMATION_PATH {
+        handler
+            .on_c2b_confirmation(parse!(C2bConfirmationRequest::from_json))
+            .await;
+        return respond(StatusCode::OK, &C2bConfirmationResponse);
+    }
+
+    for kind in ResultKind::ALL {
+        if path == kind.result_path() {
+            let result = parse!(ResultCallback::from_json);
+            if let Some(registry) = registry {
+                registry.deliver_result(result.clone());
+            }
+            handler.on_result(kind, result).await;
+            return accepted();
+        }
+
+        if path == kind.timeout_path() {
+            let body: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+            handler.on_timeout(kind, body).await;
+            return accepted();
+        }
+    }
+
+    rejected(StatusCode::NOT_FOUND, "Not found")
+}
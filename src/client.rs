@@ -1,14 +1,22 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use backoff::ExponentialBackoff;
 use reqwest::Client as HttpClient;
+use reqwest::header::HeaderMap;
 use secrecy::{ExposeSecret, SecretString};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 
+use crate::callbacks::CallbackRegistry;
 use crate::environment::ApiEnvironment;
 use crate::errors::BackoffMpesaResult;
+use crate::interceptor::{IncomingResponse, Interceptor, OutgoingRequest};
+use crate::metrics::{AUTH_ENDPOINT, MetricsRecorder, NoopMetricsRecorder};
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 #[cfg(feature = "account_balance")]
 use crate::services::AccountBalanceBuilder;
 #[cfg(feature = "b2b")]
@@ -32,10 +40,15 @@ use crate::services::{DynamicQR, DynamicQRBuilder};
 use crate::services::{MpesaExpress, MpesaExpressBuilder, MpesaExpressQuery, MpesaExpressQueryBuilder};
 #[cfg(feature = "transaction_reversal")]
 use crate::services::{TransactionReversal, TransactionReversalBuilder};
-use crate::{MpesaError, MpesaResult, ResponseError, auth};
+use crate::token_store::{AccessToken, InMemoryTokenStore, TokenStore};
+use crate::{ErrorCategory, MpesaError, MpesaResult, ResponseError, auth, redact};
 
 /// Source: [test credentials](https://developer.safaricom.co.ke/test_credentials)
 const DEFAULT_INITIATOR_PASSWORD: &str = "Safaricom999!*!";
+/// How long connecting to the API may take by default
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long before `auth_expiry` the access token is refreshed by default
+const DEFAULT_AUTH_REFRESH_MARGIN: Duration = Duration::from_secs(60);
 /// Get current package version from metadata
 const CARGO_PACKAGE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -51,17 +64,67 @@ pub(crate) fn encode_block(src: &[u8]) -> String {
     BASE64_STANDARD.encode(src)
 }
 
+/// The credential an initiator authenticates requests with
+#[derive(Clone)]
+pub(crate) enum InitiatorCredential {
+    /// Encrypted with the certificate of the environment for every request
+    Password(SecretString),
+    /// Encrypted beforehand, e.g on the Daraja portal, and sent as is
+    SecurityCredential(SecretString),
+}
+
+#[cfg(test)]
+impl InitiatorCredential {
+    pub(crate) fn expose_secret(&self) -> &str {
+        match self {
+            InitiatorCredential::Password(secret) | InitiatorCredential::SecurityCredential(secret) => {
+                secret.expose_secret()
+            }
+        }
+    }
+}
+
 /// Mpesa client that will facilitate communication with the Safaricom API
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct Mpesa {
     consumer_key: String,
     consumer_secret: SecretString,
-    initiator_password: Arc<RwLock<Option<SecretString>>>,
+    fallback_initiator_password: Arc<RwLock<Option<SecretString>>>,
+    initiators: Arc<RwLock<HashMap<String, InitiatorCredential>>>,
+    passkey: Arc<RwLock<Option<SecretString>>>,
     pub(crate) base_url: String,
     certificate: String,
+    is_production: bool,
     auth_token: Arc<RwLock<SecretString>>,
     auth_expiry: Arc<RwLock<i64>>,
+    auth_refresh_margin: Arc<RwLock<Duration>>,
+    /// Held while the access token is being refreshed so that only one refresh runs at a time
+    auth_refresh: Arc<tokio::sync::Mutex<()>>,
+    token_store: Arc<dyn TokenStore>,
+    retry_policy: Arc<RwLock<RetryPolicy>>,
+    interceptors: Arc<Vec<Arc<dyn Interceptor>>>,
+    metrics: Arc<dyn MetricsRecorder>,
     pub(crate) http_client: HttpClient,
+    callbacks: CallbackRegistry,
+}
+
+impl fmt::Debug for Mpesa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mpesa")
+            .field("consumer_key", &redact::Secret)
+            .field(
+                "initiators",
+                &self.initiators.read().unwrap().keys().collect::<Vec<_>>(),
+            )
+            .field("base_url", &self.base_url)
+            .field("auth_expiry", &self.auth_expiry())
+            .field("auth_refresh_margin", &self.auth_refresh_margin())
+            .field("token_store", &self.token_store)
+            .field("retry_policy", &self.retry_policy())
+            .field("interceptors", &self.interceptors)
+            .field("metrics", &self.metrics)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Mpesa {
@@ -88,36 +151,102 @@ impl Mpesa {
     /// # Panics
     /// This method can panic if a TLS backend cannot be initialized for the internal http_client
     pub fn new<S: Into<String>>(consumer_key: S, consumer_secret: S, environment: impl ApiEnvironment) -> Self {
-        let http_client = HttpClient::builder()
-            .connect_timeout(Duration::from_secs(10))
-            .user_agent(format!("httpie/{CARGO_PACKAGE_VERSION}"))
+        Self::with_token_store(consumer_key, consumer_secret, environment, InMemoryTokenStore::new())
+    }
+
+    /// Constructs a new `Mpesa` client that keeps its access tokens in the given [`TokenStore`].
+    ///
+    /// Clients that share a store e.g a [`FileTokenStore`](crate::FileTokenStore) on the same file, or a custom
+    /// store backed by Redis, reuse each other's tokens instead of each fetching their own.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use mpesa::{Environment, FileTokenStore, Mpesa};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     dotenvy::dotenv().ok();
+    ///
+    ///     let client = Mpesa::with_token_store(
+    ///         dotenvy::var("CONSUMER_KEY").unwrap(),
+    ///         dotenvy::var("CONSUMER_SECRET").unwrap(),
+    ///         Environment::Sandbox,
+    ///         FileTokenStore::new("/var/run/mpesa/tokens.json"),
+    ///     );
+    ///
+    ///     assert!(client.is_connected().await);
+    /// }
+    /// ```
+    /// # Panics
+    /// This method can panic if a TLS backend cannot be initialized for the internal http_client
+    pub fn with_token_store<S: Into<String>>(
+        consumer_key: S,
+        consumer_secret: S,
+        environment: impl ApiEnvironment,
+        token_store: impl TokenStore + 'static,
+    ) -> Self {
+        Self::builder(consumer_key, consumer_secret, environment)
+            .token_store(token_store)
             .build()
-            .expect("Error building http client");
+            .expect("Error building http client")
+    }
 
-        let base_url = environment.base_url().to_owned();
-        let certificate = environment.get_certificate().to_owned();
+    /// Creates a [`MpesaBuilder`] for configuring every aspect of the client, from the http client it uses to
+    /// its retry policy.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// use mpesa::{Environment, Mpesa, RetryPolicy};
+    ///
+    /// # fn main() -> mpesa::MpesaResult<()> {
+    /// let client = Mpesa::builder("consumer_key", "consumer_secret", Environment::Sandbox)
+    ///     .timeout(Duration::from_secs(30))
+    ///     .proxy(reqwest::Proxy::https("http://proxy.internal:3128")?)
+    ///     .retry_policy(RetryPolicy::none())
+    ///     .initiator("your_initiator_name", "your_initiator_password")
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder<S: Into<String>>(
+        consumer_key: S,
+        consumer_secret: S,
+        environment: impl ApiEnvironment,
+    ) -> MpesaBuilder {
+        MpesaBuilder::new(consumer_key, consumer_secret, environment)
+    }
 
-        Self {
-            consumer_key: consumer_key.into(),
-            consumer_secret: consumer_secret.into().into(),
-            initiator_password: Arc::new(RwLock::new(None)),
-            base_url,
-            certificate,
-            http_client,
-            auth_token: Arc::new(RwLock::new("".into())),
-            auth_expiry: Arc::new(RwLock::new(0)),
-        }
+    /// Whether the client makes requests against production, see [`ApiEnvironment::is_production`]
+    pub fn is_production(&self) -> bool {
+        self.is_production
     }
 
-    /// Gets the initiator password
-    /// If `None`, the default password is `"Safcom496!"`
-    pub(crate) fn initiator_password(&self) -> String {
-        self.initiator_password
-            .read()
-            .unwrap()
-            .as_ref()
-            .map(|password| password.expose_secret().into())
-            .unwrap_or(DEFAULT_INITIATOR_PASSWORD.to_owned())
+    /// Gets the credential of a request made as `initiator_name`: the credential registered for it, else the
+    /// fallback password. If neither is set, the default password of the sandbox test credentials is used in
+    /// sandbox
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::UnknownInitiator` if other initiators are registered but not `initiator_name`, and a
+    /// `MpesaError::MissingCredential` if no credential is set at all in production
+    pub(crate) fn initiator_credential(&self, initiator_name: &str) -> MpesaResult<InitiatorCredential> {
+        let initiators = self.initiators.read().unwrap();
+        if let Some(credential) = initiators.get(initiator_name) {
+            return Ok(credential.clone());
+        }
+        if let Some(password) = self.fallback_initiator_password.read().unwrap().as_ref() {
+            return Ok(InitiatorCredential::Password(password.clone()));
+        }
+        if !initiators.is_empty() {
+            Err(MpesaError::UnknownInitiator(initiator_name.to_owned()))
+        } else if self.is_production {
+            Err(MpesaError::MissingCredential("initiator password"))
+        } else {
+            Ok(InitiatorCredential::Password(DEFAULT_INITIATOR_PASSWORD.into()))
+        }
     }
 
     /// Get the consumer key
@@ -130,15 +259,59 @@ impl Mpesa {
         self.consumer_secret.expose_secret()
     }
 
-    /// Optional in development but required for production for the following apis:
+    /// Sets the fallback initiator password, see [`Mpesa::set_fallback_initiator_password`]
+    #[deprecated(
+        since = "3.4.1",
+        note = "use `set_initiator` for per-initiator credentials, or `set_fallback_initiator_password`"
+    )]
+    pub fn set_initiator_password<S: Into<String>>(&self, initiator_password: S) {
+        self.set_fallback_initiator_password(initiator_password);
+    }
+
+    /// Sets a password for requests made as any initiator without a credential registered with
+    /// [`Mpesa::set_initiator`] or [`Mpesa::set_initiator_security_credential`].
+    ///
+    /// Without a fallback, requests made as an unregistered initiator fail with a `MpesaError::UnknownInitiator`.
+    /// With it, they are signed with this password whatever the initiator name, including a misspelt one, so
+    /// prefer registering each initiator.
+    ///
+    /// In sandbox, when neither initiators nor a fallback are set, the default initiator password of the test
+    /// credentials is used
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use mpesa::{Environment, Mpesa};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     dotenvy::dotenv().ok();
+    ///
+    ///     let client = Mpesa::new(
+    ///         dotenvy::var("CONSUMER_KEY").unwrap(),
+    ///         dotenvy::var("CONSUMER_SECRET").unwrap(),
+    ///         Environment::Sandbox,
+    ///     );
+    ///     client.set_fallback_initiator_password("your_initiator_password");
+    ///     assert!(client.is_connected().await);
+    /// }
+    /// ```
+    pub fn set_fallback_initiator_password<S: Into<String>>(&self, initiator_password: S) {
+        *self.fallback_initiator_password.write().unwrap() = Some(initiator_password.into().into());
+    }
+
+    /// Registers the password of an initiator, replacing any credential registered for it.
+    ///
+    /// Required in production for the following apis, which are made as an initiator:
     /// - `account_balance`
     /// - `b2b`
     /// - `b2c`
     /// - `transaction_reversal`
     /// - `transaction_status`
     ///
-    /// You will need to call this method and set your production initiator password.
-    /// If in development, a default initiator password from the test credentials is already pre-set
+    /// The password is encrypted into the security credential of every request made as `initiator_name`.
+    /// Requests made as any other initiator fail with a `MpesaError::UnknownInitiator`, unless a fallback is set
+    /// with [`Mpesa::set_fallback_initiator_password`]
     ///
     /// # Example
     ///
@@ -154,12 +327,44 @@ impl Mpesa {
     ///         dotenvy::var("CONSUMER_SECRET").unwrap(),
     ///         Environment::Sandbox,
     ///     );
-    ///     client.set_initiator_password("your_initiator_password");
+    ///     client.set_initiator("payouts_api", "payouts_password");
+    ///     client.set_initiator_security_credential("reports_api", "EsJocK7+NjqZPC3I3EO+TbvS+xVb9TymWwaKABoaZr");
     ///     assert!(client.is_connected().await);
     /// }
     /// ```
-    pub fn set_initiator_password<S: Into<String>>(&self, initiator_password: S) {
-        *self.initiator_password.write().unwrap() = Some(initiator_password.into().into());
+    pub fn set_initiator<S: Into<String>>(&self, initiator_name: S, password: S) {
+        self.initiators.write().unwrap().insert(
+            initiator_name.into(),
+            InitiatorCredential::Password(password.into().into()),
+        );
+    }
+
+    /// Registers the security credential of an initiator, replacing any credential registered for it.
+    ///
+    /// The security credential is sent as is, for initiator passwords encrypted beforehand e.g on the Daraja
+    /// portal. See [`Mpesa::set_initiator`]
+    pub fn set_initiator_security_credential<S: Into<String>>(&self, initiator_name: S, security_credential: S) {
+        self.initiators.write().unwrap().insert(
+            initiator_name.into(),
+            InitiatorCredential::SecurityCredential(security_credential.into().into()),
+        );
+    }
+
+    /// Sets the Mpesa Express/ STK Push passkey used by requests that are not given one.
+    ///
+    /// Optional in development, where the passkey of the test credentials is used, but required in production
+    /// unless every request sets its own passkey
+    pub fn set_passkey<S: Into<String>>(&self, passkey: S) {
+        *self.passkey.write().unwrap() = Some(passkey.into().into());
+    }
+
+    /// Gets the passkey set with [`Mpesa::set_passkey`]
+    pub(crate) fn passkey(&self) -> Option<String> {
+        self.passkey
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|passkey| passkey.expose_secret().to_owned())
     }
 
     /// set auth token
@@ -178,9 +383,67 @@ impl Mpesa {
         *self.auth_expiry.read().unwrap()
     }
 
-    /// Check if we have a cached valid auth token
+    /// Sets the [`RetryPolicy`] for the requests sent by this client, including access token requests.
+    /// Defaults to `RetryPolicy::default()`
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        *self.retry_policy.write().unwrap() = policy;
+    }
+
+    /// get retry policy
+    pub(crate) fn retry_policy(&self) -> RetryPolicy {
+        *self.retry_policy.read().unwrap()
+    }
+
+    /// Sets how long before the access token expires it should be refreshed.
+    /// Defaults to 60 seconds
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// use mpesa::{Environment, Mpesa};
+    ///
+    /// let client = Mpesa::new("consumer_key", "consumer_secret", Environment::Sandbox);
+    /// client.set_auth_refresh_margin(Duration::from_secs(5 * 60));
+    /// ```
+    pub fn set_auth_refresh_margin(&self, margin: Duration) {
+        *self.auth_refresh_margin.write().unwrap() = margin;
+    }
+
+    /// get auth refresh margin
+    pub(crate) fn auth_refresh_margin(&self) -> Duration {
+        *self.auth_refresh_margin.read().unwrap()
+    }
+
+    /// Check if we have a cached auth token that is valid for longer than the refresh margin
     pub fn has_cached_auth(&self) -> bool {
-        chrono::Utc::now().timestamp() < self.auth_expiry() && !self.auth_token().is_empty()
+        AccessToken::new(self.auth_token(), self.auth_expiry()).is_valid_for(self.auth_refresh_margin())
+    }
+
+    /// Drops the cached access token if it is still `rejected`, so that the next call to `auth` fetches a new one.
+    /// A token that was already replaced by a concurrent caller is kept
+    pub(crate) async fn invalidate_auth(&self, rejected: &str) {
+        let _refresh = self.auth_refresh.lock().await;
+        if self.auth_token() != rejected {
+            return;
+        }
+        self.set_auth_token("", 0);
+        if let Err(e) = self.token_store.invalidate(&self.token_store_key()).await {
+            log::warn!("error invalidating the access token in the token store: {e}");
+        }
+    }
+
+    /// The key the access tokens of this client are stored under in its [`TokenStore`]
+    pub(crate) fn token_store_key(&self) -> String {
+        format!("{}|{}", self.base_url, self.consumer_key)
+    }
+
+    /// The registry that correlates callbacks with the requests sent by this client.
+    ///
+    /// Clones of the client share the same registry
+    pub fn callbacks(&self) -> &CallbackRegistry {
+        &self.callbacks
     }
 
     /// Checks if the client can be authenticated
@@ -196,16 +459,140 @@ impl Mpesa {
     ///
     /// Returns auth token as a `String`.
     ///
+    /// Only one refresh runs at a time across all clones of the client, concurrent callers wait
+    /// for it and reuse the token it fetched.
+    ///
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub(crate) async fn auth(&self) -> MpesaResult<String> {
         if self.has_cached_auth() {
             return Ok(self.auth_token());
         }
-        let res = backoff::future::retry(ExponentialBackoff::default(), || async { auth::auth(self).await }).await?;
+        let _refresh = self.auth_refresh.lock().await;
+        // Another caller may have refreshed the token while we waited for the lock
+        if self.has_cached_auth() {
+            return Ok(self.auth_token());
+        }
+
+        let key = self.token_store_key();
+        // A store failure only costs a token request, so it does not fail the request being authenticated
+        match self.token_store.get(&key).await {
+            Ok(Some(token)) if token.is_valid_for(self.auth_refresh_margin()) => {
+                self.set_auth_token(token.token.expose_secret(), token.expires_at);
+                return Ok(self.auth_token());
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("error reading the access token from the token store: {e}"),
+        }
+
+        let started_at = Instant::now();
+        let res = self
+            .retry_policy()
+            .retry(true, || auth::auth(self), || self.metrics.record_retry(AUTH_ENDPOINT))
+            .await;
+        self.metrics.record_auth_refresh(res.is_ok(), started_at.elapsed());
+        let res = res?;
+        if let Err(e) = self
+            .token_store
+            .put(&key, AccessToken::new(res.clone(), self.auth_expiry()))
+            .await
+        {
+            log::warn!("error writing the access token to the token store: {e}");
+        }
         Ok(res)
     }
 
+    /// Sends an owned request, such as a [`B2cRequest`](crate::services::B2cRequest) built ahead of time,
+    /// moved into a task or deserialized from a job queue.
+    ///
+    /// Values derived from the client, such as security credentials and the STK push password, are
+    /// computed when the request is sent.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use mpesa::services::C2bRegisterRequest;
+    /// use mpesa::{Environment, Mpesa, ResponseType};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> mpesa::MpesaResult<()> {
+    ///     let client = Mpesa::new("consumer_key", "consumer_secret", Environment::Sandbox);
+    ///
+    ///     let request = C2bRegisterRequest {
+    ///         short_code: "600496".to_owned(),
+    ///         response_type: ResponseType::Completed,
+    ///         confirmation_url: "https://example.com/confirmation".to_owned(),
+    ///         validation_url: "https://example.com/validation".to_owned(),
+    ///     };
+    ///
+    ///     let task = tokio::spawn(async move { client.execute(request).await });
+    ///     let response = task.await.unwrap()?;
+    ///     assert!(response.response_code.is_success());
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` on failure
+    pub async fn execute<R: MpesaRequest>(&self, request: R) -> MpesaResult<R::Response> {
+        self.call(&request).await
+    }
+
+    /// Sends a request to the endpoint of its [`MpesaRequest`] implementation.
+    ///
+    /// Every request of the crate, whether sent by a builder or with [`Mpesa::execute`], goes through this
+    /// method. It can also send requests to endpoints the crate does not cover yet, see [`MpesaRequest`].
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` on failure
+    pub async fn call<R: MpesaRequest>(&self, request: &R) -> MpesaResult<R::Response> {
+        self.call_with(request, None).await
+    }
+
+    /// Sends a request, overriding the retry policy of the client with `retry_policy`
+    pub(crate) async fn call_with<R: MpesaRequest>(
+        &self,
+        request: &R,
+        retry_policy: Option<RetryPolicy>,
+    ) -> MpesaResult<R::Response> {
+        let request = Request {
+            method: R::METHOD,
+            path: R::PATH,
+            body: request.payload(self)?,
+            idempotent: R::IDEMPOTENT,
+            retry_policy,
+            attempts: AtomicU32::new(0),
+        };
+
+        #[cfg(feature = "tracing")]
+        {
+            use tracing::Instrument;
+            use tracing::field::Empty;
+
+            let span = tracing::info_span!(
+                "mpesa.request",
+                endpoint = R::PATH,
+                method = %R::METHOD,
+                attempt = Empty,
+                http.status = Empty,
+                error_code = Empty,
+                error = Empty,
+                conversation_id = Empty,
+                checkout_request_id = Empty,
+                latency_ms = Empty,
+            );
+            let started_at = Instant::now();
+            let res = self.send(request).instrument(span.clone()).await;
+            span.record("latency_ms", started_at.elapsed().as_millis() as u64);
+            if let Err(e) = &res {
+                span.record("error", redact::text(&e.to_string()).as_ref());
+            }
+            res
+        }
+        #[cfg(not(feature = "tracing"))]
+        self.send(request).await
+    }
+
     #[cfg(feature = "b2c")]
     #[doc = include_str!("../docs/client/b2c.md")]
     #[cfg_attr(docsrs, doc(cfg(feature = "b2c")))]
@@ -320,14 +707,14 @@ impl Mpesa {
 
     cfg_if::cfg_if! {
         if #[cfg(feature = "openssl")] {
-            /// Generates security credentials
+            /// Encrypts an initiator password into a security credential.
             /// M-Pesa Core authenticates a transaction by decrypting the security credentials.
             /// Security credentials are generated by encrypting the base64 encoded initiator password with M-Pesa’s public key,
             /// a X509 certificate. Returns base64 encoded string.
             ///
             /// # Errors
             /// Returns `EncryptionError` variant of `MpesaError`
-            pub(crate) fn gen_security_credentials(&self) -> MpesaResult<String> {
+            fn encrypt_initiator_password(&self, password: &str) -> MpesaResult<String> {
                 use openssl::base64;
                 use openssl::rsa::Padding;
                 use openssl::x509::X509;
@@ -341,18 +728,18 @@ impl Mpesa {
                 let buf_len = pub_key.size();
                 let mut buffer = vec![0; buf_len];
 
-                rsa_key.public_encrypt(self.initiator_password().as_bytes(), &mut buffer, Padding::PKCS1)?;
+                rsa_key.public_encrypt(password.as_bytes(), &mut buffer, Padding::PKCS1)?;
                 Ok(base64::encode_block(&buffer))
             }
         } else if #[cfg(feature = "no_openssl")] {
-            /// Generates security credentials
+            /// Encrypts an initiator password into a security credential.
             /// M-Pesa Core authenticates a transaction by decrypting the security credentials.
             /// Security credentials are generated by encrypting the base64 encoded initiator password with M-Pesa’s public key,
             /// a X509 certificate. Returns base64 encoded string.
             ///
             /// # Errors
             /// Returns `EncryptionError` variant of `MpesaError`
-            pub(crate) fn gen_security_credentials(&self) -> MpesaResult<String> {
+            fn encrypt_initiator_password(&self, password: &str) -> MpesaResult<String> {
                 use rsa::pkcs8::DecodePublicKey; // required for RsaPublicKey::from_public_key_der
                 use rsa::{Pkcs1v15Encrypt, RsaPublicKey};
                 use x509_parser::pem::parse_x509_pem;
@@ -372,7 +759,7 @@ impl Mpesa {
 
                 let mut rng = rand::thread_rng();
                 let encrypted = public_key
-                    .encrypt(&mut rng, Pkcs1v15Encrypt, self.initiator_password().as_bytes())
+                    .encrypt(&mut rng, Pkcs1v15Encrypt, password.as_bytes())
                     .map_err(EncryptionErrors::RsaEncryption)?;
 
                 Ok(encode_block(&encrypted))
@@ -380,27 +767,301 @@ impl Mpesa {
         }
     }
 
+    /// Generates the security credential of a request made as `initiator_name`, see
+    /// [`Mpesa::initiator_credential`] for how its credential is picked
+    ///
+    /// # Errors
+    /// Returns the `MpesaError` of [`Mpesa::initiator_credential`], and an `EncryptionError` variant of
+    /// `MpesaError` if the password cannot be encrypted
+    #[cfg(any(feature = "openssl", feature = "no_openssl"))]
+    pub(crate) fn gen_security_credentials(&self, initiator_name: &str) -> MpesaResult<String> {
+        match self.initiator_credential(initiator_name)? {
+            InitiatorCredential::Password(password) => self.encrypt_initiator_password(password.expose_secret()),
+            InitiatorCredential::SecurityCredential(credential) => Ok(credential.expose_secret().to_owned()),
+        }
+    }
+
     /// Sends a request to the Safaricom API
-    /// This method is used by all the builders to send requests to the
-    /// Safaricom API
-    pub(crate) async fn send<Req, Res>(&self, req: Request<Req>) -> MpesaResult<Res>
+    ///
+    /// If the access token is rejected, it is dropped and the request is replayed once with a new token.
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::Unauthorized` if the new token is rejected too
+    async fn send<Req, Res>(&self, req: Request<Req>) -> MpesaResult<Res>
     where
         Req: Serialize + Send,
         Res: DeserializeOwned,
     {
         let auth = self.auth().await?;
-        let req = Arc::new(req);
-        let res = backoff::future::retry(ExponentialBackoff::default(), || async {
-            execute::<Req, Res>(self, &req.clone(), auth.clone()).await
+        match self.send_with_auth(&req, &auth).await {
+            Err(e) if e.is_auth_rejection() => {
+                log::debug!("access token rejected, re-authenticating: {e}");
+                self.invalidate_auth(&auth).await;
+                let auth = self.auth().await?;
+                self.send_with_auth(&req, &auth).await.map_err(|e| match e {
+                    MpesaError::Service(res) if e.is_auth_rejection() => MpesaError::Unauthorized(Some(res)),
+                    e => e,
+                })
+            }
+            res => res,
+        }
+    }
+
+    async fn send_with_auth<Req, Res>(&self, req: &Request<Req>, auth: &str) -> MpesaResult<Res>
+    where
+        Req: Serialize + Send,
+        Res: DeserializeOwned,
+    {
+        req.retry_policy
+            .unwrap_or_else(|| self.retry_policy())
+            .retry(
+                req.idempotent,
+                || execute::<Req, Res>(self, req, auth.to_owned()),
+                || self.metrics.record_retry(req.path),
+            )
+            .await
+    }
+}
+
+/// Builder for a [`Mpesa`] client, created with [`Mpesa::builder`]
+pub struct MpesaBuilder {
+    consumer_key: String,
+    consumer_secret: SecretString,
+    base_url: String,
+    certificate: String,
+    is_production: bool,
+    http_client: Option<HttpClient>,
+    timeout: Option<Duration>,
+    connect_timeout: Duration,
+    proxies: Vec<reqwest::Proxy>,
+    user_agent: String,
+    default_headers: HeaderMap,
+    token_store: Option<Arc<dyn TokenStore>>,
+    retry_policy: RetryPolicy,
+    fallback_initiator_password: Option<SecretString>,
+    initiators: HashMap<String, InitiatorCredential>,
+    passkey: Option<SecretString>,
+    auth_refresh_margin: Duration,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    metrics: Option<Arc<dyn MetricsRecorder>>,
+}
+
+impl fmt::Debug for MpesaBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpesaBuilder")
+            .field("consumer_key", &redact::Secret)
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("user_agent", &self.user_agent)
+            // Only the names of headers, their values may hold credentials
+            .field("default_headers", &self.default_headers.keys().collect::<Vec<_>>())
+            .field("token_store", &self.token_store)
+            .field("retry_policy", &self.retry_policy)
+            .field(
+                "fallback_initiator_password",
+                &redact::secret(&self.fallback_initiator_password),
+            )
+            .field("initiators", &self.initiators.keys().collect::<Vec<_>>())
+            .field("passkey", &redact::secret(&self.passkey))
+            .field("auth_refresh_margin", &self.auth_refresh_margin)
+            .field("interceptors", &self.interceptors)
+            .field("metrics", &self.metrics)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MpesaBuilder {
+    /// Creates a new `Mpesa` client builder
+    pub fn new<S: Into<String>>(consumer_key: S, consumer_secret: S, environment: impl ApiEnvironment) -> MpesaBuilder {
+        MpesaBuilder {
+            consumer_key: consumer_key.into(),
+            consumer_secret: consumer_secret.into().into(),
+            base_url: environment.base_url().to_owned(),
+            certificate: environment.get_certificate().to_owned(),
+            is_production: environment.is_production(),
+            http_client: None,
+            timeout: None,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            proxies: vec![],
+            user_agent: format!("httpie/{CARGO_PACKAGE_VERSION}"),
+            default_headers: HeaderMap::new(),
+            token_store: None,
+            retry_policy: RetryPolicy::default(),
+            fallback_initiator_password: None,
+            initiators: HashMap::new(),
+            passkey: None,
+            auth_refresh_margin: DEFAULT_AUTH_REFRESH_MARGIN,
+            interceptors: vec![],
+            metrics: None,
+        }
+    }
+
+    /// Uses an existing `reqwest::Client` to send requests.
+    ///
+    /// The timeouts, proxies, user agent and default headers of the builder are ignored in favour of the
+    /// configuration of the given client
+    pub fn http_client(mut self, http_client: HttpClient) -> MpesaBuilder {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Sets the timeout of every request, from connecting until the response body has been read.
+    /// There is no timeout by default
+    pub fn timeout(mut self, timeout: Duration) -> MpesaBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for connecting to the API. Defaults to 10 seconds
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> MpesaBuilder {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Adds a proxy to send requests through
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> MpesaBuilder {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Sets the `User-Agent` header of requests
+    pub fn user_agent<S: Into<String>>(mut self, user_agent: S) -> MpesaBuilder {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Sets headers that are sent with every request
+    pub fn default_headers(mut self, default_headers: HeaderMap) -> MpesaBuilder {
+        self.default_headers = default_headers;
+        self
+    }
+
+    /// Sets the [`TokenStore`] access tokens are kept in. Defaults to an [`InMemoryTokenStore`]
+    pub fn token_store(mut self, token_store: impl TokenStore + 'static) -> MpesaBuilder {
+        self.token_store = Some(Arc::new(token_store));
+        self
+    }
+
+    /// Sets the [`RetryPolicy`] of the client. Defaults to `RetryPolicy::default()`
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> MpesaBuilder {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the fallback initiator password, see [`Mpesa::set_fallback_initiator_password`]
+    #[deprecated(
+        since = "3.4.1",
+        note = "use `initiator` for per-initiator credentials, or `fallback_initiator_password`"
+    )]
+    pub fn initiator_password<S: Into<String>>(self, initiator_password: S) -> MpesaBuilder {
+        self.fallback_initiator_password(initiator_password)
+    }
+
+    /// Sets the fallback initiator password, see [`Mpesa::set_fallback_initiator_password`]
+    pub fn fallback_initiator_password<S: Into<String>>(mut self, initiator_password: S) -> MpesaBuilder {
+        self.fallback_initiator_password = Some(initiator_password.into().into());
+        self
+    }
+
+    /// Registers the password of an initiator, see [`Mpesa::set_initiator`]
+    pub fn initiator<S: Into<String>>(mut self, initiator_name: S, password: S) -> MpesaBuilder {
+        self.initiators.insert(
+            initiator_name.into(),
+            InitiatorCredential::Password(password.into().into()),
+        );
+        self
+    }
+
+    /// Registers the security credential of an initiator, see [`Mpesa::set_initiator_security_credential`]
+    pub fn initiator_security_credential<S: Into<String>>(
+        mut self,
+        initiator_name: S,
+        security_credential: S,
+    ) -> MpesaBuilder {
+        self.initiators.insert(
+            initiator_name.into(),
+            InitiatorCredential::SecurityCredential(security_credential.into().into()),
+        );
+        self
+    }
+
+    /// Sets the Mpesa Express/ STK Push passkey, see [`Mpesa::set_passkey`]
+    pub fn passkey<S: Into<String>>(mut self, passkey: S) -> MpesaBuilder {
+        self.passkey = Some(passkey.into().into());
+        self
+    }
+
+    /// Sets how long before the access token expires it should be refreshed, see
+    /// [`Mpesa::set_auth_refresh_margin`]
+    pub fn auth_refresh_margin(mut self, margin: Duration) -> MpesaBuilder {
+        self.auth_refresh_margin = margin;
+        self
+    }
+
+    /// Registers an [`Interceptor`] that is called around every request sent by the client.
+    /// Interceptors run in the order they are registered in
+    pub fn interceptor(mut self, interceptor: impl Interceptor + 'static) -> MpesaBuilder {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Sets the [`MetricsRecorder`] measurements of the requests sent by the client are reported to.
+    /// Nothing is recorded by default
+    pub fn metrics(mut self, metrics: impl MetricsRecorder + 'static) -> MpesaBuilder {
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+
+    /// Builds the `Mpesa` client
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::NetworkError` if the http client cannot be built e.g when a TLS backend cannot be
+    /// initialized
+    pub fn build(self) -> MpesaResult<Mpesa> {
+        let http_client = match self.http_client {
+            Some(http_client) => http_client,
+            None => {
+                let mut builder = HttpClient::builder()
+                    .connect_timeout(self.connect_timeout)
+                    .user_agent(self.user_agent)
+                    .default_headers(self.default_headers);
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                for proxy in self.proxies {
+                    builder = builder.proxy(proxy);
+                }
+                builder.build()?
+            }
+        };
+
+        Ok(Mpesa {
+            consumer_key: self.consumer_key,
+            consumer_secret: self.consumer_secret,
+            fallback_initiator_password: Arc::new(RwLock::new(self.fallback_initiator_password)),
+            initiators: Arc::new(RwLock::new(self.initiators)),
+            passkey: Arc::new(RwLock::new(self.passkey)),
+            base_url: self.base_url,
+            certificate: self.certificate,
+            is_production: self.is_production,
+            http_client,
+            auth_token: Arc::new(RwLock::new("".into())),
+            auth_expiry: Arc::new(RwLock::new(0)),
+            auth_refresh_margin: Arc::new(RwLock::new(self.auth_refresh_margin)),
+            auth_refresh: Arc::new(tokio::sync::Mutex::new(())),
+            token_store: self.token_store.unwrap_or_else(|| Arc::new(InMemoryTokenStore::new())),
+            retry_policy: Arc::new(RwLock::new(self.retry_policy)),
+            interceptors: Arc::new(self.interceptors),
+            metrics: self.metrics.unwrap_or_else(|| Arc::new(NoopMetricsRecorder)),
+            callbacks: CallbackRegistry::new(),
         })
-        .await?;
-        Ok(res)
     }
 }
 
 /// Sends a request to the Safaricom API
-/// The function has a retry policy with expoential backoff
-pub(crate) async fn execute<Req, Res>(client: &Mpesa, req: &Request<Req>, auth: String) -> BackoffMpesaResult<Res>
+/// Failures are classified as transient or permanent for the [`RetryPolicy`] of the request
+async fn execute<Req, Res>(client: &Mpesa, req: &Request<Req>, auth: String) -> BackoffMpesaResult<Res>
 where
     Req: Serialize + Send,
     Res: DeserializeOwned,
@@ -410,44 +1071,100 @@ where
     #[cfg(test)]
     let _ = env_logger::builder().try_init();
 
+    let attempt = req.attempts.fetch_add(1, Ordering::Relaxed) + 1;
+    log::debug!("sending {} /{} attempt {}", req.method, req.path, attempt);
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("attempt", attempt);
+
+    let body = serde_json::to_value(&req.body).map_err(MpesaError::from)?;
+    let mut outgoing = OutgoingRequest {
+        method: &req.method,
+        path: req.path,
+        body: &body,
+        headers: HeaderMap::from_iter([(
+            reqwest::header::ACCEPT,
+            reqwest::header::HeaderValue::from_static("application/json"),
+        )]),
+    };
+    for interceptor in client.interceptors.iter() {
+        interceptor
+            .before_send(&mut outgoing)
+            .await
+            .map_err(backoff::Error::permanent)?;
+    }
+
+    let started_at = Instant::now();
     let response = client
         .http_client
         .request(req.method.clone(), url)
         .bearer_auth(auth.clone())
-        .header(reqwest::header::ACCEPT, "application/json")
-        .json(&req.body)
+        .headers(outgoing.headers)
+        .json(&body)
         .send()
         .await
+        .inspect_err(|_| client.metrics.record_request(req.path, None, started_at.elapsed()))
         .map_err(MpesaError::from)
         .map_err(MpesaError::to_retryable)?;
 
-    if response.status().is_success() {
-        let text = response
-            .text()
-            .await
-            .map_err(MpesaError::from)
-            .map_err(MpesaError::to_retryable)?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let url = response.url().to_string();
+    let path = response.url().path().to_string();
+    let text = response
+        .text()
+        .await
+        .inspect_err(|_| {
+            client
+                .metrics
+                .record_request(req.path, Some(status.as_u16()), started_at.elapsed())
+        })
+        .map_err(MpesaError::from)
+        .map_err(MpesaError::to_retryable)?;
+    let elapsed = started_at.elapsed();
+    client.metrics.record_request(req.path, Some(status.as_u16()), elapsed);
+
+    #[cfg(feature = "tracing")]
+    record_response(attempt, status, &text, elapsed);
+
+    let incoming = IncomingResponse {
+        method: &req.method,
+        path: req.path,
+        status,
+        headers: &headers,
+        body: &text,
+        elapsed,
+    };
+    for interceptor in client.interceptors.iter() {
+        interceptor.after_receive(&incoming).await;
+    }
+
+    if status.is_success() {
         let body: Res = serde_json::from_str(&text)
-            .inspect_err(|e| log::error!("error decoding body err: {}: {}", e, text))
+            .inspect_err(|e| {
+                log::error!(
+                    "error decoding body err: {}: {}",
+                    redact::text(&e.to_string()),
+                    redact::body(&text)
+                )
+            })
             .map_err(MpesaError::from)
             .map_err(MpesaError::to_retryable)?;
         Ok(body)
     } else {
-        let status = response.status();
-        let is_content_type_html = response
-            .headers()
+        let is_content_type_html = headers
             .get(reqwest::header::CONTENT_TYPE)
             .map(|v| v.to_str().unwrap_or_default())
             .map(|s| s.contains("text/html"))
             .unwrap_or(false);
-        let url = response.url().to_string();
-        let path = response.url().path().to_string();
-        let text = response
-            .text()
-            .await
-            .map_err(MpesaError::from)
-            .map_err(MpesaError::to_retryable)?;
-        let body: ResponseError = serde_json::from_str(&text).map_err(|err| {
+        let parsed = serde_json::from_str::<ResponseError>(&text);
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || parsed
+                .as_ref()
+                .is_ok_and(|body| body.code().category() == ErrorCategory::Throttling)
+        {
+            client.metrics.record_throttled(req.path);
+        }
+        let body = parsed.map_err(|err| {
             if (is_content_type_html && status == reqwest::StatusCode::FORBIDDEN)
                 || status == reqwest::StatusCode::TOO_MANY_REQUESTS
                 || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
@@ -459,26 +1176,62 @@ where
                     is_content_type_html
                 );
                 MpesaError::to_retryable(MpesaError::TransientError)
+            } else if status == reqwest::StatusCode::UNAUTHORIZED {
+                log::debug!("Unauthorized url: {} status: {}", path, status);
+                MpesaError::to_retryable(MpesaError::Unauthorized(None))
             } else {
                 log::error!(
                     "error decoding body url: {} status: {} is html: {} err: {} : {}",
                     url,
                     status,
                     is_content_type_html,
-                    err,
-                    text
+                    redact::text(&err.to_string()),
+                    redact::body(&text)
                 );
                 MpesaError::to_retryable(MpesaError::from(err))
             }
         })?;
+        client.metrics.record_error(req.path, &body.error_code);
         Err(MpesaError::to_retryable(MpesaError::Service(body)))
     }
 }
 
-pub struct Request<Body: Serialize + Send> {
-    pub method: reqwest::Method,
-    pub path: &'static str,
-    pub body: Body,
+/// Records the outcome of an attempt on the span of the request, see [`Mpesa::call`]
+#[cfg(feature = "tracing")]
+fn record_response(attempt: u32, status: reqwest::StatusCode, body: &str, elapsed: Duration) {
+    let span = tracing::Span::current();
+    span.record("http.status", status.as_u16());
+    if let Ok(body) = serde_json::from_str::<serde_json::Value>(body) {
+        let field = |name: &str| body.get(name).and_then(serde_json::Value::as_str);
+        if let Some(code) = field("errorCode") {
+            span.record("error_code", code);
+        }
+        if let Some(id) = field("ConversationID") {
+            span.record("conversation_id", id);
+        }
+        if let Some(id) = field("CheckoutRequestID") {
+            span.record("checkout_request_id", id);
+        }
+    }
+    tracing::debug!(
+        attempt,
+        http.status = status.as_u16(),
+        latency_ms = elapsed.as_millis() as u64,
+        "received response"
+    );
+}
+
+struct Request<Body: Serialize + Send> {
+    method: reqwest::Method,
+    path: &'static str,
+    body: Body,
+    /// Whether sending the request more than once is harmless.
+    /// Requests that move money are not idempotent
+    idempotent: bool,
+    /// Overrides the retry policy of the client
+    retry_policy: Option<RetryPolicy>,
+    /// How many times the request has been sent
+    attempts: AtomicU32,
 }
 
 #[cfg(test)]
@@ -488,11 +1241,84 @@ mod tests {
     use crate::Sandbox;
 
     #[test]
-    fn test_setting_initator_password() {
+    fn test_setting_fallback_initator_password() {
         let client = Mpesa::new("consumer_key", "consumer_secret", Sandbox);
-        assert_eq!(client.initiator_password(), DEFAULT_INITIATOR_PASSWORD);
-        client.set_initiator_password("foo_bar");
-        assert_eq!(client.initiator_password(), "foo_bar".to_string());
+        assert_eq!(
+            client.initiator_credential("testapi").unwrap().expose_secret(),
+            DEFAULT_INITIATOR_PASSWORD
+        );
+        client.set_fallback_initiator_password("foo_bar");
+        assert_eq!(
+            client.initiator_credential("testapi").unwrap().expose_secret(),
+            "foo_bar"
+        );
+    }
+
+    #[test]
+    fn test_sandbox_initiator_password_is_not_used_in_production() {
+        let client = Mpesa::new("consumer_key", "consumer_secret", crate::Production);
+        assert!(client.is_production());
+        assert!(matches!(
+            client.initiator_credential("testapi"),
+            Err(MpesaError::MissingCredential("initiator password"))
+        ));
+        client.set_initiator("testapi", "foo_bar");
+        assert_eq!(
+            client.initiator_credential("testapi").unwrap().expose_secret(),
+            "foo_bar"
+        );
+    }
+
+    #[test]
+    fn test_builder_configures_the_client() {
+        let client = Mpesa::builder("consumer_key", "consumer_secret", TestEnvironment)
+            .timeout(Duration::from_secs(5))
+            .user_agent("mpesa-test")
+            .retry_policy(RetryPolicy::none())
+            .initiator("testapi", "foo_bar")
+            .auth_refresh_margin(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        assert_eq!(&client.base_url, "https://example.com");
+        assert_eq!(
+            client.initiator_credential("testapi").unwrap().expose_secret(),
+            "foo_bar"
+        );
+        assert_eq!(client.retry_policy(), RetryPolicy::none());
+        assert_eq!(client.auth_refresh_margin(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_cached_auth_respects_refresh_margin() {
+        let client = Mpesa::new("consumer_key", "consumer_secret", Sandbox);
+        assert!(!client.has_cached_auth());
+
+        client.set_auth_token("token", chrono::Utc::now().timestamp() + 120);
+        assert!(client.has_cached_auth());
+
+        client.set_auth_refresh_margin(Duration::from_secs(180));
+        assert!(!client.has_cached_auth());
+    }
+
+    #[test]
+    fn test_debug_redacts_credentials() {
+        let builder = Mpesa::builder("app_key", "app_secret", TestEnvironment).fallback_initiator_password("foo_bar");
+        let builder_debug = format!("{builder:?}");
+        let client = builder.build().unwrap();
+        client.set_auth_token("access_token", chrono::Utc::now().timestamp() + 3600);
+        let client_debug = format!("{client:?}");
+        for debug in [builder_debug, client_debug] {
+            assert!(debug.contains("[REDACTED]"), "{debug}");
+            for secret in [
+                "consumer_key",
+                "consumer_secret",
+                "foo_bar",
+                "access_token",
+                "certificate",
+            ] {
+                assert!(!debug.contains(&format!("\"{secret}\"")), "{secret} leaked in {debug}");
+            }
+        }
     }
 
     #[derive(Clone)]
@@ -518,10 +1344,49 @@ mod tests {
 
     #[cfg(any(feature = "openssl", feature = "no_openssl"))]
     #[test]
-    #[should_panic]
     fn test_gen_security_credentials_fails_with_invalid_pem() {
+        let client = Mpesa::builder("consumer_key", "consumer_secret", TestEnvironment)
+            .initiator("testapi", "password")
+            .build()
+            .unwrap();
+        let err = client.gen_security_credentials("testapi").unwrap_err();
+        #[cfg(feature = "openssl")]
+        assert!(matches!(err, MpesaError::EncryptionError(_)), "{err:?}");
+        #[cfg(not(feature = "openssl"))]
+        assert!(matches!(err, MpesaError::EncryptionErrors(_)), "{err:?}");
+    }
+
+    #[test]
+    fn test_production_client_without_initiator_fails() {
         let client = Mpesa::new("consumer_key", "consumer_secret", TestEnvironment);
-        let _ = client.gen_security_credentials().unwrap();
+        assert!(matches!(
+            client.initiator_credential("testapi"),
+            Err(MpesaError::MissingCredential(_))
+        ));
+    }
+
+    #[cfg(any(feature = "openssl", feature = "no_openssl"))]
+    #[test]
+    fn test_security_credentials_of_registered_initiators() {
+        let client = Mpesa::builder("consumer_key", "consumer_secret", Sandbox)
+            .initiator("payouts_api", "payouts_password")
+            .build()
+            .unwrap();
+        client.set_initiator_security_credential("reports_api", "EsJocK7+NjqZPC3I3EO+TbvS+xVb9TymWwaKABoaZr");
+        let credential = client.gen_security_credentials("payouts_api").unwrap();
+        assert!(!credential.is_empty() && credential != "payouts_password");
+        assert_eq!(
+            client.gen_security_credentials("reports_api").unwrap(),
+            "EsJocK7+NjqZPC3I3EO+TbvS+xVb9TymWwaKABoaZr"
+        );
+        assert!(matches!(
+            client.gen_security_credentials("testapi"),
+            Err(MpesaError::UnknownInitiator(name)) if name == "testapi"
+        ));
+
+        // unless the client opts into a fallback for unregistered initiators
+        client.set_fallback_initiator_password("foo_bar");
+        assert!(client.gen_security_credentials("testapi").is_ok());
     }
 
     #[cfg(feature = "no_openssl")]
@@ -534,7 +1399,7 @@ mod tests {
         use crate::errors::EncryptionErrors;
 
         let client = Mpesa::new("consumer_key", "consumer_secret", Sandbox);
-        let rr = client.gen_security_credentials();
+        let rr = client.gen_security_credentials("testapi");
         assert!(rr.is_ok());
         let r = rr.unwrap();
         println!("Generated security credentials: {}", r);
@@ -560,7 +1425,15 @@ mod tests {
                 let public_key = public_keyr.unwrap();
                 let mut rng = rand::thread_rng();
                 let encryptedr = public_key
-                    .encrypt(&mut rng, Pkcs1v15Encrypt, client.initiator_password().as_bytes())
+                    .encrypt(
+                        &mut rng,
+                        Pkcs1v15Encrypt,
+                        client
+                            .initiator_credential("testapi")
+                            .unwrap()
+                            .expose_secret()
+                            .as_bytes(),
+                    )
                     .map_err(EncryptionErrors::RsaEncryption);
                 assert!(encryptedr.is_ok());
                 let encrypted = encryptedr.unwrap();
@@ -0,0 +1,318 @@
+//!# MPESA Client Configuration
+//!
+//! [`MpesaConfig`] gathers the credentials, environment, initiator, shortcodes and callback URLs an application
+//! needs to talk to the Safaricom API, so that they can be loaded once from environment variables or a
+//! configuration file and shared by every service.
+
+use std::env::VarError;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::client::{Mpesa, MpesaBuilder};
+use crate::environment::Environment;
+use crate::errors::MpesaResult;
+use crate::redact;
+
+/// Configuration of an [`Mpesa`] client and the values commonly passed to its requests.
+///
+/// [`Mpesa::from_config`] applies the credentials, environment, initiator and passkey to the client. The
+/// shortcodes and callback URLs are not applied: they are data for callers to read and pass to the request
+/// builders, e.g `client.express_request().business_short_code(..)`.
+///
+/// Deserializes from any `serde` format with snake_case keys, e.g a TOML file:
+///
+/// ```toml
+/// consumer_key = "..."
+/// consumer_secret = "..."
+/// environment = "sandbox"
+/// initiator_name = "testapi"
+/// initiator_password = "..."
+/// business_short_code = "174379"
+/// passkey = "..."
+/// result_url = "https://example.com/mpesa/result"
+/// timeout_url = "https://example.com/mpesa/timeout"
+/// ```
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MpesaConfig {
+    /// The consumer key of the Daraja app
+    pub consumer_key: String,
+    /// The consumer secret of the Daraja app
+    pub consumer_secret: String,
+    /// The environment the credentials belong to
+    pub environment: Environment,
+    /// The name of the initiator of B2C, B2B, Account Balance, Transaction Status and Transaction Reversal
+    /// requests
+    #[serde(default)]
+    pub initiator_name: Option<String>,
+    /// The password of the initiator, registered for the `initiator_name` with [`Mpesa::set_initiator`].
+    ///
+    /// Without an `initiator_name`, it is used for every initiator, see
+    /// [`Mpesa::set_fallback_initiator_password`]
+    #[serde(default)]
+    pub initiator_password: Option<String>,
+    /// The Paybill or Till number that receives Mpesa Express/ STK Push and C2B payments
+    #[serde(default)]
+    pub business_short_code: Option<String>,
+    /// The shortcode that initiates B2C, B2B, Account Balance and Transaction Status requests
+    #[serde(default)]
+    pub party_a: Option<String>,
+    /// The Mpesa Express/ STK Push passkey of the `business_short_code`, see [`Mpesa::set_passkey`]
+    #[serde(default)]
+    pub passkey: Option<String>,
+    /// The URL Mpesa Express/ STK Push callbacks are posted to
+    #[serde(default)]
+    pub express_callback_url: Option<Url>,
+    /// The URL C2B confirmation requests are posted to
+    #[serde(default)]
+    pub c2b_confirmation_url: Option<Url>,
+    /// The URL C2B validation requests are posted to
+    #[serde(default)]
+    pub c2b_validation_url: Option<Url>,
+    /// The URL results of asynchronous requests are posted to
+    #[serde(default)]
+    pub result_url: Option<Url>,
+    /// The URL timeouts of asynchronous requests are posted to
+    #[serde(default)]
+    pub timeout_url: Option<Url>,
+}
+
+impl fmt::Debug for MpesaConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpesaConfig")
+            .field("consumer_key", &redact::Secret)
+            .field("consumer_secret", &redact::Secret)
+            .field("environment", &self.environment)
+            .field("initiator_name", &self.initiator_name)
+            .field("initiator_password", &redact::secret(&self.initiator_password))
+            .field("business_short_code", &self.business_short_code)
+            .field("party_a", &self.party_a)
+            .field("passkey", &redact::secret(&self.passkey))
+            .field("express_callback_url", &self.express_callback_url)
+            .field("c2b_confirmation_url", &self.c2b_confirmation_url)
+            .field("c2b_validation_url", &self.c2b_validation_url)
+            .field("result_url", &self.result_url)
+            .field("timeout_url", &self.timeout_url)
+            .finish()
+    }
+}
+
+impl MpesaConfig {
+    /// Reads the configuration from environment variables.
+    ///
+    /// | Variable | Field | Required |
+    /// |---|---|---|
+    /// | `CONSUMER_KEY` | `consumer_key` | yes |
+    /// | `CONSUMER_SECRET` | `consumer_secret` | yes |
+    /// | `ENVIRONMENT` | `environment`, `sandbox` or `production` | yes |
+    /// | `INITIATOR_NAME` | `initiator_name` | no |
+    /// | `INITIATOR_PASSWORD` | `initiator_password` | no |
+    /// | `BUSINESS_SHORT_CODE` | `business_short_code` | no |
+    /// | `PARTY_A` | `party_a` | no |
+    /// | `PASSKEY` | `passkey` | no |
+    /// | `EXPRESS_CALLBACK_URL` | `express_callback_url` | no |
+    /// | `C2B_CONFIRMATION_URL` | `c2b_confirmation_url` | no |
+    /// | `C2B_VALIDATION_URL` | `c2b_validation_url` | no |
+    /// | `RESULT_URL` | `result_url` | no |
+    /// | `TIMEOUT_URL` | `timeout_url` | no |
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::EnvironmentalVariableError` if a required variable is missing or any variable is
+    /// not valid unicode, and a `MpesaError` if the environment or a URL cannot be parsed
+    pub fn from_env() -> MpesaResult<MpesaConfig> {
+        MpesaConfig::from_vars(|name| std::env::var(name))
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Result<String, VarError>) -> MpesaResult<MpesaConfig> {
+        let required =
+            |name: &str| var(name).inspect_err(|e| log::error!("error reading environment variable {name}: {e}"));
+        let optional = |name: &str| match var(name) {
+            Ok(value) if value.trim().is_empty() => Ok(None),
+            Ok(value) => Ok(Some(value)),
+            Err(VarError::NotPresent) => Ok(None),
+            Err(e) => {
+                log::error!("error reading environment variable {name}: {e}");
+                Err(e)
+            }
+        };
+        let optional_url =
+            |name: &str| -> MpesaResult<Option<Url>> { Ok(optional(name)?.map(|url| Url::parse(&url)).transpose()?) };
+
+        Ok(MpesaConfig {
+            consumer_key: required("CONSUMER_KEY")?,
+            consumer_secret: required("CONSUMER_SECRET")?,
+            environment: required("ENVIRONMENT")?.try_into()?,
+            initiator_name: optional("INITIATOR_NAME")?,
+            initiator_password: optional("INITIATOR_PASSWORD")?,
+            business_short_code: optional("BUSINESS_SHORT_CODE")?,
+            party_a: optional("PARTY_A")?,
+            passkey: optional("PASSKEY")?,
+            express_callback_url: optional_url("EXPRESS_CALLBACK_URL")?,
+            c2b_confirmation_url: optional_url("C2B_CONFIRMATION_URL")?,
+            c2b_validation_url: optional_url("C2B_VALIDATION_URL")?,
+            result_url: optional_url("RESULT_URL")?,
+            timeout_url: optional_url("TIMEOUT_URL")?,
+        })
+    }
+}
+
+impl Mpesa {
+    /// Constructs a new `Mpesa` client from the environment variables read by [`MpesaConfig::from_env`]
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use mpesa::Mpesa;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> mpesa::MpesaResult<()> {
+    ///     dotenvy::dotenv().ok();
+    ///
+    ///     let client = Mpesa::from_env()?;
+    ///     assert!(client.is_connected().await);
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` if the configuration cannot be read or the client cannot be built
+    pub fn from_env() -> MpesaResult<Mpesa> {
+        Mpesa::from_config(&MpesaConfig::from_env()?)
+    }
+
+    /// Constructs a new `Mpesa` client with the credentials, environment, initiator and passkey of `config`
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::NetworkError` if the http client cannot be built
+    pub fn from_config(config: &MpesaConfig) -> MpesaResult<Mpesa> {
+        config
+            .configure(Mpesa::builder(
+                config.consumer_key.as_str(),
+                config.consumer_secret.as_str(),
+                config.environment.clone(),
+            ))
+            .build()
+    }
+}
+
+impl MpesaConfig {
+    /// Sets the initiator and passkey of the configuration on `builder`
+    pub(crate) fn configure(&self, mut builder: MpesaBuilder) -> MpesaBuilder {
+        builder = match (&self.initiator_name, &self.initiator_password) {
+            (Some(initiator_name), Some(password)) => builder.initiator(initiator_name.as_str(), password.as_str()),
+            (None, Some(password)) => builder.fallback_initiator_password(password.as_str()),
+            (_, None) => builder,
+        };
+        if let Some(passkey) = &self.passkey {
+            builder = builder.passkey(passkey.as_str());
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::MpesaError;
+
+    fn from_vars(vars: &[(&str, &str)]) -> MpesaResult<MpesaConfig> {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        MpesaConfig::from_vars(|name| vars.get(name).map(|v| v.to_string()).ok_or(VarError::NotPresent))
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        let config = from_vars(&[
+            ("CONSUMER_KEY", "key"),
+            ("CONSUMER_SECRET", "secret"),
+            ("ENVIRONMENT", "Sandbox"),
+            ("INITIATOR_NAME", "testapi"),
+            ("PASSKEY", ""),
+            ("RESULT_URL", "https://example.com/result"),
+        ])
+        .unwrap();
+        assert_eq!(config.consumer_key, "key");
+        assert_eq!(config.environment, Environment::Sandbox);
+        assert_eq!(config.initiator_name.as_deref(), Some("testapi"));
+        assert_eq!(config.passkey, None);
+        assert_eq!(config.result_url.unwrap().as_str(), "https://example.com/result");
+
+        let client = Mpesa::from_config(
+            &from_vars(&[
+                ("CONSUMER_KEY", "key"),
+                ("CONSUMER_SECRET", "secret"),
+                ("ENVIRONMENT", "production"),
+                ("INITIATOR_NAME", "acme_api"),
+                ("INITIATOR_PASSWORD", "foo_bar"),
+                ("PASSKEY", "bfb279f9aa9bdbcf"),
+            ])
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(client.passkey().as_deref(), Some("bfb279f9aa9bdbcf"));
+        assert_eq!(
+            client.initiator_credential("acme_api").unwrap().expose_secret(),
+            "foo_bar"
+        );
+        assert!(matches!(
+            client.initiator_credential("testapi"),
+            Err(MpesaError::UnknownInitiator(name)) if name == "testapi"
+        ));
+        assert_eq!(client.base_url, "https://api.safaricom.co.ke");
+    }
+
+    #[test]
+    fn test_config_debug_redacts_credentials() {
+        let config = from_vars(&[
+            ("CONSUMER_KEY", "key"),
+            ("CONSUMER_SECRET", "secret"),
+            ("ENVIRONMENT", "sandbox"),
+            ("INITIATOR_PASSWORD", "foo_bar"),
+            ("PASSKEY", "bfb279f9aa9bdbcf"),
+        ])
+        .unwrap();
+        let debug = format!("{config:?}");
+        assert!(debug.contains("initiator_password: Some([REDACTED])"), "{debug}");
+        for secret in ["\"key\"", "\"secret\"", "foo_bar", "bfb279f9aa9bdbcf"] {
+            assert!(!debug.contains(secret), "{secret} leaked in {debug}");
+        }
+    }
+
+    #[test]
+    fn test_config_from_env_fails_on_missing_or_invalid_variables() {
+        assert!(matches!(
+            from_vars(&[("CONSUMER_KEY", "key"), ("ENVIRONMENT", "sandbox")]),
+            Err(MpesaError::EnvironmentalVariableError(VarError::NotPresent))
+        ));
+        assert!(
+            from_vars(&[
+                ("CONSUMER_KEY", "key"),
+                ("CONSUMER_SECRET", "secret"),
+                ("ENVIRONMENT", "sandbox"),
+                ("TIMEOUT_URL", "not a url"),
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_config_deserializes() {
+        let config: MpesaConfig = serde_json::from_str(
+            r#"{
+                "consumer_key": "key",
+                "consumer_secret": "secret",
+                "environment": "production",
+                "business_short_code": "174379",
+                "express_callback_url": "https://example.com/stk"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.environment, Environment::Production);
+        assert_eq!(config.business_short_code.as_deref(), Some("174379"));
+        assert_eq!(config.initiator_password, None);
+        assert!(serde_json::from_str::<MpesaConfig>(r#"{"consumer_key": "key"}"#).is_err());
+    }
+}
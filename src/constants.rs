@@ -1,11 +1,14 @@
-use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 
 #[cfg(any(feature = "bill_manager", feature = "express"))]
 use chrono::prelude::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
 use crate::MpesaError;
+#[cfg(feature = "bill_manager")]
+use crate::redact;
 
 /// Mpesa command ids
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -35,7 +38,7 @@ impl Display for CommandId {
 /// Identifier types - both sender and receiver - identify an M-Pesa transaction’s sending and receiving party as
 /// either a shortcode, a till number or a MSISDN (phone number).
 /// There are three identifier types that can be used with M-Pesa APIs.
-#[derive(Debug, Serialize_repr, Deserialize_repr, Copy, Clone)]
+#[derive(Debug, Serialize_repr, Deserialize_repr, Copy, Clone, PartialEq, Eq)]
 #[repr(u16)]
 pub enum IdentifierTypes {
     MSISDN = 1,
@@ -50,38 +53,161 @@ impl Display for IdentifierTypes {
     }
 }
 
-/// TODO: Enable deserializing of json numbers/ strings to `MpesaResponseCode`
 /// M-pesa result and response codes
-#[derive(Debug, Copy, Clone, Deserialize_repr)]
-#[repr(u16)]
-#[allow(unused)]
+///
+/// Deserializes from both the string form e.g `"0"` sent in responses and the numeric form
+/// sent in callbacks. Codes that are not catalogued are kept in `MpesaResponseCode::Unknown`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum MpesaResponseCode {
-    Success = 0,
-    InsufficientFunds = 1,
-    LessThanMinimum = 2,
-    MoreThanMaximum = 3,
-    ExceededDailyLimit = 4,
-    ExceededMinimumBalance = 5,
-    UnresolvedPrimaryParty = 6,
-    UnresolvedReceiverParty = 7,
-    ExceededMaximumBalance = 8,
-    InvalidDebitAccount = 11,
-    InvalidCreditAccount = 12,
-    UnresolvedDebitAccount = 13,
-    UnresolvedCreditAccount = 14,
-    DuplicateDetected = 15,
-    InternalFailure = 17,
-    UnresolvedInitiator = 20,
-    TrafficBlocking = 26,
+    Success,
+    InsufficientFunds,
+    LessThanMinimum,
+    MoreThanMaximum,
+    ExceededDailyLimit,
+    ExceededMinimumBalance,
+    UnresolvedPrimaryParty,
+    UnresolvedReceiverParty,
+    ExceededMaximumBalance,
+    InvalidDebitAccount,
+    InvalidCreditAccount,
+    UnresolvedDebitAccount,
+    UnresolvedCreditAccount,
+    DuplicateDetected,
+    InternalFailure,
+    UnresolvedInitiator,
+    TrafficBlocking,
+    /// A transaction is already in process for the subscriber
+    SubscriberLocked,
+    /// The transaction expired before it was processed
+    TransactionExpired,
+    /// An error occurred while sending the STK push request
+    PushRequestError,
+    /// The customer cancelled the STK push prompt
+    RequestCancelledByUser,
+    /// The customer could not be reached or did not respond to the STK push prompt in time
+    DsTimeout,
+    /// The initiator information is invalid, e.g the customer entered the wrong PIN
+    InvalidInitiatorInformation,
+    /// The STK push request could not be processed
+    PushRequestFailed,
+    Unknown(i64),
+}
+
+impl MpesaResponseCode {
+    /// The numeric code as sent by the API
+    pub fn code(&self) -> i64 {
+        match self {
+            MpesaResponseCode::Success => 0,
+            MpesaResponseCode::InsufficientFunds => 1,
+            MpesaResponseCode::LessThanMinimum => 2,
+            MpesaResponseCode::MoreThanMaximum => 3,
+            MpesaResponseCode::ExceededDailyLimit => 4,
+            MpesaResponseCode::ExceededMinimumBalance => 5,
+            MpesaResponseCode::UnresolvedPrimaryParty => 6,
+            MpesaResponseCode::UnresolvedReceiverParty => 7,
+            MpesaResponseCode::ExceededMaximumBalance => 8,
+            MpesaResponseCode::InvalidDebitAccount => 11,
+            MpesaResponseCode::InvalidCreditAccount => 12,
+            MpesaResponseCode::UnresolvedDebitAccount => 13,
+            MpesaResponseCode::UnresolvedCreditAccount => 14,
+            MpesaResponseCode::DuplicateDetected => 15,
+            MpesaResponseCode::InternalFailure => 17,
+            MpesaResponseCode::UnresolvedInitiator => 20,
+            MpesaResponseCode::TrafficBlocking => 26,
+            MpesaResponseCode::SubscriberLocked => 1001,
+            MpesaResponseCode::TransactionExpired => 1019,
+            MpesaResponseCode::PushRequestError => 1025,
+            MpesaResponseCode::RequestCancelledByUser => 1032,
+            MpesaResponseCode::DsTimeout => 1037,
+            MpesaResponseCode::InvalidInitiatorInformation => 2001,
+            MpesaResponseCode::PushRequestFailed => 9999,
+            MpesaResponseCode::Unknown(code) => *code,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        *self == MpesaResponseCode::Success
+    }
+}
+
+impl From<i64> for MpesaResponseCode {
+    fn from(code: i64) -> Self {
+        match code {
+            0 => MpesaResponseCode::Success,
+            1 => MpesaResponseCode::InsufficientFunds,
+            2 => MpesaResponseCode::LessThanMinimum,
+            3 => MpesaResponseCode::MoreThanMaximum,
+            4 => MpesaResponseCode::ExceededDailyLimit,
+            5 => MpesaResponseCode::ExceededMinimumBalance,
+            6 => MpesaResponseCode::UnresolvedPrimaryParty,
+            7 => MpesaResponseCode::UnresolvedReceiverParty,
+            8 => MpesaResponseCode::ExceededMaximumBalance,
+            11 => MpesaResponseCode::InvalidDebitAccount,
+            12 => MpesaResponseCode::InvalidCreditAccount,
+            13 => MpesaResponseCode::UnresolvedDebitAccount,
+            14 => MpesaResponseCode::UnresolvedCreditAccount,
+            15 => MpesaResponseCode::DuplicateDetected,
+            17 => MpesaResponseCode::InternalFailure,
+            20 => MpesaResponseCode::UnresolvedInitiator,
+            26 => MpesaResponseCode::TrafficBlocking,
+            1001 => MpesaResponseCode::SubscriberLocked,
+            1019 => MpesaResponseCode::TransactionExpired,
+            1025 => MpesaResponseCode::PushRequestError,
+            1032 => MpesaResponseCode::RequestCancelledByUser,
+            1037 => MpesaResponseCode::DsTimeout,
+            2001 => MpesaResponseCode::InvalidInitiatorInformation,
+            9999 => MpesaResponseCode::PushRequestFailed,
+            code => MpesaResponseCode::Unknown(code),
+        }
+    }
 }
 
 impl Display for MpesaResponseCode {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "{:?}", *self as u16)
+        write!(f, "{}", self.code())
+    }
+}
+
+impl Serialize for MpesaResponseCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for MpesaResponseCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ResponseCodeVisitor;
+
+        impl Visitor<'_> for ResponseCodeVisitor {
+            type Value = MpesaResponseCode;
+
+            fn expecting(&self, f: &mut Formatter) -> FmtResult {
+                write!(f, "a numeric response code as a number or a string")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(MpesaResponseCode::from(v))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                i64::try_from(v)
+                    .map(MpesaResponseCode::from)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.trim()
+                    .parse::<i64>()
+                    .map(MpesaResponseCode::from)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_any(ResponseCodeVisitor)
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 /// C2B Register Response types
 pub enum ResponseType {
     Completed,
@@ -94,7 +220,7 @@ impl Display for ResponseType {
     }
 }
 
-#[derive(Debug, Deserialize_repr, Serialize_repr, Copy, Clone)]
+#[derive(Debug, Deserialize_repr, Serialize_repr, Copy, Clone, PartialEq, Eq)]
 #[repr(u16)]
 pub enum SendRemindersTypes {
     Disable = 0,
@@ -108,23 +234,40 @@ impl Display for SendRemindersTypes {
 }
 
 #[cfg(feature = "bill_manager")]
-#[derive(Debug, Serialize)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Invoice<'i> {
+pub struct Invoice {
     pub amount: f64,
-    pub account_reference: &'i str,
-    pub billed_full_name: &'i str,
-    pub billed_period: &'i str,
-    pub billed_phone_number: &'i str,
+    pub account_reference: String,
+    pub billed_full_name: String,
+    pub billed_period: String,
+    pub billed_phone_number: String,
     pub due_date: DateTime<Utc>,
-    pub external_reference: &'i str,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub invoice_items: Option<Vec<InvoiceItem<'i>>>,
-    pub invoice_name: &'i str,
+    pub external_reference: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invoice_items: Option<Vec<InvoiceItem>>,
+    pub invoice_name: String,
 }
 
 #[cfg(feature = "bill_manager")]
-impl<'i> Display for Invoice<'i> {
+impl Debug for Invoice {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("Invoice")
+            .field("amount", &self.amount)
+            .field("account_reference", &self.account_reference)
+            .field("billed_full_name", &self.billed_full_name)
+            .field("billed_period", &self.billed_period)
+            .field("billed_phone_number", &redact::Msisdn(&self.billed_phone_number))
+            .field("due_date", &self.due_date)
+            .field("external_reference", &self.external_reference)
+            .field("invoice_items", &self.invoice_items)
+            .field("invoice_name", &self.invoice_name)
+            .finish()
+    }
+}
+
+#[cfg(feature = "bill_manager")]
+impl Display for Invoice {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(
             f,
@@ -138,20 +281,20 @@ impl<'i> Display for Invoice<'i> {
 }
 
 #[cfg(feature = "bill_manager")]
-#[derive(Debug, Serialize)]
-pub struct InvoiceItem<'i> {
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvoiceItem {
     pub amount: f64,
-    pub item_name: &'i str,
+    pub item_name: String,
 }
 
 #[cfg(feature = "bill_manager")]
-impl<'i> Display for InvoiceItem<'i> {
+impl Display for InvoiceItem {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(f, "amount: {}, item_name: {}", self.amount, self.item_name)
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TransactionType {
     /// Send Money(Mobile number).
     SendMoney,
@@ -185,3 +328,33 @@ impl TryFrom<&str> for TransactionType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_code_deserializes_from_strings_and_numbers() {
+        let codes: Vec<MpesaResponseCode> = serde_json::from_str(r#"["0", 1032, " 2001 ", "1037", 42]"#).unwrap();
+        assert_eq!(
+            codes,
+            vec![
+                MpesaResponseCode::Success,
+                MpesaResponseCode::RequestCancelledByUser,
+                MpesaResponseCode::InvalidInitiatorInformation,
+                MpesaResponseCode::DsTimeout,
+                MpesaResponseCode::Unknown(42),
+            ]
+        );
+        assert!(serde_json::from_str::<MpesaResponseCode>(r#""Success""#).is_err());
+    }
+
+    #[test]
+    fn test_response_code_round_trips() {
+        for code in [0, 1, 17, 26, 1001, 1019, 1025, 1032, 1037, 2001, 9999, 123] {
+            let response_code = MpesaResponseCode::from(code);
+            assert_eq!(response_code.code(), code);
+            assert_eq!(serde_json::to_string(&response_code).unwrap(), code.to_string());
+        }
+    }
+}
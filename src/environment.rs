@@ -13,9 +13,15 @@
 use std::convert::TryFrom;
 use std::str::FromStr;
 
+use serde::{Deserialize, Serialize};
+
 use crate::MpesaError;
 
-#[derive(Debug, Clone)]
+/// The X509 certificate of the sandbox, used for encrypting initiator passwords
+const SANDBOX_CERTIFICATE: &str = include_str!("./certificates/sandbox");
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", rename_all = "lowercase")]
 /// Enum to map to desired environment so as to access certificate
 /// and the base url
 /// Required to construct a new `Mpesa` struct
@@ -31,6 +37,15 @@ pub enum Environment {
 pub trait ApiEnvironment: Clone {
     fn base_url(&self) -> &str;
     fn get_certificate(&self) -> &str;
+
+    /// Whether requests are made against production.
+    /// Sandbox defaults, such as the test initiator password and passkey, are never used in production
+    ///
+    /// Defaults to `true` unless the environment uses the sandbox certificate, so that an environment which does
+    /// not say otherwise never gets sandbox defaults
+    fn is_production(&self) -> bool {
+        self.get_certificate().trim() != SANDBOX_CERTIFICATE.trim()
+    }
 }
 
 impl FromStr for Environment {
@@ -75,9 +90,13 @@ impl ApiEnvironment for Environment {
     fn get_certificate(&self) -> &str {
         match self {
             Environment::Production => include_str!("./certificates/production"),
-            Environment::Sandbox => include_str!("./certificates/sandbox"),
+            Environment::Sandbox => SANDBOX_CERTIFICATE,
         }
     }
+
+    fn is_production(&self) -> bool {
+        matches!(self, Environment::Production)
+    }
 }
 
 #[cfg(test)]
@@ -93,15 +112,37 @@ mod tests {
         accepted_production_values.into_iter().for_each(|v| {
             let environment: Environment = v.parse().unwrap();
             assert_eq!(environment.base_url(), "https://api.safaricom.co.ke");
-            assert_eq!(environment.get_certificate(), include_str!("./certificates/production"))
+            assert_eq!(environment.get_certificate(), include_str!("./certificates/production"));
+            assert!(environment.is_production());
         });
         accepted_sandbox_values.into_iter().for_each(|v| {
             let environment: Environment = v.try_into().unwrap();
             assert_eq!(environment.base_url(), "https://sandbox.safaricom.co.ke");
-            assert_eq!(environment.get_certificate(), include_str!("./certificates/sandbox"))
+            assert_eq!(environment.get_certificate(), include_str!("./certificates/sandbox"));
+            assert!(!environment.is_production());
         })
     }
 
+    #[derive(Clone)]
+    struct CustomEnvironment(&'static str);
+
+    impl ApiEnvironment for CustomEnvironment {
+        fn base_url(&self) -> &str {
+            "http://localhost:8080"
+        }
+
+        fn get_certificate(&self) -> &str {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_custom_environments_are_production_unless_they_use_the_sandbox_certificate() {
+        assert!(!CustomEnvironment(SANDBOX_CERTIFICATE).is_production());
+        assert!(CustomEnvironment(include_str!("./certificates/production")).is_production());
+        assert!(CustomEnvironment("").is_production());
+    }
+
     #[test]
     #[should_panic]
     fn test_invalid_string_panics() {
@@ -9,6 +9,8 @@ use thiserror::Error;
 pub enum MpesaError {
     #[error("Service error: {0}")]
     Service(ResponseError),
+    #[error("The access token was rejected even after re-authenticating")]
+    Unauthorized(Option<ResponseError>),
     #[error("An error has occurred while performing the http request")]
     NetworkError(#[from] reqwest::Error),
     #[error("A recoverable error has occurred while performing an operation. Retrying is possible.")]
@@ -27,6 +29,29 @@ pub enum MpesaError {
     Message(&'static str),
     #[error("An error has occurred while building the request: {0}")]
     BuilderError(BuilderError),
+    #[cfg(feature = "account_balance")]
+    #[error("Invalid account balance: {0}")]
+    InvalidAccountBalance(String),
+    #[error("The request was rejected by an interceptor: {0}")]
+    Rejected(String),
+    #[error("No client is registered for tenant or shortcode `{0}`")]
+    UnknownTenant(String),
+    #[error("No credential is registered for initiator `{0}`")]
+    UnknownInitiator(String),
+    #[error("The {0} must be set in production, sandbox defaults are only used in sandbox")]
+    MissingCredential(&'static str),
+    #[error("An error has occurred in the token store: {0}")]
+    TokenStoreError(String),
+    #[error("Timed out waiting for the callback of `{0}`")]
+    CallbackTimeout(String),
+    #[error("Another task is already waiting for the callback of `{0}`")]
+    AlreadyWaiting(String),
+    #[cfg(feature = "express")]
+    #[error("STK push `{0}` was still pending at the polling deadline")]
+    PollDeadlineExceeded(String),
+    #[cfg(feature = "callbacks_server")]
+    #[error("An error has occurred in the callback server: {0}")]
+    CallbackServerError(#[from] std::io::Error),
 }
 
 /// Encryption errors when the `no_openssl` feature is enabled
@@ -66,6 +91,157 @@ impl fmt::Display for ResponseError {
     }
 }
 
+impl ResponseError {
+    /// The typed `error_code` of the response
+    pub fn code(&self) -> DarajaErrorCode {
+        DarajaErrorCode::from(self.error_code.as_str())
+    }
+}
+
+/// The broad class of a [`DarajaErrorCode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The access token or credentials were rejected
+    Auth,
+    /// The request was malformed or referenced something that does not exist
+    Validation,
+    /// The request was rejected due to load or rate limits and can be retried later
+    Throttling,
+    /// M-Pesa failed to process the request
+    Internal,
+}
+
+/// Error codes returned by the Daraja API in the `errorCode` of a [`ResponseError`]
+///
+/// Source: [Daraja error codes](https://developer.safaricom.co.ke/Documentation)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DarajaErrorCode {
+    /// `400.002.02`: A field of the request payload is missing or invalid
+    InvalidRequestPayload,
+    /// `400.008.01`: The authentication type of the token request is invalid
+    InvalidAuthenticationType,
+    /// `400.008.02`: The grant type of the token request is invalid
+    InvalidGrantType,
+    /// `401.002.01`: The access token is invalid or expired
+    InvalidCredentials,
+    /// `404.001.01`: The requested resource does not exist
+    ResourceNotFound,
+    /// `404.001.03`: The access token is invalid
+    InvalidAccessToken,
+    /// `404.001.04`: The authentication header is missing or malformed
+    InvalidAuthenticationHeader,
+    /// `500.001.1001`: A server error that covers, among others, a transaction still being
+    /// processed, a locked subscriber and an unknown merchant
+    ServerError,
+    /// `500.002.1001`: The service is currently unreachable
+    ServiceUnreachable,
+    /// `500.003.01`: Internal server error
+    InternalServerError,
+    /// `500.003.02`: The system is busy
+    SystemBusy,
+    /// `500.003.03`: The quota or spike arrest limit was exceeded
+    QuotaViolation,
+    /// Any other error code
+    Unknown(String),
+}
+
+impl DarajaErrorCode {
+    /// The error code as sent by the API, e.g `500.003.02`
+    pub fn as_str(&self) -> &str {
+        match self {
+            DarajaErrorCode::InvalidRequestPayload => "400.002.02",
+            DarajaErrorCode::InvalidAuthenticationType => "400.008.01",
+            DarajaErrorCode::InvalidGrantType => "400.008.02",
+            DarajaErrorCode::InvalidCredentials => "401.002.01",
+            DarajaErrorCode::ResourceNotFound => "404.001.01",
+            DarajaErrorCode::InvalidAccessToken => "404.001.03",
+            DarajaErrorCode::InvalidAuthenticationHeader => "404.001.04",
+            DarajaErrorCode::ServerError => "500.001.1001",
+            DarajaErrorCode::ServiceUnreachable => "500.002.1001",
+            DarajaErrorCode::InternalServerError => "500.003.01",
+            DarajaErrorCode::SystemBusy => "500.003.02",
+            DarajaErrorCode::QuotaViolation => "500.003.03",
+            DarajaErrorCode::Unknown(code) => code,
+        }
+    }
+
+    /// The broad class of the error.
+    ///
+    /// Unknown codes are classified by their HTTP status prefix
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            DarajaErrorCode::InvalidAuthenticationType
+            | DarajaErrorCode::InvalidGrantType
+            | DarajaErrorCode::InvalidCredentials
+            | DarajaErrorCode::InvalidAccessToken
+            | DarajaErrorCode::InvalidAuthenticationHeader => ErrorCategory::Auth,
+            DarajaErrorCode::InvalidRequestPayload | DarajaErrorCode::ResourceNotFound => ErrorCategory::Validation,
+            DarajaErrorCode::SystemBusy | DarajaErrorCode::QuotaViolation => ErrorCategory::Throttling,
+            DarajaErrorCode::ServerError
+            | DarajaErrorCode::ServiceUnreachable
+            | DarajaErrorCode::InternalServerError => ErrorCategory::Internal,
+            DarajaErrorCode::Unknown(code) => match code.split('.').next().unwrap_or_default() {
+                "401" | "403" => ErrorCategory::Auth,
+                "429" => ErrorCategory::Throttling,
+                status if status.starts_with('4') => ErrorCategory::Validation,
+                _ => ErrorCategory::Internal,
+            },
+        }
+    }
+
+    /// A human readable hint on how to resolve the error
+    pub fn hint(&self) -> &'static str {
+        match self {
+            DarajaErrorCode::InvalidRequestPayload => {
+                "Check that all required fields of the request are set and correctly formatted"
+            }
+            DarajaErrorCode::InvalidAuthenticationType => {
+                "Authenticate with HTTP Basic auth using the consumer key and secret"
+            }
+            DarajaErrorCode::InvalidGrantType => "Request the token with `grant_type=client_credentials`",
+            DarajaErrorCode::InvalidCredentials | DarajaErrorCode::InvalidAccessToken => {
+                "Generate a new access token, and check that the consumer key and secret belong to the environment in use"
+            }
+            DarajaErrorCode::ResourceNotFound => "Check the request URL and the environment the client points to",
+            DarajaErrorCode::InvalidAuthenticationHeader => "Send the access token as `Authorization: Bearer <token>`",
+            DarajaErrorCode::ServerError => {
+                "Check the error message, the transaction may still be processing or the subscriber may be busy"
+            }
+            DarajaErrorCode::ServiceUnreachable => "Retry the request later, the service is temporarily unavailable",
+            DarajaErrorCode::InternalServerError => "Retry the request later, or contact Safaricom if it persists",
+            DarajaErrorCode::SystemBusy => "Retry the request after a short delay",
+            DarajaErrorCode::QuotaViolation => "Reduce the request rate and retry after a short delay",
+            DarajaErrorCode::Unknown(_) => "Refer to the error message and the Daraja documentation",
+        }
+    }
+}
+
+impl From<&str> for DarajaErrorCode {
+    fn from(code: &str) -> Self {
+        match code.trim() {
+            "400.002.02" => DarajaErrorCode::InvalidRequestPayload,
+            "400.008.01" => DarajaErrorCode::InvalidAuthenticationType,
+            "400.008.02" => DarajaErrorCode::InvalidGrantType,
+            "401.002.01" => DarajaErrorCode::InvalidCredentials,
+            "404.001.01" => DarajaErrorCode::ResourceNotFound,
+            "404.001.03" => DarajaErrorCode::InvalidAccessToken,
+            "404.001.04" => DarajaErrorCode::InvalidAuthenticationHeader,
+            "500.001.1001" => DarajaErrorCode::ServerError,
+            "500.002.1001" => DarajaErrorCode::ServiceUnreachable,
+            "500.003.01" => DarajaErrorCode::InternalServerError,
+            "500.003.02" => DarajaErrorCode::SystemBusy,
+            "500.003.03" => DarajaErrorCode::QuotaViolation,
+            code => DarajaErrorCode::Unknown(code.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for DarajaErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum BuilderError {
     #[error("Field [{0}] is required")]
@@ -93,16 +269,28 @@ impl From<url::ParseError> for MpesaError {
 }
 
 impl MpesaError {
+    /// Checks if the error means that the access token the request was sent with was rejected
+    pub(crate) fn is_auth_rejection(&self) -> bool {
+        match self {
+            MpesaError::Unauthorized(_) => true,
+            MpesaError::Service(res) => res.code().category() == ErrorCategory::Auth,
+            _ => false,
+        }
+    }
+
     pub fn to_retryable<E: Into<MpesaError>>(val: E) -> backoff::Error<Self> {
         let val = val.into();
         match &val {
             MpesaError::TransientError => backoff::Error::transient(val),
+            MpesaError::NetworkError(err) if err.is_connect() || err.is_timeout() => backoff::Error::transient(val),
             MpesaError::Service(res) => {
-                match res.error_code.as_str() {
+                match res.code() {
                     // system busy|quota violation or spike arrest violation
-                    "500.003.02" | "500.003.03" => backoff::Error::retry_after(val, std::time::Duration::from_secs(1)),
+                    DarajaErrorCode::SystemBusy | DarajaErrorCode::QuotaViolation => {
+                        backoff::Error::retry_after(val, std::time::Duration::from_secs(1))
+                    }
                     // transaction already in progress
-                    "500.001.1001" if res.error_message.contains("Unable to lock subscriber") => {
+                    DarajaErrorCode::ServerError if res.error_message.contains("Unable to lock subscriber") => {
                         backoff::Error::retry_after(val, std::time::Duration::from_secs(1))
                     }
                     _ => backoff::Error::permanent(val),
@@ -151,3 +339,66 @@ impl TryFrom<MpesaError> for backoff::Error<reqwest::Error> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daraja_error_code_round_trips() {
+        for code in [
+            "400.002.02",
+            "400.008.01",
+            "400.008.02",
+            "401.002.01",
+            "404.001.01",
+            "404.001.03",
+            "404.001.04",
+            "500.001.1001",
+            "500.002.1001",
+            "500.003.01",
+            "500.003.02",
+            "500.003.03",
+        ] {
+            let parsed = DarajaErrorCode::from(code);
+            assert!(
+                !matches!(parsed, DarajaErrorCode::Unknown(_)),
+                "{code} is not catalogued"
+            );
+            assert_eq!(parsed.to_string(), code);
+        }
+    }
+
+    #[test]
+    fn test_daraja_error_code_categories() {
+        assert_eq!(DarajaErrorCode::InvalidAccessToken.category(), ErrorCategory::Auth);
+        assert_eq!(
+            DarajaErrorCode::InvalidRequestPayload.category(),
+            ErrorCategory::Validation
+        );
+        assert_eq!(DarajaErrorCode::QuotaViolation.category(), ErrorCategory::Throttling);
+        assert_eq!(DarajaErrorCode::InternalServerError.category(), ErrorCategory::Internal);
+
+        let unknown = DarajaErrorCode::from("401.003.01");
+        assert_eq!(unknown, DarajaErrorCode::Unknown("401.003.01".to_owned()));
+        assert_eq!(unknown.category(), ErrorCategory::Auth);
+        assert_eq!(
+            DarajaErrorCode::from("400.002.05").category(),
+            ErrorCategory::Validation
+        );
+        assert_eq!(DarajaErrorCode::from("garbage").category(), ErrorCategory::Internal);
+    }
+
+    #[test]
+    fn test_response_error_code() {
+        let error: ResponseError = serde_json::from_str(
+            r#"{"requestId": "11728-2929992-1", "errorCode": "500.003.02", "errorMessage": "System is busy"}"#,
+        )
+        .unwrap();
+        assert_eq!(error.code(), DarajaErrorCode::SystemBusy);
+        assert!(matches!(
+            MpesaError::to_retryable(MpesaError::Service(error)),
+            backoff::Error::Transient { .. }
+        ));
+    }
+}
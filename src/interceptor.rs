@@ -0,0 +1,115 @@
+//!# Request and response interceptors
+//!
+//! An [`Interceptor`] registered on a client with [`MpesaBuilder::interceptor`](crate::MpesaBuilder::interceptor)
+//! is called around every request sent to the Safaricom API, which allows e.g audit logging, custom metrics or
+//! adding headers without patching the crate.
+//!
+//! Interceptors see every attempt of a request, including retries, but not the requests fetching access tokens.
+
+use std::fmt::{self, Debug};
+use std::time::Duration;
+
+pub use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode};
+
+use crate::errors::MpesaResult;
+use crate::redact;
+
+/// A request about to be sent to the Safaricom API
+pub struct OutgoingRequest<'a> {
+    pub method: &'a Method,
+    /// The path of the endpoint, relative to the base url of the environment
+    pub path: &'static str,
+    /// The JSON body of the request
+    pub body: &'a serde_json::Value,
+    /// The headers sent with the request. The `Authorization` header is added after the interceptors ran
+    pub headers: HeaderMap,
+}
+
+impl Debug for OutgoingRequest<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut body = self.body.clone();
+        redact::json(&mut body);
+        f.debug_struct("OutgoingRequest")
+            .field("method", &self.method)
+            .field("path", &self.path)
+            .field("body", &body)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+/// A response received from the Safaricom API
+pub struct IncomingResponse<'a> {
+    pub method: &'a Method,
+    /// The path of the endpoint, relative to the base url of the environment
+    pub path: &'static str,
+    pub status: StatusCode,
+    pub headers: &'a HeaderMap,
+    /// The raw body of the response, before it is decoded
+    pub body: &'a str,
+    /// How long it took from sending the request until the body of the response was read
+    pub elapsed: Duration,
+}
+
+impl Debug for IncomingResponse<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncomingResponse")
+            .field("method", &self.method)
+            .field("path", &self.path)
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .field("body", &redact::body(self.body))
+            .field("elapsed", &self.elapsed)
+            .finish()
+    }
+}
+
+/// Hooks called before a request is sent to the Safaricom API and after its response is received.
+///
+/// Interceptors run in the order they were registered in.
+///
+/// # Example
+///
+/// ```rust
+/// use mpesa::interceptor::{Interceptor, IncomingResponse, OutgoingRequest, async_trait};
+/// use mpesa::{Environment, Mpesa, MpesaError, MpesaResult};
+///
+/// #[derive(Debug)]
+/// struct AuditLog;
+///
+/// #[async_trait]
+/// impl Interceptor for AuditLog {
+///     async fn before_send(&self, request: &mut OutgoingRequest<'_>) -> MpesaResult<()> {
+///         if request.path.starts_with("mpesa/b2c") && request.body["Amount"].as_u64() > Some(100_000) {
+///             return Err(MpesaError::Rejected("B2C amount exceeds the allowed limit".to_owned()));
+///         }
+///         request.headers.insert("X-Request-Source", "payments-service".parse().unwrap());
+///         Ok(())
+///     }
+///
+///     async fn after_receive(&self, response: &IncomingResponse<'_>) {
+///         println!("{} /{} -> {} in {:?}", response.method, response.path, response.status, response.elapsed);
+///     }
+/// }
+///
+/// let client = Mpesa::builder("consumer_key", "consumer_secret", Environment::Sandbox)
+///     .interceptor(AuditLog)
+///     .build()
+///     .unwrap();
+/// ```
+#[async_trait]
+pub trait Interceptor: Debug + Send + Sync {
+    /// Called before the request is sent. Headers can be added to `request`.
+    ///
+    /// # Errors
+    /// Returning an error rejects the request, which is then neither sent nor retried. The error is returned to
+    /// the caller as is, [`MpesaError::Rejected`](crate::MpesaError::Rejected) is meant for this
+    async fn before_send(&self, _request: &mut OutgoingRequest<'_>) -> MpesaResult<()> {
+        Ok(())
+    }
+
+    /// Called after the response is received, before it is decoded
+    async fn after_receive(&self, _response: &IncomingResponse<'_>) {}
+}
@@ -15,6 +15,14 @@
 //! - **dynamic_qr** *(enabled by default)*: Enables Dynamic QR generation service support.
 //! - **transaction_reversal** *(enabled by default)*: Enables Transaction Reversal service support.
 //! - **transaction_status** *(enabled by default)*: Enables Transaction Status service support.
+//! - **callbacks_server**: Enables an embedded HTTP server that receives M-Pesa callbacks and routes them to a
+//!   user supplied handler.
+//! - **tower**: Implements `tower::Service` for the client, so that tower layers such as timeouts and concurrency
+//!   limits can wrap calls to the Safaricom API.
+//! - **metrics**: Enables [`metrics::FacadeRecorder`], which reports measurements of requests to the `metrics`
+//!   crate facade.
+//! - **tracing**: Records every request to the Safaricom API in a `tracing` span, with phone numbers and
+//!   credentials redacted.
 //! - **no_openssl** *(enabled by default)*: Disables the dependency on `openssl` as the crate for handling mpesa
 //!   certificates and base64 encoding, instead using the `x509-parser`, `rsa`, `base64` and `rand` crates.
 //!   Automatically enabled when any of the features *account_balance*, *b2b*, *b2c*, *bill_manager*, *express*,
@@ -23,15 +31,25 @@
 //!   of the default.
 
 mod auth;
+pub mod callbacks;
 mod client;
+pub mod config;
 mod constants;
 pub mod environment;
 mod errors;
+pub mod interceptor;
+pub mod metrics;
+pub mod redact;
+pub mod registry;
+mod request;
+mod retry;
 pub mod services;
+pub mod token_store;
 pub mod validator;
 
-pub use client::Mpesa;
-pub use constants::{CommandId, IdentifierTypes, ResponseType, SendRemindersTypes, TransactionType};
+pub use client::{Mpesa, MpesaBuilder};
+pub use config::MpesaConfig;
+pub use constants::{CommandId, IdentifierTypes, MpesaResponseCode, ResponseType, SendRemindersTypes, TransactionType};
 #[cfg(feature = "bill_manager")]
 #[cfg_attr(docsrs, doc(cfg(feature = "bill_manager")))]
 pub use constants::{Invoice, InvoiceItem};
@@ -40,4 +58,9 @@ pub use environment::Environment::{self, Production, Sandbox};
 #[cfg(feature = "no_openssl")]
 #[cfg_attr(docsrs, doc(cfg(feature = "no_openssl")))]
 pub use errors::EncryptionErrors;
-pub use errors::{BuilderError, MpesaError, MpesaResult, ResponseError};
+pub use errors::{BuilderError, DarajaErrorCode, ErrorCategory, MpesaError, MpesaResult, ResponseError};
+pub use interceptor::Interceptor;
+pub use registry::MpesaRegistry;
+pub use request::MpesaRequest;
+pub use retry::RetryPolicy;
+pub use token_store::{AccessToken, FileTokenStore, InMemoryTokenStore, TokenStore};
@@ -0,0 +1,336 @@
+//!# Metrics
+//!
+//! A [`MetricsRecorder`] registered on a client with [`MpesaBuilder::metrics`](crate::MpesaBuilder::metrics) is
+//! told about every request sent to the Safaricom API, every retry, access token refresh, error code and
+//! throttled request, so that a degraded API can be alerted on.
+//!
+//! The crate ships with:
+//! 1. [`InMemoryMetricsRecorder`], which keeps counts and latencies in memory e.g to assert on them in tests
+//! 2. [`FacadeRecorder`], with the `metrics` feature, which forwards to the [`metrics`](https://docs.rs/metrics)
+//!    crate facade and from there to any exporter e.g Prometheus
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Path of the endpoint access tokens are requested from, reported as the endpoint of token refreshes
+pub const AUTH_ENDPOINT: &str = "oauth/v1/generate";
+
+/// Receives measurements of the requests sent by a client.
+///
+/// Endpoints are paths relative to the base url of the environment e.g `mpesa/b2c/v3/paymentrequest`.
+/// Every method defaults to doing nothing, so that recorders only implement what they need. Methods are called
+/// on the hot path of every request and should not block.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mpesa::metrics::MetricsRecorder;
+/// use mpesa::{Environment, Mpesa};
+///
+/// #[derive(Debug)]
+/// struct SlowRequestLog;
+///
+/// impl MetricsRecorder for SlowRequestLog {
+///     fn record_request(&self, endpoint: &'static str, status: Option<u16>, latency: Duration) {
+///         if latency > Duration::from_secs(5) {
+///             println!("slow request to {endpoint}: {status:?} in {latency:?}");
+///         }
+///     }
+/// }
+///
+/// let client = Mpesa::builder("consumer_key", "consumer_secret", Environment::Sandbox)
+///     .metrics(SlowRequestLog)
+///     .build()
+///     .unwrap();
+/// ```
+pub trait MetricsRecorder: Debug + Send + Sync {
+    /// Called after every attempt at sending a request, retries included.
+    /// `status` is `None` if no response was received e.g on connection failures
+    fn record_request(&self, _endpoint: &'static str, _status: Option<u16>, _latency: Duration) {}
+
+    /// Called when a failed attempt at sending a request is about to be retried
+    fn record_retry(&self, _endpoint: &'static str) {}
+
+    /// Called after a new access token was requested, including its retries
+    fn record_auth_refresh(&self, _success: bool, _latency: Duration) {}
+
+    /// Called when the API responds with an error code e.g `500.003.02`
+    fn record_error(&self, _endpoint: &'static str, _error_code: &str) {}
+
+    /// Called when the API rejects a request due to load or rate limits
+    fn record_throttled(&self, _endpoint: &'static str) {}
+}
+
+/// The recorder of clients that have none registered
+#[derive(Debug)]
+pub(crate) struct NoopMetricsRecorder;
+
+impl MetricsRecorder for NoopMetricsRecorder {}
+
+#[derive(Debug, Default)]
+struct Measurements {
+    requests: HashMap<&'static str, u64>,
+    latencies: HashMap<&'static str, Vec<Duration>>,
+    retries: HashMap<&'static str, u64>,
+    auth_refreshes: u64,
+    auth_failures: u64,
+    errors: HashMap<&'static str, HashMap<String, u64>>,
+    throttled: HashMap<&'static str, u64>,
+}
+
+/// Keeps measurements in memory.
+///
+/// Clones of the recorder share the same measurements, so a clone can be registered on a client and the
+/// original asserted on.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryMetricsRecorder {
+    measurements: Arc<RwLock<Measurements>>,
+}
+
+impl InMemoryMetricsRecorder {
+    pub fn new() -> InMemoryMetricsRecorder {
+        InMemoryMetricsRecorder::default()
+    }
+
+    /// The number of attempts at sending requests to `endpoint`, retries included
+    pub fn requests(&self, endpoint: &str) -> u64 {
+        self.measurements
+            .read()
+            .unwrap()
+            .requests
+            .get(endpoint)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The latencies of the attempts at sending requests to `endpoint`, in the order they were sent
+    pub fn latencies(&self, endpoint: &str) -> Vec<Duration> {
+        self.measurements
+            .read()
+            .unwrap()
+            .latencies
+            .get(endpoint)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The number of retries of requests to `endpoint`
+    pub fn retries(&self, endpoint: &str) -> u64 {
+        self.measurements
+            .read()
+            .unwrap()
+            .retries
+            .get(endpoint)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The number of access token refreshes, failed ones included
+    pub fn auth_refreshes(&self) -> u64 {
+        self.measurements.read().unwrap().auth_refreshes
+    }
+
+    /// The number of failed access token refreshes
+    pub fn auth_failures(&self) -> u64 {
+        self.measurements.read().unwrap().auth_failures
+    }
+
+    /// The number of responses from `endpoint` with the error code `error_code`
+    pub fn errors(&self, endpoint: &str, error_code: &str) -> u64 {
+        self.measurements
+            .read()
+            .unwrap()
+            .errors
+            .get(endpoint)
+            .and_then(|errors| errors.get(error_code))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The number of throttled requests to `endpoint`
+    pub fn throttled(&self, endpoint: &str) -> u64 {
+        self.measurements
+            .read()
+            .unwrap()
+            .throttled
+            .get(endpoint)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+impl MetricsRecorder for InMemoryMetricsRecorder {
+    fn record_request(&self, endpoint: &'static str, _status: Option<u16>, latency: Duration) {
+        let mut measurements = self.measurements.write().unwrap();
+        *measurements.requests.entry(endpoint).or_default() += 1;
+        measurements.latencies.entry(endpoint).or_default().push(latency);
+    }
+
+    fn record_retry(&self, endpoint: &'static str) {
+        *self.measurements.write().unwrap().retries.entry(endpoint).or_default() += 1;
+    }
+
+    fn record_auth_refresh(&self, success: bool, _latency: Duration) {
+        let mut measurements = self.measurements.write().unwrap();
+        measurements.auth_refreshes += 1;
+        if !success {
+            measurements.auth_failures += 1;
+        }
+    }
+
+    fn record_error(&self, endpoint: &'static str, error_code: &str) {
+        *self
+            .measurements
+            .write()
+            .unwrap()
+            .errors
+            .entry(endpoint)
+            .or_default()
+            .entry(error_code.to_owned())
+            .or_default() += 1;
+    }
+
+    fn record_throttled(&self, endpoint: &'static str) {
+        *self
+            .measurements
+            .write()
+            .unwrap()
+            .throttled
+            .entry(endpoint)
+            .or_default() += 1;
+    }
+}
+
+/// Forwards measurements to the [`metrics`](https://docs.rs/metrics) crate facade, as:
+///
+/// - `mpesa_requests_total`, a counter labelled with the `endpoint` and HTTP `status`, which is `none` when no
+///   response was received
+/// - `mpesa_request_duration_seconds`, a histogram labelled with the `endpoint`
+/// - `mpesa_retries_total`, a counter labelled with the `endpoint`
+/// - `mpesa_auth_refreshes_total`, a counter labelled with the `outcome`, either `success` or `failure`
+/// - `mpesa_auth_refresh_duration_seconds`, a histogram
+/// - `mpesa_errors_total`, a counter labelled with the `endpoint` and Daraja `error_code`
+/// - `mpesa_throttled_total`, a counter labelled with the `endpoint`
+#[cfg(feature = "metrics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FacadeRecorder;
+
+#[cfg(feature = "metrics")]
+impl MetricsRecorder for FacadeRecorder {
+    fn record_request(&self, endpoint: &'static str, status: Option<u16>, latency: Duration) {
+        let status = status.map_or_else(|| "none".to_owned(), |status| status.to_string());
+        ::metrics::counter!("mpesa_requests_total", "endpoint" => endpoint, "status" => status).increment(1);
+        ::metrics::histogram!("mpesa_request_duration_seconds", "endpoint" => endpoint).record(latency);
+    }
+
+    fn record_retry(&self, endpoint: &'static str) {
+        ::metrics::counter!("mpesa_retries_total", "endpoint" => endpoint).increment(1);
+    }
+
+    fn record_auth_refresh(&self, success: bool, latency: Duration) {
+        let outcome = if success { "success" } else { "failure" };
+        ::metrics::counter!("mpesa_auth_refreshes_total", "outcome" => outcome).increment(1);
+        ::metrics::histogram!("mpesa_auth_refresh_duration_seconds").record(latency);
+    }
+
+    fn record_error(&self, endpoint: &'static str, error_code: &str) {
+        ::metrics::counter!("mpesa_errors_total", "endpoint" => endpoint, "error_code" => error_code.to_owned())
+            .increment(1);
+    }
+
+    fn record_throttled(&self, endpoint: &'static str) {
+        ::metrics::counter!("mpesa_throttled_total", "endpoint" => endpoint).increment(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_recorder_counts_per_endpoint() {
+        let recorder = InMemoryMetricsRecorder::new();
+        let clone = recorder.clone();
+        clone.record_request("mpesa/b2c/v3/paymentrequest", Some(200), Duration::from_millis(120));
+        clone.record_request("mpesa/b2c/v3/paymentrequest", None, Duration::from_millis(10));
+        clone.record_error("mpesa/b2c/v3/paymentrequest", "500.003.02");
+        clone.record_auth_refresh(false, Duration::from_millis(50));
+
+        assert_eq!(recorder.requests("mpesa/b2c/v3/paymentrequest"), 2);
+        assert_eq!(
+            recorder.latencies("mpesa/b2c/v3/paymentrequest"),
+            vec![Duration::from_millis(120), Duration::from_millis(10)]
+        );
+        assert_eq!(recorder.errors("mpesa/b2c/v3/paymentrequest", "500.003.02"), 1);
+        assert_eq!(recorder.errors("mpesa/b2c/v3/paymentrequest", "500.003.03"), 0);
+        assert_eq!(recorder.requests("mpesa/b2b/v1/paymentrequest"), 0);
+        assert_eq!((recorder.auth_refreshes(), recorder.auth_failures()), (1, 1));
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_facade_recorder_reports_to_the_metrics_crate() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        ::metrics::with_local_recorder(&recorder, || {
+            FacadeRecorder.record_request("mpesa/b2c/v3/paymentrequest", Some(200), Duration::from_millis(250));
+            FacadeRecorder.record_request("mpesa/b2c/v3/paymentrequest", None, Duration::from_millis(10));
+            FacadeRecorder.record_throttled("mpesa/b2c/v3/paymentrequest");
+            FacadeRecorder.record_error("mpesa/b2c/v3/paymentrequest", "500.003.03");
+            FacadeRecorder.record_auth_refresh(true, Duration::from_millis(80));
+        });
+
+        let mut metrics: Vec<_> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let key = key.key();
+                let labels: Vec<_> = key.labels().map(|l| format!("{}={}", l.key(), l.value())).collect();
+                (key.name().to_owned(), labels.join(","), value)
+            })
+            .collect();
+        metrics.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        let counters: Vec<_> = metrics
+            .iter()
+            .filter_map(|(name, labels, value)| match value {
+                DebugValue::Counter(n) => Some((name.as_str(), labels.as_str(), *n)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            counters,
+            vec![
+                ("mpesa_auth_refreshes_total", "outcome=success", 1),
+                (
+                    "mpesa_errors_total",
+                    "endpoint=mpesa/b2c/v3/paymentrequest,error_code=500.003.03",
+                    1
+                ),
+                (
+                    "mpesa_requests_total",
+                    "endpoint=mpesa/b2c/v3/paymentrequest,status=200",
+                    1
+                ),
+                (
+                    "mpesa_requests_total",
+                    "endpoint=mpesa/b2c/v3/paymentrequest,status=none",
+                    1
+                ),
+                ("mpesa_throttled_total", "endpoint=mpesa/b2c/v3/paymentrequest", 1),
+            ]
+        );
+        let histogram = metrics
+            .iter()
+            .find(|(name, _, _)| name == "mpesa_request_duration_seconds")
+            .map(|(_, _, value)| value);
+        assert_eq!(histogram, Some(&DebugValue::Histogram(vec![0.25.into(), 0.01.into()])));
+    }
+}
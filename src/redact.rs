@@ -0,0 +1,294 @@
+//!# Redaction of personal data and credentials
+//!
+//! Request and response bodies of the Safaricom API carry phone numbers (MSISDNs), customer names and
+//! credentials. The helpers in this module mask them before bodies are logged or recorded in traces, and in the
+//! `Debug` output of the types of the crate. They are public so that [interceptors](crate::interceptor) and
+//! callback handlers can do the same.
+//!
+//! # Example
+//!
+//! ```rust
+//! use mpesa::redact;
+//! use serde_json::json;
+//!
+//! assert_eq!(redact::msisdn("254708374149"), "254******149");
+//! assert_eq!(redact::text("Refund to 0708374149 for order 42"), "Refund to 070****149 for order 42");
+//!
+//! let mut body = json!({
+//!     "PartyA": "600981",
+//!     "PhoneNumber": 254708374149u64,
+//!     "Password": "c2VjcmV0",
+//!     "ResultParameter": [{ "Key": "ReceiverPartyPublicName", "Value": "254708374149 - John Doe" }]
+//! });
+//! redact::json(&mut body);
+//! assert_eq!(
+//!     body,
+//!     json!({
+//!         "PartyA": "600981",
+//!         "PhoneNumber": "254******149",
+//!         "Password": "[REDACTED]",
+//!         "ResultParameter": [{ "Key": "ReceiverPartyPublicName", "Value": "[REDACTED]" }]
+//!     })
+//! );
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+/// Replaces values that are redacted as a whole
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Kenyan MSISDNs in international or local format e.g `254708374149`, `+254708374149` or `0708374149`
+static MSISDN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(?:254|0)[17]\d{8}\b").unwrap());
+
+/// Keys whose values are always redacted as a whole: credentials and customer names.
+///
+/// They are matched against object keys, and against the `Key` or `Name` of the `{"Key": .., "Value": ..}` pairs
+/// found in result parameters and callback metadata
+const SENSITIVE_KEYS: &[&str] = &[
+    "securitycredential",
+    "security_credential",
+    "password",
+    "initiatorpassword",
+    "initiator_password",
+    "passkey",
+    "pass_key",
+    "access_token",
+    "consumer_secret",
+    "firstname",
+    "middlename",
+    "lastname",
+    "receiverpartypublicname",
+];
+
+/// Masks all but the first and last 3 characters of a phone number e.g `254708374149` becomes `254******149`
+pub fn msisdn(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 6 {
+        return "*".repeat(chars.len());
+    }
+    let (head, rest) = chars.split_at(3);
+    let (middle, tail) = rest.split_at(rest.len() - 3);
+    format!(
+        "{}{}{}",
+        head.iter().collect::<String>(),
+        "*".repeat(middle.len()),
+        tail.iter().collect::<String>()
+    )
+}
+
+/// Masks every phone number found in free text
+pub fn text(value: &str) -> Cow<'_, str> {
+    MSISDN.replace_all(value, |caps: &Captures| msisdn(&caps[0]))
+}
+
+/// Redacts a JSON value in place.
+///
+/// Credentials and customer names are replaced with [`REDACTED`] and phone numbers are masked, whether they
+/// are strings, numbers or part of a longer string. This includes the `Value` of `Key`/`Value` and
+/// `Name`/`Value` pairs such as `{"Key": "ReceiverPartyPublicName", "Value": "254708374149 - John Doe"}`.
+pub fn json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            let sensitive_pair = map
+                .iter()
+                .any(|(key, name)| (key == "Key" || key == "Name") && name.as_str().is_some_and(is_sensitive));
+            for (key, value) in map.iter_mut() {
+                redact_value(value, is_sensitive(key) || (sensitive_pair && key == "Value"));
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(json),
+        Value::String(s) => {
+            if let Cow::Owned(redacted) = text(s) {
+                *s = redacted;
+            }
+        }
+        Value::Number(n) => {
+            let n = n.to_string();
+            if MSISDN.is_match(&n) {
+                *value = Value::String(msisdn(&n));
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+}
+
+fn is_sensitive(key: &str) -> bool {
+    SENSITIVE_KEYS.contains(&key.to_ascii_lowercase().as_str())
+}
+
+fn redact_value(value: &mut Value, sensitive: bool) {
+    if sensitive && !value.is_null() {
+        *value = Value::String(REDACTED.to_owned());
+    } else {
+        json(value);
+    }
+}
+
+/// Redacts a copy of the `Value` of a `Key`/`Value` or `Name`/`Value` pair, as [`json`] would within the pair
+pub(crate) fn pair_value(key: &str, value: &Value) -> Value {
+    let mut value = value.clone();
+    redact_value(&mut value, is_sensitive(key));
+    value
+}
+
+/// Redacts a raw HTTP body, as JSON if it can be parsed and as free text otherwise
+pub fn body(body: &str) -> String {
+    match serde_json::from_str::<Value>(body) {
+        Ok(mut value) => {
+            json(&mut value);
+            value.to_string()
+        }
+        Err(_) => text(body).into_owned(),
+    }
+}
+
+/// Formats a secret as [`REDACTED`] in `Debug` output
+pub(crate) struct Secret;
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Formats a phone number masked with [`msisdn`] in `Debug` output
+pub(crate) struct Msisdn<'a>(pub(crate) &'a str);
+
+impl fmt::Debug for Msisdn<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&msisdn(self.0), f)
+    }
+}
+
+/// Formats an optional secret as `Some([REDACTED])` or `None` in `Debug` output
+pub(crate) fn secret<T>(value: &Option<T>) -> Option<Secret> {
+    value.as_ref().map(|_| Secret)
+}
+
+/// Formats an optional phone number masked with [`msisdn`] in `Debug` output
+pub(crate) fn optional_msisdn<S: AsRef<str>>(value: &Option<S>) -> Option<Msisdn<'_>> {
+    value.as_ref().map(|value| Msisdn(value.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_msisdn() {
+        assert_eq!(msisdn("254708374149"), "254******149");
+        assert_eq!(msisdn("0708374149"), "070****149");
+        assert_eq!(msisdn("600981"), "******");
+        assert_eq!(msisdn(""), "");
+    }
+
+    #[test]
+    fn test_text_masks_phone_numbers_only() {
+        assert_eq!(text("+254708374149, 0112345678"), "+254******149, 011****678");
+        assert_eq!(
+            text("ws_CO_191220191020363925 at 600981"),
+            "ws_CO_191220191020363925 at 600981"
+        );
+    }
+
+    #[test]
+    fn test_json_redacts_nested_values() {
+        let mut value = json!({
+            "Body": {
+                "stkCallback": {
+                    "CallbackMetadata": {
+                        "Item": [
+                            { "Name": "Amount", "Value": 1.0 },
+                            { "Name": "PhoneNumber", "Value": 254708374149u64 }
+                        ]
+                    }
+                }
+            },
+            "SecurityCredential": "EsJocK7+NjqZPC3I3EO+TbvS+xVb9TymWwaKABoaZr",
+            "FirstName": "John",
+            "Remarks": "Refund to 0708374149",
+            "ReferenceData": null
+        });
+        json(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "Body": {
+                    "stkCallback": {
+                        "CallbackMetadata": {
+                            "Item": [
+                                { "Name": "Amount", "Value": 1.0 },
+                                { "Name": "PhoneNumber", "Value": "254******149" }
+                            ]
+                        }
+                    }
+                },
+                "SecurityCredential": REDACTED,
+                "FirstName": REDACTED,
+                "Remarks": "Refund to 070****149",
+                "ReferenceData": null
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_redacts_values_of_sensitive_pairs() {
+        let mut value = json!({
+            "Result": {
+                "ResultType": 0,
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully.",
+                "OriginatorConversationID": "10571-7910404-1",
+                "ConversationID": "AG_20191219_00004e48cf7e3533f581",
+                "TransactionID": "NLJ41HAY6Q",
+                "ResultParameters": {
+                    "ResultParameter": [
+                        { "Key": "TransactionAmount", "Value": 10 },
+                        { "Key": "TransactionReceipt", "Value": "NLJ41HAY6Q" },
+                        { "Key": "B2CRecipientIsRegisteredCustomer", "Value": "Y" },
+                        { "Key": "B2CChargesPaidAccountAvailableFunds", "Value": -4510.00 },
+                        { "Key": "ReceiverPartyPublicName", "Value": "254708374149 - John Doe" },
+                        { "Key": "TransactionCompletedDateTime", "Value": "19.12.2019 11:45:50" },
+                        { "Key": "B2CUtilityAccountAvailableFunds", "Value": 10116.00 },
+                        { "Key": "B2CWorkingAccountAvailableFunds", "Value": 900000.00 }
+                    ]
+                },
+                "ReferenceData": {
+                    "ReferenceItem": {
+                        "Key": "QueueTimeoutURL",
+                        "Value": "https://internalsandbox.safaricom.co.ke/mpesa/b2cresults/v1/submit"
+                    }
+                }
+            }
+        });
+        let expected = {
+            let mut expected = value.clone();
+            expected["Result"]["ResultParameters"]["ResultParameter"][4]["Value"] = json!(REDACTED);
+            expected
+        };
+        json(&mut value);
+        assert_eq!(value, expected);
+        assert!(!value.to_string().contains("John Doe"));
+    }
+
+    #[test]
+    fn test_debug_helpers() {
+        assert_eq!(format!("{:?}", Secret), "[REDACTED]");
+        assert_eq!(format!("{:?}", Msisdn("254708374149")), r#""254******149""#);
+        assert_eq!(format!("{:?}", secret(&Some("pass"))), "Some([REDACTED])");
+        assert_eq!(format!("{:?}", optional_msisdn::<String>(&None)), "None");
+    }
+
+    #[test]
+    fn test_body_falls_back_to_text() {
+        assert_eq!(body(r#"{"MSISDN":"254708374149"}"#), r#"{"MSISDN":"254******149"}"#);
+        assert_eq!(body("<html>254708374149</html>"), "<html>254******149</html>");
+    }
+}
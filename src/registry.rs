@@ -0,0 +1,285 @@
+//!# Multi-tenant client registry
+//!
+//! An [`Mpesa`] client holds the credentials of a single Daraja app. Businesses that run several paybills and
+//! tills, each with its own app, initiator and passkey, register them with an [`MpesaRegistry`] and look up the
+//! client of a request by tenant id or shortcode. Clients are built on first use and kept for the lifetime of
+//! the registry, along with their access tokens.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use mpesa::{MpesaConfig, MpesaRegistry};
+//! use serde_json::json;
+//!
+//! #[tokio::main]
+//! async fn main() -> mpesa::MpesaResult<()> {
+//!     let mut registry = MpesaRegistry::new();
+//!     let config: MpesaConfig = serde_json::from_value(json!({
+//!         "consumer_key": "...",
+//!         "consumer_secret": "...",
+//!         "environment": "production",
+//!         "initiator_name": "acme_api",
+//!         "initiator_password": "...",
+//!         "party_a": "600000",
+//!         "passkey": "..."
+//!     }))?;
+//!     registry.register("acme", config);
+//!
+//!     let response = registry
+//!         .for_shortcode("600000")?
+//!         .b2c("acme_api")
+//!         .party_a("600000")
+//!         .originator_conversation_id("29464-48063588-1")
+//!         .party_b("254708374149")
+//!         .result_url("https://example.com/mpesa/result")
+//!         .timeout_url("https://example.com/mpesa/timeout")
+//!         .amount(1000)
+//!         .send()
+//!         .await?;
+//!     println!("{}", response.conversation_id);
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+use crate::client::{Mpesa, MpesaBuilder};
+use crate::config::MpesaConfig;
+use crate::errors::{MpesaError, MpesaResult};
+use crate::token_store::{InMemoryTokenStore, TokenStore};
+
+type BuildFn = dyn Fn(&MpesaConfig) -> MpesaBuilder + Send + Sync;
+
+struct Tenant {
+    config: MpesaConfig,
+    build: Box<BuildFn>,
+    client: OnceLock<Mpesa>,
+}
+
+/// [`Mpesa`] clients of several tenants, keyed by tenant id and by the shortcodes of each tenant.
+///
+/// The `business_short_code` and `party_a` of the configuration of a tenant are its shortcodes. When two tenants
+/// share a shortcode, it belongs to the one registered last.
+pub struct MpesaRegistry {
+    tenants: HashMap<String, Tenant>,
+    shortcodes: HashMap<String, String>,
+    token_store: Arc<dyn TokenStore>,
+}
+
+impl fmt::Debug for MpesaRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpesaRegistry")
+            .field("tenants", &self.tenants.keys().collect::<Vec<_>>())
+            .field("shortcodes", &self.shortcodes)
+            .field("token_store", &self.token_store)
+            .finish()
+    }
+}
+
+impl Default for MpesaRegistry {
+    fn default() -> Self {
+        MpesaRegistry::new()
+    }
+}
+
+impl MpesaRegistry {
+    /// Creates an empty registry whose clients keep their access tokens in one [`InMemoryTokenStore`]
+    pub fn new() -> MpesaRegistry {
+        MpesaRegistry::with_token_store(InMemoryTokenStore::new())
+    }
+
+    /// Creates an empty registry whose clients keep their access tokens in `token_store`.
+    ///
+    /// Tokens are stored per consumer key and environment, so tenants never see each other's tokens
+    pub fn with_token_store(token_store: impl TokenStore + 'static) -> MpesaRegistry {
+        MpesaRegistry {
+            tenants: HashMap::new(),
+            shortcodes: HashMap::new(),
+            token_store: Arc::new(token_store),
+        }
+    }
+
+    /// Registers a tenant, replacing any tenant registered under the same id.
+    ///
+    /// Its client is built from the credentials, environment, initiator and passkey of `config` on first use
+    pub fn register<S: Into<String>>(&mut self, tenant_id: S, config: MpesaConfig) -> &mut MpesaRegistry {
+        self.register_with(tenant_id, config, |config| {
+            Mpesa::builder(
+                config.consumer_key.as_str(),
+                config.consumer_secret.as_str(),
+                config.environment.clone(),
+            )
+        })
+    }
+
+    /// Registers a tenant whose client is built from the [`MpesaBuilder`] returned by `build`, e.g to set
+    /// timeouts, interceptors or a custom [`ApiEnvironment`](crate::ApiEnvironment).
+    ///
+    /// The token store of the registry and the initiator and passkey of `config` are set on the builder
+    pub fn register_with<S, F>(&mut self, tenant_id: S, config: MpesaConfig, build: F) -> &mut MpesaRegistry
+    where
+        S: Into<String>,
+        F: Fn(&MpesaConfig) -> MpesaBuilder + Send + Sync + 'static,
+    {
+        let tenant_id = tenant_id.into();
+        self.shortcodes.retain(|_, id| *id != tenant_id);
+        for shortcode in [&config.business_short_code, &config.party_a].into_iter().flatten() {
+            self.shortcodes.insert(shortcode.clone(), tenant_id.clone());
+        }
+        self.tenants.insert(
+            tenant_id,
+            Tenant {
+                config,
+                build: Box::new(build),
+                client: OnceLock::new(),
+            },
+        );
+        self
+    }
+
+    /// Removes a tenant and its shortcodes, returning its configuration
+    pub fn deregister(&mut self, tenant_id: &str) -> Option<MpesaConfig> {
+        self.shortcodes.retain(|_, id| id != tenant_id);
+        self.tenants.remove(tenant_id).map(|tenant| tenant.config)
+    }
+
+    /// The ids of the registered tenants
+    pub fn tenants(&self) -> impl Iterator<Item = &str> {
+        self.tenants.keys().map(String::as_str)
+    }
+
+    /// The configuration of a tenant, e.g to read its initiator name or passkey
+    pub fn config(&self, tenant_id: &str) -> Option<&MpesaConfig> {
+        self.tenants.get(tenant_id).map(|tenant| &tenant.config)
+    }
+
+    /// The id of the tenant a shortcode belongs to
+    pub fn tenant_of(&self, shortcode: &str) -> Option<&str> {
+        self.shortcodes.get(shortcode).map(String::as_str)
+    }
+
+    /// The client of a tenant, built on first use
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::UnknownTenant` if no tenant is registered under `tenant_id`, and a
+    /// `MpesaError::NetworkError` if the client cannot be built
+    pub fn for_tenant(&self, tenant_id: &str) -> MpesaResult<&Mpesa> {
+        let tenant = self
+            .tenants
+            .get(tenant_id)
+            .ok_or_else(|| MpesaError::UnknownTenant(tenant_id.to_owned()))?;
+        self.client(tenant)
+    }
+
+    /// The client of the tenant a shortcode belongs to, built on first use
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::UnknownTenant` if `shortcode` belongs to no tenant, and a
+    /// `MpesaError::NetworkError` if the client cannot be built
+    pub fn for_shortcode(&self, shortcode: &str) -> MpesaResult<&Mpesa> {
+        let tenant = self
+            .shortcodes
+            .get(shortcode)
+            .and_then(|tenant_id| self.tenants.get(tenant_id))
+            .ok_or_else(|| MpesaError::UnknownTenant(shortcode.to_owned()))?;
+        self.client(tenant)
+    }
+
+    fn client<'a>(&self, tenant: &'a Tenant) -> MpesaResult<&'a Mpesa> {
+        if let Some(client) = tenant.client.get() {
+            return Ok(client);
+        }
+        let builder = (tenant.build)(&tenant.config).token_store(Arc::clone(&self.token_store));
+        let client = tenant.config.configure(builder).build()?;
+        // When two threads race to build the client, the one stored first is kept
+        Ok(tenant.client.get_or_init(|| client))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Environment;
+
+    fn config(consumer_key: &str, business_short_code: &str, party_a: &str) -> MpesaConfig {
+        MpesaConfig {
+            consumer_key: consumer_key.to_owned(),
+            consumer_secret: "secret".to_owned(),
+            environment: Environment::Sandbox,
+            initiator_name: Some("testapi".to_owned()),
+            initiator_password: Some(format!("{consumer_key}_password")),
+            business_short_code: Some(business_short_code.to_owned()),
+            party_a: Some(party_a.to_owned()),
+            passkey: Some(format!("{consumer_key}_passkey")),
+            express_callback_url: None,
+            c2b_confirmation_url: None,
+            c2b_validation_url: None,
+            result_url: None,
+            timeout_url: None,
+        }
+    }
+
+    fn registry() -> MpesaRegistry {
+        let mut registry = MpesaRegistry::new();
+        registry
+            .register("acme", config("acme_key", "174379", "600000"))
+            .register("globex", config("globex_key", "174380", "600001"));
+        registry
+    }
+
+    #[test]
+    fn test_clients_are_built_once_per_tenant() {
+        let registry = registry();
+        assert!(registry.tenants.values().all(|tenant| tenant.client.get().is_none()));
+
+        let acme = registry.for_tenant("acme").unwrap();
+        assert_eq!(
+            acme.initiator_credential("testapi").unwrap().expose_secret(),
+            "acme_key_password"
+        );
+        assert_eq!(acme.passkey().as_deref(), Some("acme_key_passkey"));
+        assert!(matches!(
+            acme.initiator_credential("globex_api"),
+            Err(MpesaError::UnknownInitiator(name)) if name == "globex_api"
+        ));
+        assert!(std::ptr::eq(acme, registry.for_shortcode("174379").unwrap()));
+        assert!(std::ptr::eq(acme, registry.for_shortcode("600000").unwrap()));
+        assert!(registry.tenants["globex"].client.get().is_none());
+
+        let globex = registry.for_shortcode("600001").unwrap();
+        assert_eq!(
+            globex.initiator_credential("testapi").unwrap().expose_secret(),
+            "globex_key_password"
+        );
+        assert_eq!(globex.passkey().as_deref(), Some("globex_key_passkey"));
+        assert!(!std::ptr::eq(acme, globex));
+    }
+
+    #[test]
+    fn test_unknown_tenants_and_shortcodes() {
+        let registry = registry();
+        assert!(matches!(registry.for_tenant("initech"), Err(MpesaError::UnknownTenant(id)) if id == "initech"));
+        assert!(matches!(registry.for_shortcode("600002"), Err(MpesaError::UnknownTenant(id)) if id == "600002"));
+    }
+
+    #[test]
+    fn test_registering_again_replaces_the_tenant() {
+        let mut registry = registry();
+        registry.for_tenant("acme").unwrap();
+        registry.register("acme", config("acme_key_v2", "174379", "600010"));
+
+        assert_eq!(registry.tenant_of("600010"), Some("acme"));
+        assert_eq!(registry.tenant_of("600000"), None);
+        let acme = registry.for_shortcode("174379").unwrap();
+        assert_eq!(
+            acme.initiator_credential("testapi").unwrap().expose_secret(),
+            "acme_key_v2_password"
+        );
+
+        assert!(registry.deregister("acme").is_some());
+        assert_eq!(registry.tenant_of("174379"), None);
+        assert_eq!(registry.tenants().collect::<Vec<_>>(), vec!["globex"]);
+    }
+}
@@ -0,0 +1,136 @@
+use reqwest::Method;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::client::Mpesa;
+use crate::errors::MpesaResult;
+
+/// A request to a Safaricom API endpoint, tying the request to the path, method and response of the endpoint.
+///
+/// Every service of the crate has a request type implementing this trait e.g
+/// [`B2cRequest`](crate::services::B2cRequest), and all of them are sent through [`Mpesa::call`]. Implementing
+/// it for your own types lets the client send requests to endpoints the crate does not cover yet, with the same
+/// authentication and retries.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use mpesa::{Environment, Mpesa, MpesaRequest, MpesaResult};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Debug, Serialize)]
+/// #[serde(rename_all = "PascalCase")]
+/// struct PullTransactionsRequest {
+///     short_code: String,
+///     start_date: String,
+///     end_date: String,
+///     offset_value: String,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// #[serde(rename_all = "PascalCase")]
+/// struct PullTransactionsResponse {
+///     response_code: String,
+///     response_message: String,
+/// }
+///
+/// impl MpesaRequest for PullTransactionsRequest {
+///     type Response = PullTransactionsResponse;
+///
+///     const PATH: &'static str = "pulltransactions/v1/query";
+///     const IDEMPOTENT: bool = true;
+///
+///     fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+///         Ok(self)
+///     }
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> MpesaResult<()> {
+///     let client = Mpesa::new("consumer_key", "consumer_secret", Environment::Sandbox);
+///     let response = client
+///         .call(&PullTransactionsRequest {
+///             short_code: "600000".to_owned(),
+///             start_date: "2020-08-04 8:36:00".to_owned(),
+///             end_date: "2020-08-16 10:10:000".to_owned(),
+///             offset_value: "0".to_owned(),
+///         })
+///         .await?;
+///     println!("{}", response.response_message);
+///     Ok(())
+/// }
+/// ```
+pub trait MpesaRequest: Send + Sync {
+    /// The response returned by the endpoint
+    type Response: DeserializeOwned;
+
+    /// The path of the endpoint, relative to the base url of the environment
+    const PATH: &'static str;
+
+    /// The HTTP method of the endpoint
+    const METHOD: Method = Method::POST;
+
+    /// Whether sending the request more than once is harmless, see [`RetryPolicy`](crate::RetryPolicy).
+    /// Requests that move money are not idempotent
+    const IDEMPOTENT: bool = false;
+
+    /// Builds the JSON body sent to the endpoint, deriving values such as security credentials from `client`
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` if the body cannot be built, in which case the request is not sent
+    fn payload(&self, client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync>;
+}
+
+/// Sends any [`MpesaRequest`] through the client, so that tower middleware such as timeouts, concurrency limits
+/// and load shedding can wrap calls to the Safaricom API.
+///
+/// The client is always ready, its own retries and token refreshes happen inside the returned future.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// use mpesa::services::C2bRegisterRequest;
+/// use mpesa::{Environment, Mpesa, ResponseType};
+/// use tower::{ServiceBuilder, ServiceExt};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), tower::BoxError> {
+///     let client = Mpesa::new("consumer_key", "consumer_secret", Environment::Sandbox);
+///     let service = ServiceBuilder::new()
+///         .concurrency_limit(10)
+///         .timeout(Duration::from_secs(30))
+///         .service(client);
+///
+///     let request = C2bRegisterRequest {
+///         short_code: "600496".to_owned(),
+///         response_type: ResponseType::Completed,
+///         confirmation_url: "https://example.com/confirmation".to_owned(),
+///         validation_url: "https://example.com/validation".to_owned(),
+///     };
+///     let response = service.oneshot(request).await?;
+///     assert!(response.response_code.is_success());
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "tower")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+impl<R> tower_service::Service<R> for Mpesa
+where
+    R: MpesaRequest + 'static,
+    R::Response: Send + 'static,
+{
+    type Response = R::Response;
+    type Error = crate::MpesaError;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = MpesaResult<R::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut std::task::Context<'_>) -> std::task::Poll<MpesaResult<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let client = self.clone();
+        Box::pin(async move { client.execute(request).await })
+    }
+}
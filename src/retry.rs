@@ -0,0 +1,173 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use backoff::{ExponentialBackoff, ExponentialBackoffBuilder};
+
+use crate::errors::{BackoffMpesaResult, DarajaErrorCode, MpesaError, MpesaResult};
+
+/// How failed requests to the Safaricom API are retried.
+///
+/// Failures are retried with an exponential backoff, starting at `initial_interval` and growing by
+/// `multiplier` up to `max_interval`, until either `max_attempts` requests have been sent or
+/// `max_elapsed` has passed since the first one.
+///
+/// Requests that move money e.g B2C payments and STK pushes are only retried when the failure proves that the
+/// request never reached Safaricom, such as a failure to connect, so that a retry cannot pay out or prompt the
+/// customer twice. Other requests are also retried on timeouts and when the API is busy.
+///
+/// The policy of a client applies to all its requests, and can be overridden for a single request through the
+/// `retry_policy` method of its builder.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use mpesa::{Environment, Mpesa, RetryPolicy};
+///
+/// let client = Mpesa::new("consumer_key", "consumer_secret", Environment::Sandbox);
+/// client.set_retry_policy(RetryPolicy {
+///     max_attempts: 3,
+///     max_elapsed: Some(Duration::from_secs(10)),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximum number of times a request is sent, including the first attempt
+    pub max_attempts: u32,
+    /// How long after the first attempt no more retries are made. `None` means no limit
+    pub max_elapsed: Option<Duration>,
+    /// The delay before the first retry
+    pub initial_interval: Duration,
+    /// The factor the delay grows by after every retry
+    pub multiplier: f64,
+    /// The maximum delay between retries
+    pub max_interval: Duration,
+}
+
+impl Default for RetryPolicy {
+    /// Sends a request up to 4 times within 30 seconds, waiting 500ms, 1s and 2s between the attempts
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            max_elapsed: Some(Duration::from_secs(30)),
+            initial_interval: Duration::from_millis(500),
+            multiplier: 2.0,
+            max_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    fn backoff(&self) -> ExponentialBackoff {
+        ExponentialBackoffBuilder::new()
+            .with_initial_interval(self.initial_interval)
+            .with_multiplier(self.multiplier)
+            .with_max_interval(self.max_interval)
+            .with_max_elapsed_time(self.max_elapsed)
+            .build()
+    }
+
+    /// Runs `operation` until it succeeds, fails permanently or the policy is exhausted.
+    /// `on_retry` is called before every retry.
+    ///
+    /// When `idempotent` is false, failures are only retried if the request never reached Safaricom
+    pub(crate) async fn retry<T, F, Fut>(
+        &self,
+        idempotent: bool,
+        mut operation: F,
+        mut on_retry: impl FnMut(),
+    ) -> MpesaResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = BackoffMpesaResult<T>>,
+    {
+        let attempts = AtomicU32::new(0);
+        let res = backoff::future::retry_notify(
+            self.backoff(),
+            || {
+                let attempt = attempts.fetch_add(1, Ordering::Relaxed) + 1;
+                let fut = operation();
+                async move {
+                    match fut.await {
+                        Err(backoff::Error::Transient { err, .. })
+                            if attempt >= self.max_attempts || !(idempotent || err.is_undelivered()) =>
+                        {
+                            Err(backoff::Error::permanent(err))
+                        }
+                        res => res,
+                    }
+                }
+            },
+            |_, _| on_retry(),
+        )
+        .await?;
+        Ok(res)
+    }
+}
+
+impl MpesaError {
+    /// Checks if the error proves that the request was never processed by Safaricom
+    pub(crate) fn is_undelivered(&self) -> bool {
+        match self {
+            MpesaError::NetworkError(err) => err.is_connect(),
+            // Spike arrest and quota violations are rejected by the API gateway
+            MpesaError::Service(res) => res.code() == DarajaErrorCode::QuotaViolation,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_interval: Duration::from_millis(1),
+            max_interval: Duration::from_millis(1),
+            ..Default::default()
+        }
+    }
+
+    async fn count_attempts(policy: RetryPolicy, idempotent: bool) -> u32 {
+        let attempts = AtomicU32::new(0);
+        let mut retries = 0;
+        let res: MpesaResult<()> = policy
+            .retry(
+                idempotent,
+                || async {
+                    attempts.fetch_add(1, Ordering::Relaxed);
+                    Err(backoff::Error::transient(MpesaError::TransientError))
+                },
+                || retries += 1,
+            )
+            .await;
+        assert!(matches!(res, Err(MpesaError::TransientError)));
+        let attempts = attempts.into_inner();
+        assert_eq!(retries, attempts - 1);
+        attempts
+    }
+
+    #[tokio::test]
+    async fn test_retries_up_to_max_attempts() {
+        assert_eq!(count_attempts(fast(3), true).await, 3);
+        assert_eq!(count_attempts(RetryPolicy::none(), true).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_non_idempotent_requests_are_not_retried_on_ambiguous_errors() {
+        assert_eq!(count_attempts(fast(3), false).await, 1);
+    }
+}
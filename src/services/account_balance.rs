@@ -1,25 +1,66 @@
 #![doc = include_str!("../../docs/client/account_balance.md")]
 
+use std::fmt;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
-use crate::constants::{CommandId, IdentifierTypes};
+use crate::callbacks::ResultCallback;
+use crate::constants::{CommandId, IdentifierTypes, MpesaResponseCode};
+use crate::redact;
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 use crate::{Mpesa, MpesaError, MpesaResult};
 
 const ACCOUNT_BALANCE_URL: &str = "mpesa/accountbalance/v1/query";
 
-#[derive(Debug, Serialize)]
+/// An owned Account Balance request, sent with [`Mpesa::execute`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountBalanceRequest {
+    /// The credential/ username used to authenticate the transaction request
+    pub initiator_name: String,
+    pub command_id: CommandId,
+    /// The shortcode whose balance is queried
+    pub party_a: String,
+    pub identifier_type: IdentifierTypes,
+    pub remarks: String,
+    pub queue_timeout_url: String,
+    pub result_url: String,
+}
+
+impl MpesaRequest for AccountBalanceRequest {
+    type Response = AccountBalanceResponse;
+
+    const PATH: &'static str = ACCOUNT_BALANCE_URL;
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(AccountBalancePayload {
+            initiator: &self.initiator_name,
+            security_credential: client.gen_security_credentials(&self.initiator_name)?,
+            command_id: self.command_id,
+            party_a: &self.party_a,
+            identifier_type: self.identifier_type.to_string(),
+            remarks: &self.remarks,
+            queue_time_out_url: &self.queue_timeout_url,
+            result_url: &self.result_url,
+        })
+    }
+}
+
+#[derive(Serialize)]
 /// Account Balance payload
 struct AccountBalancePayload<'mpesa> {
     #[serde(rename(serialize = "Initiator"))]
     initiator: &'mpesa str,
     #[serde(rename(serialize = "SecurityCredential"))]
-    security_credential: &'mpesa str,
+    security_credential: String,
     #[serde(rename(serialize = "CommandID"))]
     command_id: CommandId,
     #[serde(rename(serialize = "PartyA"))]
     party_a: &'mpesa str,
     #[serde(rename(serialize = "IdentifierType"))]
-    identifier_type: &'mpesa str,
+    identifier_type: String,
     #[serde(rename(serialize = "Remarks"))]
     remarks: &'mpesa str,
     #[serde(rename(serialize = "QueueTimeOutURL"))]
@@ -28,6 +69,21 @@ struct AccountBalancePayload<'mpesa> {
     result_url: &'mpesa str,
 }
 
+impl fmt::Debug for AccountBalancePayload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccountBalancePayload")
+            .field("initiator", &self.initiator)
+            .field("security_credential", &redact::Secret)
+            .field("command_id", &self.command_id)
+            .field("party_a", &self.party_a)
+            .field("identifier_type", &self.identifier_type)
+            .field("remarks", &self.remarks)
+            .field("queue_time_out_url", &self.queue_time_out_url)
+            .field("result_url", &self.result_url)
+            .finish()
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct AccountBalanceResponse {
     #[serde(rename(deserialize = "ConversationID"))]
@@ -35,7 +91,7 @@ pub struct AccountBalanceResponse {
     #[serde(rename(deserialize = "OriginatorConversationID"))]
     pub originator_conversation_id: String,
     #[serde(rename(deserialize = "ResponseCode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "ResponseDescription"))]
     pub response_description: String,
 }
@@ -49,6 +105,7 @@ pub struct AccountBalanceBuilder<'mpesa> {
     remarks: Option<&'mpesa str>,
     queue_timeout_url: Option<&'mpesa str>,
     result_url: Option<&'mpesa str>,
+    retry_policy: Option<RetryPolicy>,
 }
 
 impl<'mpesa> AccountBalanceBuilder<'mpesa> {
@@ -64,9 +121,16 @@ impl<'mpesa> AccountBalanceBuilder<'mpesa> {
             remarks: None,
             queue_timeout_url: None,
             result_url: None,
+            retry_policy: None,
         }
     }
 
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> AccountBalanceBuilder<'mpesa> {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
     /// Adds a `CommandId`, the unique command passed to the MPESA system.
     /// Defaults to `CommandId::AccountBalance` if not passed explicitly.
     ///
@@ -142,27 +206,44 @@ impl<'mpesa> AccountBalanceBuilder<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<AccountBalanceResponse> {
-        let credentials = self.client.gen_security_credentials()?;
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&self.build()?, retry_policy).await
+    }
 
-        let payload = AccountBalancePayload {
+    /// Builds an owned [`AccountBalanceRequest`], which can be sent later with [`Mpesa::execute`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::Message` if a required field is missing
+    pub fn build(self) -> MpesaResult<AccountBalanceRequest> {
+        Ok(AccountBalanceRequest {
+            initiator_name: self.initiator_name.to_owned(),
             command_id: self.command_id.unwrap_or(CommandId::AccountBalance),
-            party_a: self.party_a.ok_or(MpesaError::Message("party_a is required"))?,
-            identifier_type: &self.identifier_type.unwrap_or(IdentifierTypes::ShortCode).to_string(),
-            remarks: self.remarks.unwrap_or("None"),
-            initiator: self.initiator_name,
-            queue_time_out_url: self
+            party_a: self
+                .party_a
+                .ok_or(MpesaError::Message("party_a is required"))?
+                .to_owned(),
+            identifier_type: self.identifier_type.unwrap_or(IdentifierTypes::ShortCode),
+            remarks: self.remarks.unwrap_or("None").to_owned(),
+            queue_timeout_url: self
                 .queue_timeout_url
-                .ok_or(MpesaError::Message("queue_timeout_url is required"))?,
-            result_url: self.result_url.ok_or(MpesaError::Message("result_url is required"))?,
-            security_credential: &credentials,
-        };
+                .ok_or(MpesaError::Message("queue_timeout_url is required"))?
+                .to_owned(),
+            result_url: self
+                .result_url
+                .ok_or(MpesaError::Message("result_url is required"))?
+                .to_owned(),
+        })
+    }
 
+    /// Sends the request and waits for its result, correlated by `ConversationID` through [`Mpesa::callbacks`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` on failure, or a `MpesaError::CallbackTimeout` if the result does not
+    /// arrive within `timeout`
+    pub async fn send_and_wait(self, timeout: Duration) -> MpesaResult<ResultCallback> {
         self.client
-            .send(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: ACCOUNT_BALANCE_URL,
-                body: payload,
-            })
+            .callbacks()
+            .send_and_wait(self.send(), |response| &response.conversation_id, timeout)
             .await
     }
 }
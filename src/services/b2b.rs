@@ -1,19 +1,69 @@
 #![doc = include_str!("../../docs/client/b2b.md")]
 
+use std::fmt;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
+use crate::callbacks::ResultCallback;
 use crate::client::Mpesa;
-use crate::constants::{CommandId, IdentifierTypes};
+use crate::constants::{CommandId, IdentifierTypes, MpesaResponseCode};
 use crate::errors::{MpesaError, MpesaResult};
+use crate::redact;
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 
 const B2B_URL: &str = "mpesa/b2b/v1/paymentrequest";
 
-#[derive(Debug, Serialize)]
+/// An owned B2B payment request, sent with [`Mpesa::execute`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct B2bRequest {
+    /// The credential/ username used to authenticate the transaction request
+    pub initiator_name: String,
+    pub command_id: CommandId,
+    pub amount: f64,
+    /// The shortcode sending the money
+    pub party_a: String,
+    pub sender_identifier_type: IdentifierTypes,
+    /// The shortcode receiving the money
+    pub party_b: String,
+    pub receiver_identifier_type: IdentifierTypes,
+    pub remarks: String,
+    pub queue_timeout_url: Option<String>,
+    pub result_url: Option<String>,
+    pub account_reference: Option<String>,
+}
+
+impl MpesaRequest for B2bRequest {
+    type Response = B2bResponse;
+
+    const PATH: &'static str = B2B_URL;
+    const IDEMPOTENT: bool = false;
+
+    fn payload(&self, client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(B2bPayload {
+            initiator: &self.initiator_name,
+            security_credential: client.gen_security_credentials(&self.initiator_name)?,
+            command_id: self.command_id,
+            amount: self.amount,
+            party_a: &self.party_a,
+            sender_identifier_type: self.sender_identifier_type.to_string(),
+            party_b: &self.party_b,
+            reciever_identifier_type: self.receiver_identifier_type.to_string(),
+            remarks: &self.remarks,
+            queue_time_out_url: self.queue_timeout_url.as_deref(),
+            result_url: self.result_url.as_deref(),
+            account_reference: self.account_reference.as_deref(),
+        })
+    }
+}
+
+#[derive(Serialize)]
 struct B2bPayload<'mpesa> {
     #[serde(rename(serialize = "Initiator"))]
     initiator: &'mpesa str,
     #[serde(rename(serialize = "SecurityCredential"))]
-    security_credential: &'mpesa str,
+    security_credential: String,
     #[serde(rename(serialize = "CommandID"))]
     command_id: CommandId,
     #[serde(rename(serialize = "Amount"))]
@@ -21,11 +71,11 @@ struct B2bPayload<'mpesa> {
     #[serde(rename(serialize = "PartyA"))]
     party_a: &'mpesa str,
     #[serde(rename(serialize = "SenderIdentifierType"))]
-    sender_identifier_type: &'mpesa str,
+    sender_identifier_type: String,
     #[serde(rename(serialize = "PartyB"))]
     party_b: &'mpesa str,
     #[serde(rename(serialize = "RecieverIdentifierType"))]
-    reciever_identifier_type: &'mpesa str,
+    reciever_identifier_type: String,
     #[serde(rename(serialize = "Remarks"))]
     remarks: &'mpesa str,
     #[serde(rename(serialize = "QueueTimeOutURL"), skip_serializing_if = "Option::is_none")]
@@ -36,6 +86,25 @@ struct B2bPayload<'mpesa> {
     account_reference: Option<&'mpesa str>,
 }
 
+impl fmt::Debug for B2bPayload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("B2bPayload")
+            .field("initiator", &self.initiator)
+            .field("security_credential", &redact::Secret)
+            .field("command_id", &self.command_id)
+            .field("amount", &self.amount)
+            .field("party_a", &self.party_a)
+            .field("sender_identifier_type", &self.sender_identifier_type)
+            .field("party_b", &self.party_b)
+            .field("reciever_identifier_type", &self.reciever_identifier_type)
+            .field("remarks", &self.remarks)
+            .field("queue_time_out_url", &self.queue_time_out_url)
+            .field("result_url", &self.result_url)
+            .field("account_reference", &self.account_reference)
+            .finish()
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct B2bResponse {
     #[serde(rename(deserialize = "ConversationID"))]
@@ -43,7 +112,7 @@ pub struct B2bResponse {
     #[serde(rename(deserialize = "OriginatorConversationID"))]
     pub originator_conversation_id: String,
     #[serde(rename(deserialize = "ResponseCode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "ResponseDescription"))]
     pub response_description: String,
 }
@@ -63,6 +132,7 @@ pub struct B2bBuilder<'mpesa> {
     queue_timeout_url: Option<&'mpesa str>,
     result_url: Option<&'mpesa str>,
     account_ref: Option<&'mpesa str>,
+    retry_policy: Option<RetryPolicy>,
 }
 
 impl<'mpesa> B2bBuilder<'mpesa> {
@@ -82,9 +152,16 @@ impl<'mpesa> B2bBuilder<'mpesa> {
             result_url: None,
             command_id: None,
             account_ref: None,
+            retry_policy: None,
         }
     }
 
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> B2bBuilder<'mpesa> {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
     /// Adds the `CommandId`. Defaults to `CommandId::BusinessToBusinessTransfer` if not explicitly provided.
     ///
     /// # Errors
@@ -202,29 +279,45 @@ impl<'mpesa> B2bBuilder<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<B2bResponse> {
-        let credentials = self.client.gen_security_credentials()?;
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&self.build()?, retry_policy).await
+    }
 
-        let payload = B2bPayload {
-            initiator: self.initiator_name,
-            security_credential: &credentials,
+    /// Builds an owned [`B2bRequest`], which can be sent later with [`Mpesa::execute`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::Message` if a required field is missing
+    pub fn build(self) -> MpesaResult<B2bRequest> {
+        Ok(B2bRequest {
+            initiator_name: self.initiator_name.to_owned(),
             command_id: self.command_id.unwrap_or(CommandId::BusinessToBusinessTransfer),
             amount: self.amount.ok_or(MpesaError::Message("amount is required"))?,
-            party_a: self.party_a.ok_or(MpesaError::Message("party_a is required"))?,
-            sender_identifier_type: &self.sender_id.unwrap_or(IdentifierTypes::ShortCode).to_string(),
-            party_b: self.party_b.ok_or(MpesaError::Message("party_b is required"))?,
-            reciever_identifier_type: &self.receiver_id.unwrap_or(IdentifierTypes::ShortCode).to_string(),
-            remarks: self.remarks.unwrap_or("None"),
-            queue_time_out_url: self.queue_timeout_url,
-            result_url: self.result_url,
-            account_reference: self.account_ref,
-        };
+            party_a: self
+                .party_a
+                .ok_or(MpesaError::Message("party_a is required"))?
+                .to_owned(),
+            sender_identifier_type: self.sender_id.unwrap_or(IdentifierTypes::ShortCode),
+            party_b: self
+                .party_b
+                .ok_or(MpesaError::Message("party_b is required"))?
+                .to_owned(),
+            receiver_identifier_type: self.receiver_id.unwrap_or(IdentifierTypes::ShortCode),
+            remarks: self.remarks.unwrap_or("None").to_owned(),
+            queue_timeout_url: self.queue_timeout_url.map(str::to_owned),
+            result_url: self.result_url.map(str::to_owned),
+            account_reference: self.account_ref.map(str::to_owned),
+        })
+    }
 
+    /// Sends the request and waits for its result, correlated by `ConversationID` through [`Mpesa::callbacks`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` on failure, or a `MpesaError::CallbackTimeout` if the result does not
+    /// arrive within `timeout`
+    pub async fn send_and_wait(self, timeout: Duration) -> MpesaResult<ResultCallback> {
         self.client
-            .send(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: B2B_URL,
-                body: payload,
-            })
+            .callbacks()
+            .send_and_wait(self.send(), |response| &response.conversation_id, timeout)
             .await
     }
 }
@@ -1,18 +1,84 @@
 #![doc = include_str!("../../docs/client/b2c.md")]
 
+use std::fmt;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
-use crate::{CommandId, Mpesa, MpesaError, MpesaResult};
+use crate::callbacks::ResultCallback;
+use crate::redact;
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
+use crate::{CommandId, Mpesa, MpesaError, MpesaResponseCode, MpesaResult};
 
 const B2C_URL: &str = "mpesa/b2c/v3/paymentrequest";
 
-#[derive(Debug, Serialize)]
+/// An owned B2C payment request, sent with [`Mpesa::execute`]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct B2cRequest {
+    /// The credential/ username used to authenticate the transaction request
+    pub initiator_name: String,
+    pub command_id: CommandId,
+    /// A unique identifier of the request, generated by the business
+    pub originator_conversation_id: String,
+    pub amount: f64,
+    /// The paybill number sending the money
+    pub party_a: String,
+    /// The mobile number receiving the money
+    pub party_b: String,
+    pub remarks: String,
+    pub queue_timeout_url: String,
+    pub result_url: String,
+    pub occasion: String,
+}
+
+impl fmt::Debug for B2cRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("B2cRequest")
+            .field("initiator_name", &self.initiator_name)
+            .field("command_id", &self.command_id)
+            .field("originator_conversation_id", &self.originator_conversation_id)
+            .field("amount", &self.amount)
+            .field("party_a", &self.party_a)
+            .field("party_b", &redact::Msisdn(&self.party_b))
+            .field("remarks", &self.remarks)
+            .field("queue_timeout_url", &self.queue_timeout_url)
+            .field("result_url", &self.result_url)
+            .field("occasion", &self.occasion)
+            .finish()
+    }
+}
+
+impl MpesaRequest for B2cRequest {
+    type Response = B2cResponse;
+
+    const PATH: &'static str = B2C_URL;
+    const IDEMPOTENT: bool = false;
+
+    fn payload(&self, client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(B2cPayload {
+            initiator_name: &self.initiator_name,
+            security_credential: client.gen_security_credentials(&self.initiator_name)?,
+            command_id: self.command_id,
+            originator_conversation_id: &self.originator_conversation_id,
+            amount: self.amount,
+            party_a: &self.party_a,
+            party_b: &self.party_b,
+            remarks: &self.remarks,
+            queue_time_out_url: &self.queue_timeout_url,
+            result_url: &self.result_url,
+            occasion: &self.occasion,
+        })
+    }
+}
+
+#[derive(Serialize)]
 /// Payload to allow for b2c transactions:
 struct B2cPayload<'mpesa> {
     #[serde(rename(serialize = "InitiatorName"))]
     initiator_name: &'mpesa str,
     #[serde(rename(serialize = "SecurityCredential"))]
-    security_credential: &'mpesa str,
+    security_credential: String,
     #[serde(rename(serialize = "CommandID"))]
     command_id: CommandId,
     #[serde(rename(serialize = "OriginatorConversationID"))]
@@ -33,6 +99,24 @@ struct B2cPayload<'mpesa> {
     occasion: &'mpesa str,
 }
 
+impl fmt::Debug for B2cPayload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("B2cPayload")
+            .field("initiator_name", &self.initiator_name)
+            .field("security_credential", &redact::Secret)
+            .field("command_id", &self.command_id)
+            .field("originator_conversation_id", &self.originator_conversation_id)
+            .field("amount", &self.amount)
+            .field("party_a", &self.party_a)
+            .field("party_b", &redact::Msisdn(self.party_b))
+            .field("remarks", &self.remarks)
+            .field("queue_time_out_url", &self.queue_time_out_url)
+            .field("result_url", &self.result_url)
+            .field("occasion", &self.occasion)
+            .finish()
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct B2cResponse {
     #[serde(rename(deserialize = "ConversationID"))]
@@ -40,13 +124,12 @@ pub struct B2cResponse {
     #[serde(rename(deserialize = "OriginatorConversationID"))]
     pub originator_conversation_id: String,
     #[serde(rename(deserialize = "ResponseCode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "ResponseDescription"))]
     pub response_description: String,
 }
 
 /// B2C transaction builder struct
-#[derive(Debug)]
 pub struct B2cBuilder<'mpesa> {
     initiator_name: &'mpesa str,
     client: &'mpesa Mpesa,
@@ -59,6 +142,26 @@ pub struct B2cBuilder<'mpesa> {
     queue_timeout_url: Option<&'mpesa str>,
     result_url: Option<&'mpesa str>,
     occasion: Option<&'mpesa str>,
+    retry_policy: Option<RetryPolicy>,
+}
+
+impl fmt::Debug for B2cBuilder<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("B2cBuilder")
+            .field("initiator_name", &self.initiator_name)
+            .field("client", &self.client)
+            .field("command_id", &self.command_id)
+            .field("originator_conversation_id", &self.originator_conversation_id)
+            .field("amount", &self.amount)
+            .field("party_a", &self.party_a)
+            .field("party_b", &redact::optional_msisdn(&self.party_b))
+            .field("remarks", &self.remarks)
+            .field("queue_timeout_url", &self.queue_timeout_url)
+            .field("result_url", &self.result_url)
+            .field("occasion", &self.occasion)
+            .field("retry_policy", &self.retry_policy)
+            .finish()
+    }
 }
 
 impl<'mpesa> B2cBuilder<'mpesa> {
@@ -77,9 +180,16 @@ impl<'mpesa> B2cBuilder<'mpesa> {
             result_url: None,
             occasion: None,
             command_id: None,
+            retry_policy: None,
         }
     }
 
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> B2cBuilder<'mpesa> {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
     /// Adds the `OriginatorConversationID`.
     pub fn originator_conversation_id(mut self, originator_conversation_id: &'mpesa str) -> B2cBuilder<'mpesa> {
         self.originator_conversation_id = Some(originator_conversation_id);
@@ -188,32 +298,53 @@ impl<'mpesa> B2cBuilder<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure.
     pub async fn send(self) -> MpesaResult<B2cResponse> {
-        let credentials = self.client.gen_security_credentials()?;
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&self.build()?, retry_policy).await
+    }
 
-        let payload = B2cPayload {
+    /// Builds an owned [`B2cRequest`], which can be sent later with [`Mpesa::execute`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::Message` if a required field is missing
+    pub fn build(self) -> MpesaResult<B2cRequest> {
+        Ok(B2cRequest {
+            initiator_name: self.initiator_name.to_owned(),
+            command_id: self.command_id.unwrap_or(CommandId::BusinessPayment),
             originator_conversation_id: self
                 .originator_conversation_id
-                .ok_or(MpesaError::Message("originator_conversation_id is required"))?,
-            initiator_name: self.initiator_name,
-            security_credential: &credentials,
-            command_id: self.command_id.unwrap_or(CommandId::BusinessPayment),
+                .ok_or(MpesaError::Message("originator_conversation_id is required"))?
+                .to_owned(),
             amount: self.amount.ok_or(MpesaError::Message("amount is required"))?,
-            party_a: self.party_a.ok_or(MpesaError::Message("party_a is required"))?,
-            party_b: self.party_b.ok_or(MpesaError::Message("party_b is required"))?,
-            remarks: self.remarks.unwrap_or("None"),
-            queue_time_out_url: self
+            party_a: self
+                .party_a
+                .ok_or(MpesaError::Message("party_a is required"))?
+                .to_owned(),
+            party_b: self
+                .party_b
+                .ok_or(MpesaError::Message("party_b is required"))?
+                .to_owned(),
+            remarks: self.remarks.unwrap_or("None").to_owned(),
+            queue_timeout_url: self
                 .queue_timeout_url
-                .ok_or(MpesaError::Message("queue_timeout_url is required"))?,
-            result_url: self.result_url.ok_or(MpesaError::Message("result_url is required"))?,
-            occasion: self.occasion.unwrap_or("None"),
-        };
+                .ok_or(MpesaError::Message("queue_timeout_url is required"))?
+                .to_owned(),
+            result_url: self
+                .result_url
+                .ok_or(MpesaError::Message("result_url is required"))?
+                .to_owned(),
+            occasion: self.occasion.unwrap_or("None").to_owned(),
+        })
+    }
 
+    /// Sends the request and waits for its result, correlated by `ConversationID` through [`Mpesa::callbacks`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` on failure, or a `MpesaError::CallbackTimeout` if the result does not
+    /// arrive within `timeout`
+    pub async fn send_and_wait(self, timeout: Duration) -> MpesaResult<ResultCallback> {
         self.client
-            .send(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: B2C_URL,
-                body: payload,
-            })
+            .callbacks()
+            .send_and_wait(self.send(), |response| &response.conversation_id, timeout)
             .await
     }
 }
@@ -1,13 +1,35 @@
 #![doc = include_str!("../../../docs/client/bill_manager/bulk_invoice.md")]
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::client::Mpesa;
 use crate::constants::Invoice;
 use crate::errors::{MpesaError, MpesaResult};
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 
 const BILL_MANAGER_BULK_INVOICE_API_URL: &str = "v1/billmanager-invoice/bulk-invoicing";
 
+/// An owned Bill Manager Bulk Invoice request, sent with [`Mpesa::execute`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BulkInvoiceRequest {
+    pub invoices: Vec<Invoice>,
+}
+
+impl MpesaRequest for BulkInvoiceRequest {
+    type Response = BulkInvoiceResponse;
+
+    const PATH: &'static str = BILL_MANAGER_BULK_INVOICE_API_URL;
+    const IDEMPOTENT: bool = false;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        if self.invoices.is_empty() {
+            return Err(MpesaError::Message("invoices cannot be empty"));
+        }
+        Ok(&self.invoices)
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct BulkInvoiceResponse {
     #[serde(rename(deserialize = "rescode"))]
@@ -21,7 +43,8 @@ pub struct BulkInvoiceResponse {
 #[derive(Debug)]
 pub struct BulkInvoiceBuilder<'mpesa> {
     client: &'mpesa Mpesa,
-    invoices: Vec<Invoice<'mpesa>>,
+    invoices: Vec<Invoice>,
+    retry_policy: Option<RetryPolicy>,
 }
 
 impl<'mpesa> BulkInvoiceBuilder<'mpesa> {
@@ -30,17 +53,24 @@ impl<'mpesa> BulkInvoiceBuilder<'mpesa> {
         BulkInvoiceBuilder {
             client,
             invoices: vec![],
+            retry_policy: None,
         }
     }
 
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> BulkInvoiceBuilder<'mpesa> {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
     /// Adds a single `invoice`
-    pub fn invoice(mut self, invoice: Invoice<'mpesa>) -> BulkInvoiceBuilder<'mpesa> {
+    pub fn invoice(mut self, invoice: Invoice) -> BulkInvoiceBuilder<'mpesa> {
         self.invoices.push(invoice);
         self
     }
 
     /// Adds multiple `invoices`
-    pub fn invoices(mut self, mut invoices: Vec<Invoice<'mpesa>>) -> BulkInvoiceBuilder<'mpesa> {
+    pub fn invoices(mut self, mut invoices: Vec<Invoice>) -> BulkInvoiceBuilder<'mpesa> {
         self.invoices.append(&mut invoices);
         self
     }
@@ -52,16 +82,21 @@ impl<'mpesa> BulkInvoiceBuilder<'mpesa> {
     /// # Errors
     /// Returns an `MpesaError` on failure.
     pub async fn send(self) -> MpesaResult<BulkInvoiceResponse> {
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&self.build()?, retry_policy).await
+    }
+
+    /// Builds an owned [`BulkInvoiceRequest`], which can be sent later with [`Mpesa::execute`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::Message` if no invoice was added
+    pub fn build(self) -> MpesaResult<BulkInvoiceRequest> {
         if self.invoices.is_empty() {
             return Err(MpesaError::Message("invoices cannot be empty"));
         }
 
-        self.client
-            .send(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: BILL_MANAGER_BULK_INVOICE_API_URL,
-                body: self.invoices,
-            })
-            .await
+        Ok(BulkInvoiceRequest {
+            invoices: self.invoices,
+        })
     }
 }
@@ -4,9 +4,33 @@ use serde::{Deserialize, Serialize};
 
 use crate::client::Mpesa;
 use crate::errors::MpesaResult;
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 
 const BILL_MANAGER_CANCEL_INVOICE_API_URL: &str = "v1/billmanager-invoice/cancel-single-invoice";
 
+/// An owned Bill Manager Cancel Invoice request, sent with [`Mpesa::execute`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CancelInvoiceRequest {
+    /// The external references of the invoices to cancel
+    pub external_references: Vec<String>,
+}
+
+impl MpesaRequest for CancelInvoiceRequest {
+    type Response = CancelInvoiceResponse;
+
+    const PATH: &'static str = BILL_MANAGER_CANCEL_INVOICE_API_URL;
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(self
+            .external_references
+            .iter()
+            .map(|external_reference| CancelInvoicePayload { external_reference })
+            .collect::<Vec<_>>())
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct CancelInvoicePayload<'mpesa> {
@@ -26,7 +50,8 @@ pub struct CancelInvoiceResponse {
 #[derive(Debug)]
 pub struct CancelInvoiceBuilder<'mpesa> {
     client: &'mpesa Mpesa,
-    external_references: Vec<CancelInvoicePayload<'mpesa>>,
+    external_references: Vec<&'mpesa str>,
+    retry_policy: Option<RetryPolicy>,
 }
 
 impl<'mpesa> CancelInvoiceBuilder<'mpesa> {
@@ -35,24 +60,25 @@ impl<'mpesa> CancelInvoiceBuilder<'mpesa> {
         CancelInvoiceBuilder {
             client,
             external_references: vec![],
+            retry_policy: None,
         }
     }
 
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> CancelInvoiceBuilder<'mpesa> {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
     /// Adds an `external_reference`
     pub fn external_reference(mut self, external_reference: &'mpesa str) -> CancelInvoiceBuilder<'mpesa> {
-        self.external_references
-            .push(CancelInvoicePayload { external_reference });
+        self.external_references.push(external_reference);
         self
     }
 
     /// Adds `external_references`
-    pub fn external_references(mut self, external_references: Vec<&'mpesa str>) -> CancelInvoiceBuilder<'mpesa> {
-        self.external_references.append(
-            &mut external_references
-                .into_iter()
-                .map(|external_reference| CancelInvoicePayload { external_reference })
-                .collect(),
-        );
+    pub fn external_references(mut self, mut external_references: Vec<&'mpesa str>) -> CancelInvoiceBuilder<'mpesa> {
+        self.external_references.append(&mut external_references);
         self
     }
 
@@ -65,12 +91,14 @@ impl<'mpesa> CancelInvoiceBuilder<'mpesa> {
     /// # Errors
     /// Returns an `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<CancelInvoiceResponse> {
-        self.client
-            .send(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: BILL_MANAGER_CANCEL_INVOICE_API_URL,
-                body: self.external_references,
-            })
-            .await
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&self.build(), retry_policy).await
+    }
+
+    /// Builds an owned [`CancelInvoiceRequest`], which can be sent later with [`Mpesa::execute`]
+    pub fn build(self) -> CancelInvoiceRequest {
+        CancelInvoiceRequest {
+            external_references: self.external_references.into_iter().map(str::to_owned).collect(),
+        }
     }
 }
@@ -5,9 +5,9 @@ mod onboard_modify;
 mod reconciliation;
 mod single_invoice;
 
-pub use bulk_invoice::{BulkInvoiceBuilder, BulkInvoiceResponse};
-pub use cancel_invoice::{CancelInvoiceBuilder, CancelInvoiceResponse};
-pub use onboard::{OnboardBuilder, OnboardResponse};
-pub use onboard_modify::{OnboardModifyBuilder, OnboardModifyResponse};
-pub use reconciliation::{ReconciliationBuilder, ReconciliationResponse};
-pub use single_invoice::{SingleInvoiceBuilder, SingleInvoiceResponse};
+pub use bulk_invoice::{BulkInvoiceBuilder, BulkInvoiceRequest, BulkInvoiceResponse};
+pub use cancel_invoice::{CancelInvoiceBuilder, CancelInvoiceRequest, CancelInvoiceResponse};
+pub use onboard::{OnboardBuilder, OnboardRequest, OnboardResponse};
+pub use onboard_modify::{OnboardModifyBuilder, OnboardModifyRequest, OnboardModifyResponse};
+pub use reconciliation::{ReconciliationBuilder, ReconciliationRequest, ReconciliationResponse};
+pub use single_invoice::{SingleInvoiceBuilder, SingleInvoiceRequest, SingleInvoiceResponse};
@@ -5,9 +5,43 @@ use serde::{Deserialize, Serialize};
 use crate::client::Mpesa;
 use crate::constants::SendRemindersTypes;
 use crate::errors::{MpesaError, MpesaResult};
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 
 const BILL_MANAGER_ONBOARD_API_URL: &str = "v1/billmanager-invoice/optin";
 
+/// An owned Bill Manager Onboarding request, sent with [`Mpesa::execute`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OnboardRequest {
+    pub callback_url: String,
+    pub email: String,
+    /// A file with your organization's logo
+    pub logo: String,
+    /// The official contact of the organization, in the format `07XXXXXXXX`
+    pub official_contact: String,
+    pub send_reminders: SendRemindersTypes,
+    /// The 6 digit MPESA Till Number or PayBill Number
+    pub short_code: String,
+}
+
+impl MpesaRequest for OnboardRequest {
+    type Response = OnboardResponse;
+
+    const PATH: &'static str = BILL_MANAGER_ONBOARD_API_URL;
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(OnboardPayload {
+            callback_url: &self.callback_url,
+            email: &self.email,
+            logo: &self.logo,
+            official_contact: &self.official_contact,
+            send_reminders: self.send_reminders,
+            short_code: &self.short_code,
+        })
+    }
+}
+
 #[derive(Debug, Serialize)]
 /// Payload to opt you in as a biller to the bill manager features.
 struct OnboardPayload<'mpesa> {
@@ -42,6 +76,7 @@ pub struct OnboardBuilder<'mpesa> {
     official_contact: Option<&'mpesa str>,
     send_reminders: Option<SendRemindersTypes>,
     short_code: Option<&'mpesa str>,
+    retry_policy: Option<RetryPolicy>,
 }
 
 impl<'mpesa> OnboardBuilder<'mpesa> {
@@ -55,9 +90,16 @@ impl<'mpesa> OnboardBuilder<'mpesa> {
             official_contact: None,
             send_reminders: None,
             short_code: None,
+            retry_policy: None,
         }
     }
 
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> OnboardBuilder<'mpesa> {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
     /// Adds `callbackUrl`.
     ///
     /// # Errors
@@ -121,25 +163,31 @@ impl<'mpesa> OnboardBuilder<'mpesa> {
     /// # Errors
     /// Returns an `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<OnboardResponse> {
-        let payload = OnboardPayload {
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&self.build()?, retry_policy).await
+    }
+
+    /// Builds an owned [`OnboardRequest`], which can be sent later with [`Mpesa::execute`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::Message` if a required field is missing
+    pub fn build(self) -> MpesaResult<OnboardRequest> {
+        Ok(OnboardRequest {
             callback_url: self
                 .callback_url
-                .ok_or(MpesaError::Message("callback_url is required"))?,
-            email: self.email.ok_or(MpesaError::Message("email is required"))?,
-            logo: self.logo.ok_or(MpesaError::Message("logo is required"))?,
+                .ok_or(MpesaError::Message("callback_url is required"))?
+                .to_owned(),
+            email: self.email.ok_or(MpesaError::Message("email is required"))?.to_owned(),
+            logo: self.logo.ok_or(MpesaError::Message("logo is required"))?.to_owned(),
             official_contact: self
                 .official_contact
-                .ok_or(MpesaError::Message("official_contact is required"))?,
+                .ok_or(MpesaError::Message("official_contact is required"))?
+                .to_owned(),
             send_reminders: self.send_reminders.unwrap_or(SendRemindersTypes::Disable),
-            short_code: self.short_code.ok_or(MpesaError::Message("short_code is required"))?,
-        };
-
-        self.client
-            .send(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: BILL_MANAGER_ONBOARD_API_URL,
-                body: payload,
-            })
-            .await
+            short_code: self
+                .short_code
+                .ok_or(MpesaError::Message("short_code is required"))?
+                .to_owned(),
+        })
     }
 }
@@ -5,9 +5,42 @@ use serde::{Deserialize, Serialize};
 use crate::client::Mpesa;
 use crate::constants::SendRemindersTypes;
 use crate::errors::MpesaResult;
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 
 const BILL_MANAGER_ONBOARD_MODIFY_API_URL: &str = "v1/billmanager-invoice/change-optin-details";
 
+/// An owned Bill Manager Onboarding Modify request, sent with [`Mpesa::execute`].
+///
+/// Only the details that are set are modified
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OnboardModifyRequest {
+    pub callback_url: Option<String>,
+    pub email: Option<String>,
+    pub logo: Option<String>,
+    pub official_contact: Option<String>,
+    pub send_reminders: Option<SendRemindersTypes>,
+    pub short_code: Option<String>,
+}
+
+impl MpesaRequest for OnboardModifyRequest {
+    type Response = OnboardModifyResponse;
+
+    const PATH: &'static str = BILL_MANAGER_ONBOARD_MODIFY_API_URL;
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(OnboardModifyPayload {
+            callback_url: self.callback_url.as_deref(),
+            email: self.email.as_deref(),
+            logo: self.logo.as_deref(),
+            official_contact: self.official_contact.as_deref(),
+            send_reminders: self.send_reminders,
+            short_code: self.short_code.as_deref(),
+        })
+    }
+}
+
 #[derive(Debug, Serialize)]
 /// Payload to modify opt-in details to the bill manager api.
 struct OnboardModifyPayload<'mpesa> {
@@ -42,6 +75,7 @@ pub struct OnboardModifyBuilder<'mpesa> {
     official_contact: Option<&'mpesa str>,
     send_reminders: Option<SendRemindersTypes>,
     short_code: Option<&'mpesa str>,
+    retry_policy: Option<RetryPolicy>,
 }
 
 impl<'mpesa> OnboardModifyBuilder<'mpesa> {
@@ -55,9 +89,16 @@ impl<'mpesa> OnboardModifyBuilder<'mpesa> {
             official_contact: None,
             send_reminders: None,
             short_code: None,
+            retry_policy: None,
         }
     }
 
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> OnboardModifyBuilder<'mpesa> {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
     /// Adds `callbackUrl`.
     pub fn callback_url(mut self, callback_url: &'mpesa str) -> OnboardModifyBuilder<'mpesa> {
         self.callback_url = Some(callback_url);
@@ -103,21 +144,19 @@ impl<'mpesa> OnboardModifyBuilder<'mpesa> {
     /// # Errors
     /// Returns an `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<OnboardModifyResponse> {
-        let payload = OnboardModifyPayload {
-            callback_url: self.callback_url,
-            email: self.email,
-            logo: self.logo,
-            official_contact: self.official_contact,
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&self.build(), retry_policy).await
+    }
+
+    /// Builds an owned [`OnboardModifyRequest`], which can be sent later with [`Mpesa::execute`]
+    pub fn build(self) -> OnboardModifyRequest {
+        OnboardModifyRequest {
+            callback_url: self.callback_url.map(str::to_owned),
+            email: self.email.map(str::to_owned),
+            logo: self.logo.map(str::to_owned),
+            official_contact: self.official_contact.map(str::to_owned),
             send_reminders: self.send_reminders,
-            short_code: self.short_code,
-        };
-
-        self.client
-            .send(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: BILL_MANAGER_ONBOARD_MODIFY_API_URL,
-                body: payload,
-            })
-            .await
+            short_code: self.short_code.map(str::to_owned),
+        }
     }
 }
@@ -1,14 +1,67 @@
 #![doc = include_str!("../../../docs/client/bill_manager/reconciliation.md")]
 
+use std::fmt;
+
 use chrono::prelude::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::client::Mpesa;
 use crate::errors::{MpesaError, MpesaResult};
+use crate::redact;
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 
 const BILL_MANAGER_RECONCILIATION_API_URL: &str = "v1/billmanager-invoice/reconciliation";
 
-#[derive(Debug, Serialize)]
+/// An owned Bill Manager Reconciliation request, sent with [`Mpesa::execute`]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReconciliationRequest {
+    pub account_reference: String,
+    pub external_reference: String,
+    pub full_name: String,
+    pub invoice_name: String,
+    pub paid_amount: f64,
+    pub payment_date: DateTime<Utc>,
+    pub phone_number: String,
+    pub transaction_id: String,
+}
+
+impl fmt::Debug for ReconciliationRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconciliationRequest")
+            .field("account_reference", &self.account_reference)
+            .field("external_reference", &self.external_reference)
+            .field("full_name", &self.full_name)
+            .field("invoice_name", &self.invoice_name)
+            .field("paid_amount", &self.paid_amount)
+            .field("payment_date", &self.payment_date)
+            .field("phone_number", &redact::Msisdn(&self.phone_number))
+            .field("transaction_id", &self.transaction_id)
+            .finish()
+    }
+}
+
+impl MpesaRequest for ReconciliationRequest {
+    type Response = ReconciliationResponse;
+
+    const PATH: &'static str = BILL_MANAGER_RECONCILIATION_API_URL;
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(ReconciliationPayload {
+            account_reference: &self.account_reference,
+            external_reference: &self.external_reference,
+            full_name: &self.full_name,
+            invoice_name: &self.invoice_name,
+            paid_amount: self.paid_amount,
+            payment_date: self.payment_date,
+            phone_number: &self.phone_number,
+            transaction_id: &self.transaction_id,
+        })
+    }
+}
+
+#[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ReconciliationPayload<'mpesa> {
     account_reference: &'mpesa str,
@@ -21,6 +74,21 @@ struct ReconciliationPayload<'mpesa> {
     transaction_id: &'mpesa str,
 }
 
+impl fmt::Debug for ReconciliationPayload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconciliationPayload")
+            .field("account_reference", &self.account_reference)
+            .field("external_reference", &self.external_reference)
+            .field("full_name", &self.full_name)
+            .field("invoice_name", &self.invoice_name)
+            .field("paid_amount", &self.paid_amount)
+            .field("payment_date", &self.payment_date)
+            .field("phone_number", &redact::Msisdn(self.phone_number))
+            .field("transaction_id", &self.transaction_id)
+            .finish()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct ReconciliationResponse {
     #[serde(rename(deserialize = "rescode"))]
@@ -29,7 +97,6 @@ pub struct ReconciliationResponse {
     pub response_message: String,
 }
 
-#[derive(Debug)]
 pub struct ReconciliationBuilder<'mpesa> {
     client: &'mpesa Mpesa,
     account_reference: Option<&'mpesa str>,
@@ -40,6 +107,24 @@ pub struct ReconciliationBuilder<'mpesa> {
     payment_date: Option<DateTime<Utc>>,
     phone_number: Option<&'mpesa str>,
     transaction_id: Option<&'mpesa str>,
+    retry_policy: Option<RetryPolicy>,
+}
+
+impl fmt::Debug for ReconciliationBuilder<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconciliationBuilder")
+            .field("client", &self.client)
+            .field("account_reference", &self.account_reference)
+            .field("external_reference", &self.external_reference)
+            .field("full_name", &self.full_name)
+            .field("invoice_name", &self.invoice_name)
+            .field("paid_amount", &self.paid_amount)
+            .field("payment_date", &self.payment_date)
+            .field("phone_number", &redact::optional_msisdn(&self.phone_number))
+            .field("transaction_id", &self.transaction_id)
+            .field("retry_policy", &self.retry_policy)
+            .finish()
+    }
 }
 
 impl<'mpesa> ReconciliationBuilder<'mpesa> {
@@ -55,9 +140,16 @@ impl<'mpesa> ReconciliationBuilder<'mpesa> {
             payment_date: None,
             phone_number: None,
             transaction_id: None,
+            retry_policy: None,
         }
     }
 
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> ReconciliationBuilder<'mpesa> {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
     /// Adds `account_reference`
     pub fn account_reference(mut self, account_reference: &'mpesa str) -> ReconciliationBuilder<'mpesa> {
         self.account_reference = Some(account_reference);
@@ -115,35 +207,44 @@ impl<'mpesa> ReconciliationBuilder<'mpesa> {
     /// # Errors
     /// Returns an `MpesaError` on failure.
     pub async fn send(self) -> MpesaResult<ReconciliationResponse> {
-        let payload = ReconciliationPayload {
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&self.build()?, retry_policy).await
+    }
+
+    /// Builds an owned [`ReconciliationRequest`], which can be sent later with [`Mpesa::execute`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::Message` if a required field is missing
+    pub fn build(self) -> MpesaResult<ReconciliationRequest> {
+        Ok(ReconciliationRequest {
             account_reference: self
                 .account_reference
-                .ok_or(MpesaError::Message("account_reference is required"))?,
+                .ok_or(MpesaError::Message("account_reference is required"))?
+                .to_owned(),
             external_reference: self
                 .external_reference
-                .ok_or(MpesaError::Message("external_reference is required"))?,
-            full_name: self.full_name.ok_or(MpesaError::Message("full_name is required"))?,
+                .ok_or(MpesaError::Message("external_reference is required"))?
+                .to_owned(),
+            full_name: self
+                .full_name
+                .ok_or(MpesaError::Message("full_name is required"))?
+                .to_owned(),
             invoice_name: self
                 .invoice_name
-                .ok_or(MpesaError::Message("invoice_name is required"))?,
+                .ok_or(MpesaError::Message("invoice_name is required"))?
+                .to_owned(),
             paid_amount: self.paid_amount.ok_or(MpesaError::Message("paid_amount is required"))?,
             payment_date: self
                 .payment_date
                 .ok_or(MpesaError::Message("payment_date is required"))?,
             phone_number: self
                 .phone_number
-                .ok_or(MpesaError::Message("phone_number is required"))?,
+                .ok_or(MpesaError::Message("phone_number is required"))?
+                .to_owned(),
             transaction_id: self
                 .transaction_id
-                .ok_or(MpesaError::Message("transaction_id is required"))?,
-        };
-
-        self.client
-            .send(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: BILL_MANAGER_RECONCILIATION_API_URL,
-                body: payload,
-            })
-            .await
+                .ok_or(MpesaError::Message("transaction_id is required"))?
+                .to_owned(),
+        })
     }
 }
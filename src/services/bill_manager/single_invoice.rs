@@ -1,14 +1,36 @@
 #![doc = include_str!("../../../docs/client/bill_manager/single_invoice.md")]
 
+use std::fmt;
+
 use chrono::prelude::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::client::Mpesa;
 use crate::constants::{Invoice, InvoiceItem};
 use crate::errors::{MpesaError, MpesaResult};
+use crate::redact;
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 
 const BILL_MANAGER_SINGLE_INVOICE_API_URL: &str = "v1/billmanager-invoice/single-invoicing";
 
+/// An owned Bill Manager Single Invoice request, sent with [`Mpesa::execute`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SingleInvoiceRequest {
+    pub invoice: Invoice,
+}
+
+impl MpesaRequest for SingleInvoiceRequest {
+    type Response = SingleInvoiceResponse;
+
+    const PATH: &'static str = BILL_MANAGER_SINGLE_INVOICE_API_URL;
+    const IDEMPOTENT: bool = false;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(&self.invoice)
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct SingleInvoiceResponse {
     #[serde(rename(deserialize = "rescode"))]
@@ -19,7 +41,6 @@ pub struct SingleInvoiceResponse {
     pub status_message: String,
 }
 
-#[derive(Debug)]
 pub struct SingleInvoiceBuilder<'mpesa> {
     client: &'mpesa Mpesa,
     amount: Option<f64>,
@@ -29,8 +50,30 @@ pub struct SingleInvoiceBuilder<'mpesa> {
     billed_phone_number: Option<&'mpesa str>,
     due_date: Option<DateTime<Utc>>,
     external_reference: Option<&'mpesa str>,
-    invoice_items: Option<Vec<InvoiceItem<'mpesa>>>,
+    invoice_items: Option<Vec<InvoiceItem>>,
     invoice_name: Option<&'mpesa str>,
+    retry_policy: Option<RetryPolicy>,
+}
+
+impl fmt::Debug for SingleInvoiceBuilder<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SingleInvoiceBuilder")
+            .field("client", &self.client)
+            .field("amount", &self.amount)
+            .field("account_reference", &self.account_reference)
+            .field("billed_full_name", &self.billed_full_name)
+            .field("billed_period", &self.billed_period)
+            .field(
+                "billed_phone_number",
+                &redact::optional_msisdn(&self.billed_phone_number),
+            )
+            .field("due_date", &self.due_date)
+            .field("external_reference", &self.external_reference)
+            .field("invoice_items", &self.invoice_items)
+            .field("invoice_name", &self.invoice_name)
+            .field("retry_policy", &self.retry_policy)
+            .finish()
+    }
 }
 
 impl<'mpesa> SingleInvoiceBuilder<'mpesa> {
@@ -47,9 +90,16 @@ impl<'mpesa> SingleInvoiceBuilder<'mpesa> {
             external_reference: None,
             invoice_items: None,
             invoice_name: None,
+            retry_policy: None,
         }
     }
 
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> SingleInvoiceBuilder<'mpesa> {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
     /// Adds `amount`
     pub fn amount<Number: Into<f64>>(mut self, amount: Number) -> SingleInvoiceBuilder<'mpesa> {
         self.amount = Some(amount.into());
@@ -93,7 +143,7 @@ impl<'mpesa> SingleInvoiceBuilder<'mpesa> {
     }
 
     /// Adds `invoice_items`
-    pub fn invoice_items(mut self, invoice_items: Vec<InvoiceItem<'mpesa>>) -> SingleInvoiceBuilder<'mpesa> {
+    pub fn invoice_items(mut self, invoice_items: Vec<InvoiceItem>) -> SingleInvoiceBuilder<'mpesa> {
         self.invoice_items = Some(invoice_items);
         self
     }
@@ -113,36 +163,45 @@ impl<'mpesa> SingleInvoiceBuilder<'mpesa> {
     /// # Errors
     /// Returns an `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<SingleInvoiceResponse> {
-        let payload = Invoice {
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&self.build()?, retry_policy).await
+    }
+
+    /// Builds an owned [`SingleInvoiceRequest`], which can be sent later with [`Mpesa::execute`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::Message` if a required field is missing
+    pub fn build(self) -> MpesaResult<SingleInvoiceRequest> {
+        let invoice = Invoice {
             amount: self.amount.ok_or(MpesaError::Message("amount is required"))?,
             account_reference: self
                 .account_reference
-                .ok_or(MpesaError::Message("account_reference is required"))?,
+                .ok_or(MpesaError::Message("account_reference is required"))?
+                .to_owned(),
             billed_full_name: self
                 .billed_full_name
-                .ok_or(MpesaError::Message("billed_full_name is required"))?,
+                .ok_or(MpesaError::Message("billed_full_name is required"))?
+                .to_owned(),
             billed_period: self
                 .billed_period
-                .ok_or(MpesaError::Message("billed_period is required"))?,
+                .ok_or(MpesaError::Message("billed_period is required"))?
+                .to_owned(),
             billed_phone_number: self
                 .billed_phone_number
-                .ok_or(MpesaError::Message("billed_phone_number is required"))?,
+                .ok_or(MpesaError::Message("billed_phone_number is required"))?
+                .to_owned(),
             due_date: self.due_date.ok_or(MpesaError::Message("due_date is required"))?,
             external_reference: self
                 .external_reference
-                .ok_or(MpesaError::Message("external_reference is required"))?,
+                .ok_or(MpesaError::Message("external_reference is required"))?
+                .to_owned(),
             invoice_items: self.invoice_items,
             invoice_name: self
                 .invoice_name
-                .ok_or(MpesaError::Message("invoice_name is required"))?,
+                .ok_or(MpesaError::Message("invoice_name is required"))?
+                .to_owned(),
         };
 
-        self.client
-            .send(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: BILL_MANAGER_SINGLE_INVOICE_API_URL,
-                body: payload,
-            })
-            .await
+        Ok(SingleInvoiceRequest { invoice })
     }
 }
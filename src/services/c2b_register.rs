@@ -3,11 +3,40 @@
 use serde::{Deserialize, Serialize};
 
 use crate::client::Mpesa;
-use crate::constants::ResponseType;
+use crate::constants::{MpesaResponseCode, ResponseType};
 use crate::errors::{MpesaError, MpesaResult};
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 
 const C2B_REGISTER_URL: &str = "mpesa/c2b/v1/registerurl";
 
+/// An owned C2B Register request, sent with [`Mpesa::execute`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct C2bRegisterRequest {
+    pub validation_url: String,
+    pub confirmation_url: String,
+    /// What M-Pesa does with a transaction when the validation URL cannot be reached
+    pub response_type: ResponseType,
+    /// The Paybill or Till number the URLs are registered for
+    pub short_code: String,
+}
+
+impl MpesaRequest for C2bRegisterRequest {
+    type Response = C2bRegisterResponse;
+
+    const PATH: &'static str = C2B_REGISTER_URL;
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(C2bRegisterPayload {
+            validation_url: &self.validation_url,
+            confirmation_url: &self.confirmation_url,
+            response_type: self.response_type,
+            short_code: &self.short_code,
+        })
+    }
+}
+
 #[derive(Debug, Serialize)]
 /// Payload to register the 3rd party’s confirmation and validation URLs to M-Pesa
 struct C2bRegisterPayload<'mpesa> {
@@ -26,7 +55,7 @@ pub struct C2bRegisterResponse {
     #[serde(rename(deserialize = "OriginatorCoversationID"))]
     pub originator_conversation_id: String,
     #[serde(rename(deserialize = "ResponseCode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "ResponseDescription"))]
     pub response_description: String,
 }
@@ -39,6 +68,7 @@ pub struct C2bRegisterBuilder<'mpesa> {
     confirmation_url: Option<&'mpesa str>,
     response_type: Option<ResponseType>,
     short_code: Option<&'mpesa str>,
+    retry_policy: Option<RetryPolicy>,
 }
 
 impl<'mpesa> C2bRegisterBuilder<'mpesa> {
@@ -50,9 +80,16 @@ impl<'mpesa> C2bRegisterBuilder<'mpesa> {
             confirmation_url: None,
             response_type: None,
             short_code: None,
+            retry_policy: None,
         }
     }
 
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> C2bRegisterBuilder<'mpesa> {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
     /// Adds `ValidationURL` for the client. This is a required field
     ///
     /// # Error
@@ -103,23 +140,29 @@ impl<'mpesa> C2bRegisterBuilder<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<C2bRegisterResponse> {
-        let payload = C2bRegisterPayload {
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&self.build()?, retry_policy).await
+    }
+
+    /// Builds an owned [`C2bRegisterRequest`], which can be sent later with [`Mpesa::execute`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::Message` if a required field is missing
+    pub fn build(self) -> MpesaResult<C2bRegisterRequest> {
+        Ok(C2bRegisterRequest {
             validation_url: self
                 .validation_url
-                .ok_or(MpesaError::Message("validation_url is required"))?,
+                .ok_or(MpesaError::Message("validation_url is required"))?
+                .to_owned(),
             confirmation_url: self
                 .confirmation_url
-                .ok_or(MpesaError::Message("confirmation_url is required"))?,
+                .ok_or(MpesaError::Message("confirmation_url is required"))?
+                .to_owned(),
             response_type: self.response_type.unwrap_or(ResponseType::Completed),
-            short_code: self.short_code.ok_or(MpesaError::Message("short_code is required"))?,
-        };
-
-        self.client
-            .send(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: C2B_REGISTER_URL,
-                body: payload,
-            })
-            .await
+            short_code: self
+                .short_code
+                .ok_or(MpesaError::Message("short_code is required"))?
+                .to_owned(),
+        })
     }
 }
@@ -1,14 +1,60 @@
 #![doc = include_str!("../../docs/client/c2b_simulate.md")]
 
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
 use crate::client::Mpesa;
-use crate::constants::CommandId;
+use crate::constants::{CommandId, MpesaResponseCode};
 use crate::errors::{MpesaError, MpesaResult};
+use crate::redact;
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 
 const C2B_SIMULATE_URL: &str = "mpesa/c2b/v1/simulate";
 
-#[derive(Debug, Serialize)]
+/// An owned C2B Simulate request, sent with [`Mpesa::execute`]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct C2bSimulateRequest {
+    pub command_id: CommandId,
+    pub amount: f64,
+    /// The phone number making the payment
+    pub msisdn: String,
+    pub bill_ref_number: String,
+    /// The Paybill or Till number receiving the payment
+    pub short_code: String,
+}
+
+impl fmt::Debug for C2bSimulateRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("C2bSimulateRequest")
+            .field("command_id", &self.command_id)
+            .field("amount", &self.amount)
+            .field("msisdn", &redact::Msisdn(&self.msisdn))
+            .field("bill_ref_number", &self.bill_ref_number)
+            .field("short_code", &self.short_code)
+            .finish()
+    }
+}
+
+impl MpesaRequest for C2bSimulateRequest {
+    type Response = C2bSimulateResponse;
+
+    const PATH: &'static str = C2B_SIMULATE_URL;
+    const IDEMPOTENT: bool = false;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(C2bSimulatePayload {
+            command_id: self.command_id,
+            amount: self.amount,
+            msisdn: &self.msisdn,
+            bill_ref_number: &self.bill_ref_number,
+            short_code: &self.short_code,
+        })
+    }
+}
+
+#[derive(Serialize)]
 /// Payload to make payment requests from C2B.
 /// See more: https://developer.safaricom.co.ke/docs#c2b-api
 struct C2bSimulatePayload<'mpesa> {
@@ -24,6 +70,18 @@ struct C2bSimulatePayload<'mpesa> {
     short_code: &'mpesa str,
 }
 
+impl fmt::Debug for C2bSimulatePayload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("C2bSimulatePayload")
+            .field("command_id", &self.command_id)
+            .field("amount", &self.amount)
+            .field("msisdn", &redact::Msisdn(self.msisdn))
+            .field("bill_ref_number", &self.bill_ref_number)
+            .field("short_code", &self.short_code)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct C2bSimulateResponse {
     #[serde(rename(deserialize = "ConversationID"), skip_serializing_if = "Option::is_none")]
@@ -31,12 +89,11 @@ pub struct C2bSimulateResponse {
     #[serde(rename(deserialize = "OriginatorCoversationID"))]
     pub originator_conversation_id: String,
     #[serde(rename(deserialize = "ResponseCode"))]
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     #[serde(rename(deserialize = "ResponseDescription"))]
     pub response_description: String,
 }
 
-#[derive(Debug)]
 pub struct C2bSimulateBuilder<'mpesa> {
     client: &'mpesa Mpesa,
     command_id: Option<CommandId>,
@@ -44,6 +101,21 @@ pub struct C2bSimulateBuilder<'mpesa> {
     msisdn: Option<&'mpesa str>,
     bill_ref_number: Option<&'mpesa str>,
     short_code: Option<&'mpesa str>,
+    retry_policy: Option<RetryPolicy>,
+}
+
+impl fmt::Debug for C2bSimulateBuilder<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("C2bSimulateBuilder")
+            .field("client", &self.client)
+            .field("command_id", &self.command_id)
+            .field("amount", &self.amount)
+            .field("msisdn", &redact::optional_msisdn(&self.msisdn))
+            .field("bill_ref_number", &self.bill_ref_number)
+            .field("short_code", &self.short_code)
+            .field("retry_policy", &self.retry_policy)
+            .finish()
+    }
 }
 
 impl<'mpesa> C2bSimulateBuilder<'mpesa> {
@@ -56,9 +128,16 @@ impl<'mpesa> C2bSimulateBuilder<'mpesa> {
             msisdn: None,
             bill_ref_number: None,
             short_code: None,
+            retry_policy: None,
         }
     }
 
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> C2bSimulateBuilder<'mpesa> {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
     /// Adds `CommandId`. Defaults to `CommandId::CustomerPaybillOnline` if no value explicitly passed
     ///
     /// # Errors
@@ -117,22 +196,27 @@ impl<'mpesa> C2bSimulateBuilder<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<C2bSimulateResponse> {
-        let payload = C2bSimulatePayload {
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&self.build()?, retry_policy).await
+    }
+
+    /// Builds an owned [`C2bSimulateRequest`], which can be sent later with [`Mpesa::execute`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::Message` if a required field is missing
+    pub fn build(self) -> MpesaResult<C2bSimulateRequest> {
+        Ok(C2bSimulateRequest {
             command_id: self.command_id.unwrap_or(CommandId::CustomerPayBillOnline),
             amount: self.amount.ok_or(MpesaError::Message("amount is required"))?,
-            msisdn: self.msisdn.ok_or(MpesaError::Message("msisdn is required"))?,
+            msisdn: self.msisdn.ok_or(MpesaError::Message("msisdn is required"))?.to_owned(),
             bill_ref_number: self
                 .bill_ref_number
-                .ok_or(MpesaError::Message("bill_ref_number is required"))?,
-            short_code: self.short_code.ok_or(MpesaError::Message("short_code is required"))?,
-        };
-
-        self.client
-            .send(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: C2B_SIMULATE_URL,
-                body: payload,
-            })
-            .await
+                .ok_or(MpesaError::Message("bill_ref_number is required"))?
+                .to_owned(),
+            short_code: self
+                .short_code
+                .ok_or(MpesaError::Message("short_code is required"))?
+                .to_owned(),
+        })
     }
 }
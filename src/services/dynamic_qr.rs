@@ -6,46 +6,71 @@ use serde::{Deserialize, Serialize};
 use crate::client::Mpesa;
 use crate::constants::TransactionType;
 use crate::errors::{MpesaError, MpesaResult};
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 
 const DYNAMIC_QR_URL: &str = "mpesa/qrcode/v1/generate";
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all(serialize = "PascalCase"))]
-pub struct DynamicQRRequest<'mpesa> {
+/// An owned Dynamic QR request, sent with [`Mpesa::execute`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DynamicQRRequest {
     /// Name of the Company/M-Pesa Merchant Name
-    pub merchant_name: &'mpesa str,
+    pub merchant_name: String,
     /// Transaction Reference Number
-    pub ref_no: &'mpesa str,
+    pub ref_no: String,
     /// The total amount of the transaction
     pub amount: u32,
     /// Transaction Type
-    ///
-    /// This can be a `TransactionType` or a `&str`
-    /// The `&str` must be one of the following:
-    /// - `BG` for Buy Goods
-    /// - `PB` for Pay Bill
-    /// - `WA` Withdraw Cash
-    /// - `SM` Send Money (Mobile Number)
-    /// - `SB` Sent to Business. Business number CPI in MSISDN format.
-    #[serde(rename = "TrxCode")]
     pub transaction_type: TransactionType,
     ///Credit Party Identifier.
     ///
     /// Can be a Mobile Number, Business Number, Agent
     /// Till, Paybill or Business number, or Merchant Buy Goods.
-    #[serde(rename = "CPI")]
-    pub credit_party_identifier: &'mpesa str,
+    pub credit_party_identifier: String,
     /// Size of the QR code image in pixels.
     ///
     /// QR code image will always be a square image.
-    pub size: &'mpesa str,
+    pub size: String,
+}
+
+impl MpesaRequest for DynamicQRRequest {
+    type Response = DynamicQRResponse;
+
+    const PATH: &'static str = DYNAMIC_QR_URL;
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(DynamicQRPayload {
+            merchant_name: &self.merchant_name,
+            ref_no: &self.ref_no,
+            amount: self.amount,
+            transaction_type: self.transaction_type,
+            credit_party_identifier: &self.credit_party_identifier,
+            size: &self.size,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all(serialize = "PascalCase"))]
+struct DynamicQRPayload<'mpesa> {
+    merchant_name: &'mpesa str,
+    ref_no: &'mpesa str,
+    amount: u32,
+    #[serde(rename = "TrxCode")]
+    transaction_type: TransactionType,
+    #[serde(rename = "CPI")]
+    credit_party_identifier: &'mpesa str,
+    size: &'mpesa str,
 }
 
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all(deserialize = "PascalCase"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct DynamicQRResponse {
-    #[serde(rename(deserialize = "QRCode"))]
+    #[serde(rename = "QRCode")]
     pub qr_code: String,
+    /// Unlike other APIs, Dynamic QR sends a reference rather than a numeric code here,
+    /// e.g `AG_20191219_000043fdf61864fe9ff5`
     pub response_code: String,
     pub response_description: String,
 }
@@ -84,17 +109,20 @@ pub struct DynamicQR<'mpesa> {
     /// QR code image will always be a square image.
     #[builder(setter(into))]
     size: &'mpesa str,
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    #[builder(setter(strip_option), default)]
+    retry_policy: Option<RetryPolicy>,
 }
 
-impl<'mpesa> From<DynamicQR<'mpesa>> for DynamicQRRequest<'mpesa> {
-    fn from(express: DynamicQR<'mpesa>) -> DynamicQRRequest<'mpesa> {
+impl From<DynamicQR<'_>> for DynamicQRRequest {
+    fn from(express: DynamicQR<'_>) -> DynamicQRRequest {
         DynamicQRRequest {
-            merchant_name: express.merchant_name,
-            ref_no: express.ref_no,
+            merchant_name: express.merchant_name.to_owned(),
+            ref_no: express.ref_no.to_owned(),
             amount: express.amount,
             transaction_type: express.transaction_type,
-            credit_party_identifier: express.credit_party_identifier,
-            size: express.size,
+            credit_party_identifier: express.credit_party_identifier.to_owned(),
+            size: express.size.to_owned(),
         }
     }
 }
@@ -107,15 +135,16 @@ impl<'mpesa> DynamicQR<'mpesa> {
     /// # Build Dynamic QR
     ///
     /// Returns a `DynamicQR` which can be used to send a request
-    pub fn from_request(client: &'mpesa Mpesa, request: DynamicQRRequest<'mpesa>) -> DynamicQR<'mpesa> {
+    pub fn from_request(client: &'mpesa Mpesa, request: &'mpesa DynamicQRRequest) -> DynamicQR<'mpesa> {
         DynamicQR {
             client,
-            merchant_name: request.merchant_name,
-            ref_no: request.ref_no,
+            merchant_name: &request.merchant_name,
+            ref_no: &request.ref_no,
             amount: request.amount,
             transaction_type: request.transaction_type,
-            credit_party_identifier: request.credit_party_identifier,
-            size: request.size,
+            credit_party_identifier: &request.credit_party_identifier,
+            size: &request.size,
+            retry_policy: None,
         }
     }
 
@@ -133,12 +162,7 @@ impl<'mpesa> DynamicQR<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<DynamicQRResponse> {
-        self.client
-            .send::<DynamicQRRequest, _>(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: DYNAMIC_QR_URL,
-                body: self.into(),
-            })
-            .await
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&DynamicQRRequest::from(self), retry_policy).await
     }
 }
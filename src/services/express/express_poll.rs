@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use super::MpesaExpressQueryResponse;
+use crate::constants::MpesaResponseCode;
+
+/// The final state of an Mpesa Express/ STK Push request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StkPushOutcome {
+    /// `0`: The customer completed the payment
+    Success,
+    /// `1`: The customer's balance is insufficient for the transaction
+    InsufficientFunds,
+    /// `1001`: A transaction is already in process for the customer
+    SubscriberBusy,
+    /// `1019`: The transaction expired before it was processed
+    TransactionExpired,
+    /// `1025` or `9999`: An error occurred while sending the push request
+    PushRequestFailed,
+    /// `1032`: The customer cancelled the prompt
+    CancelledByUser,
+    /// `1037`: The customer could not be reached or did not respond to the prompt in time
+    Timeout,
+    /// `2001`: The customer entered the wrong PIN
+    WrongPin,
+    /// Any other result code
+    Failed {
+        result_code: MpesaResponseCode,
+        result_desc: String,
+    },
+}
+
+impl StkPushOutcome {
+    /// Maps the `ResultCode` and `ResultDesc` of a query response or callback to an outcome
+    pub fn from_result(result_code: MpesaResponseCode, result_desc: &str) -> StkPushOutcome {
+        match result_code {
+            MpesaResponseCode::Success => StkPushOutcome::Success,
+            MpesaResponseCode::InsufficientFunds => StkPushOutcome::InsufficientFunds,
+            MpesaResponseCode::SubscriberLocked => StkPushOutcome::SubscriberBusy,
+            MpesaResponseCode::TransactionExpired => StkPushOutcome::TransactionExpired,
+            MpesaResponseCode::PushRequestError | MpesaResponseCode::PushRequestFailed => {
+                StkPushOutcome::PushRequestFailed
+            }
+            MpesaResponseCode::RequestCancelledByUser => StkPushOutcome::CancelledByUser,
+            MpesaResponseCode::DsTimeout => StkPushOutcome::Timeout,
+            MpesaResponseCode::InvalidInitiatorInformation => StkPushOutcome::WrongPin,
+            _ => StkPushOutcome::Failed {
+                result_code,
+                result_desc: result_desc.to_owned(),
+            },
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self, StkPushOutcome::Success)
+    }
+}
+
+impl MpesaExpressQueryResponse {
+    /// The outcome of the STK push
+    pub fn outcome(&self) -> StkPushOutcome {
+        StkPushOutcome::from_result(self.result_code, &self.result_desc)
+    }
+}
+
+/// The schedule on which an STK push is re-queried until it reaches a final state.
+///
+/// The first query is sent after `initial_delay`, after which the interval between queries starts at
+/// `interval` and grows by `multiplier` up to `max_interval`. Polling stops once `deadline` has elapsed
+/// since the start.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PollSchedule {
+    pub initial_delay: Duration,
+    pub interval: Duration,
+    pub multiplier: f64,
+    pub max_interval: Duration,
+    pub deadline: Duration,
+}
+
+impl Default for PollSchedule {
+    /// Polls every 3 to 15 seconds for up to 2 minutes, which covers the time M-Pesa gives the
+    /// customer to respond to the prompt
+    fn default() -> Self {
+        PollSchedule {
+            initial_delay: Duration::from_secs(3),
+            interval: Duration::from_secs(3),
+            multiplier: 1.5,
+            max_interval: Duration::from_secs(15),
+            deadline: Duration::from_secs(120),
+        }
+    }
+}
+
+impl PollSchedule {
+    /// Polls every `interval` until `deadline` has elapsed
+    pub fn fixed(interval: Duration, deadline: Duration) -> PollSchedule {
+        PollSchedule {
+            initial_delay: interval,
+            interval,
+            multiplier: 1.0,
+            max_interval: interval,
+            deadline,
+        }
+    }
+
+    pub(super) fn next_interval(&self, interval: Duration) -> Duration {
+        interval.mul_f64(self.multiplier.max(1.0)).min(self.max_interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome_from_result_code() {
+        let outcome = |code: i64, desc: &str| StkPushOutcome::from_result(MpesaResponseCode::from(code), desc);
+        assert_eq!(outcome(0, "Success"), StkPushOutcome::Success);
+        assert_eq!(outcome(1, "Insufficient"), StkPushOutcome::InsufficientFunds);
+        assert_eq!(outcome(1032, "Cancelled"), StkPushOutcome::CancelledByUser);
+        assert_eq!(outcome(1037, "Timeout"), StkPushOutcome::Timeout);
+        assert_eq!(outcome(2001, "Wrong PIN"), StkPushOutcome::WrongPin);
+        assert_eq!(
+            outcome(17, "Rule limited"),
+            StkPushOutcome::Failed {
+                result_code: MpesaResponseCode::InternalFailure,
+                result_desc: "Rule limited".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn test_poll_schedule_backs_off_up_to_max_interval() {
+        let schedule = PollSchedule {
+            interval: Duration::from_secs(4),
+            multiplier: 2.0,
+            max_interval: Duration::from_secs(10),
+            ..Default::default()
+        };
+        assert_eq!(schedule.next_interval(Duration::from_secs(4)), Duration::from_secs(8));
+        assert_eq!(schedule.next_interval(Duration::from_secs(8)), Duration::from_secs(10));
+
+        let fixed = PollSchedule::fixed(Duration::from_secs(5), Duration::from_secs(60));
+        assert_eq!(fixed.next_interval(Duration::from_secs(5)), Duration::from_secs(5));
+    }
+}
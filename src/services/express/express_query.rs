@@ -1,5 +1,7 @@
 #![doc = include_str!("../../../docs/client/express.md")]
 
+use std::fmt;
+
 use chrono::DateTime;
 use chrono::prelude::Local;
 use derive_builder::Builder;
@@ -14,30 +16,85 @@ cfg_if::cfg_if! {
 }
 
 use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
 
-use super::{DEFAULT_PASSKEY, serialize_utc_to_string};
+use super::{PollSchedule, StkPushOutcome, pass_key, serialize_utc_to_string};
 use crate::client::Mpesa;
-use crate::errors::{MpesaError, MpesaResult};
+use crate::constants::MpesaResponseCode;
+use crate::errors::{DarajaErrorCode, MpesaError, MpesaResult};
+use crate::redact;
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 
 const EXPRESS_QUERY_URL: &str = "mpesa/stkpushquery/v1/query";
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "PascalCase")]
-pub struct MpesaExpressQueryRequest<'mpesa> {
+/// The part of the `500.001.1001` error message that marks a push the customer has not yet responded to
+const PENDING_ERROR_MESSAGE: &str = "being processed";
+
+/// An owned Mpesa Express/ STK Push query, sent with [`Mpesa::execute`]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct MpesaExpressQueryRequest {
     /// This is the organization's shortcode (Paybill or Buygoods - A 5 to
     /// 6-digit account number) used to identify an organization and receive
     /// the transaction.
-    pub business_short_code: &'mpesa str,
-    /// This is the password used for encrypting the request sent:
-    pub password: String,
-    /// This is the Timestamp of the transaction, normally in the format of
-    /// (YYYYMMDDHHMMSS)
-    #[serde(serialize_with = "serialize_utc_to_string")]
-    pub timestamp: DateTime<Local>,
+    pub business_short_code: String,
     /// This is a global unique identifier of the processed checkout transaction
     /// request.
+    pub checkout_request_id: String,
+    /// The passkey the password of the request is derived from. Defaults to the passkey of the client, see
+    /// [`Mpesa::set_passkey`]
+    pub pass_key: Option<String>,
+}
+
+impl fmt::Debug for MpesaExpressQueryRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpesaExpressQueryRequest")
+            .field("business_short_code", &self.business_short_code)
+            .field("checkout_request_id", &self.checkout_request_id)
+            .field("pass_key", &redact::secret(&self.pass_key))
+            .finish()
+    }
+}
+
+impl MpesaRequest for MpesaExpressQueryRequest {
+    type Response = MpesaExpressQueryResponse;
+
+    const PATH: &'static str = EXPRESS_QUERY_URL;
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(MpesaExpressQueryPayload {
+            business_short_code: &self.business_short_code,
+            password: MpesaExpressQuery::encode_password(
+                &self.business_short_code,
+                &pass_key(client, self.pass_key.as_deref())?,
+            ),
+            timestamp: chrono::Local::now(),
+            checkout_request_id: &self.checkout_request_id,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct MpesaExpressQueryPayload<'mpesa> {
+    business_short_code: &'mpesa str,
+    password: String,
+    #[serde(serialize_with = "serialize_utc_to_string")]
+    timestamp: DateTime<Local>,
     #[serde(rename = "CheckoutRequestID")]
-    pub checkout_request_id: &'mpesa str,
+    checkout_request_id: &'mpesa str,
+}
+
+impl fmt::Debug for MpesaExpressQueryPayload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpesaExpressQueryPayload")
+            .field("business_short_code", &self.business_short_code)
+            .field("password", &redact::Secret)
+            .field("timestamp", &self.timestamp)
+            .field("checkout_request_id", &self.checkout_request_id)
+            .finish()
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -52,7 +109,7 @@ pub struct MpesaExpressQueryResponse {
     /// This is a Numeric status code that indicates the status of the
     /// transaction submission. 0 means successful submission and any other
     /// code means an error occurred.
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     ///Response description is an acknowledgment message from the API that
     /// gives the status of the request submission. It usually maps to a
     /// specific ResponseCode value.
@@ -63,14 +120,14 @@ pub struct MpesaExpressQueryResponse {
     /// This is a Numeric status code that indicates the status of the
     /// transaction submission. 0 means successful submission and any other
     /// code means an error occurred.
-    pub result_code: String,
+    pub result_code: MpesaResponseCode,
     ///Response description is an acknowledgment message from the API that
     /// gives the status of the request submission. It usually maps to a
     /// specific ResponseCode value.
     pub result_desc: String,
 }
 
-#[derive(Builder, Debug, Clone)]
+#[derive(Builder, Clone)]
 #[builder(build_fn(error = "MpesaError"))]
 pub struct MpesaExpressQuery<'mpesa> {
     #[builder(pattern = "immutable")]
@@ -85,26 +142,37 @@ pub struct MpesaExpressQuery<'mpesa> {
     /// The password for encrypting the request is obtained by base64 encoding
     /// BusinessShortCode, Passkey and Timestamp.
     /// The timestamp format is YYYYMMDDHHmmss
-    #[builder(setter(into, strip_option), default = "Some(DEFAULT_PASSKEY)")]
+    /// Defaults to the passkey of the client, see [`Mpesa::set_passkey`]
+    #[builder(setter(into, strip_option), default)]
     pass_key: Option<&'mpesa str>,
 
     /// This is a global unique identifier of the processed checkout transaction
     /// request.
     #[builder(setter(into))]
     checkout_request_id: &'mpesa str,
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    #[builder(setter(strip_option), default)]
+    retry_policy: Option<RetryPolicy>,
 }
 
-impl<'mpesa> From<MpesaExpressQuery<'mpesa>> for MpesaExpressQueryRequest<'mpesa> {
-    fn from(express: MpesaExpressQuery<'mpesa>) -> MpesaExpressQueryRequest<'mpesa> {
-        let timestamp = chrono::Local::now();
-
-        let encoded_password = MpesaExpressQuery::encode_password(express.business_short_code, express.pass_key);
+impl fmt::Debug for MpesaExpressQuery<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpesaExpressQuery")
+            .field("client", &self.client)
+            .field("business_short_code", &self.business_short_code)
+            .field("pass_key", &redact::secret(&self.pass_key))
+            .field("checkout_request_id", &self.checkout_request_id)
+            .field("retry_policy", &self.retry_policy)
+            .finish()
+    }
+}
 
+impl From<MpesaExpressQuery<'_>> for MpesaExpressQueryRequest {
+    fn from(express: MpesaExpressQuery<'_>) -> MpesaExpressQueryRequest {
         MpesaExpressQueryRequest {
-            business_short_code: express.business_short_code,
-            password: encoded_password,
-            timestamp,
-            checkout_request_id: express.checkout_request_id,
+            business_short_code: express.business_short_code.to_owned(),
+            checkout_request_id: express.checkout_request_id.to_owned(),
+            pass_key: express.pass_key.map(str::to_owned),
         }
     }
 }
@@ -119,30 +187,21 @@ impl<'mpesa> MpesaExpressQuery<'mpesa> {
     /// The password for encrypting the request is obtained by base64 encoding
     /// BusinessShortCode, Passkey and Timestamp.
     /// The timestamp format is YYYYMMDDHHmmss
-    pub fn encode_password(business_short_code: &str, pass_key: Option<&'mpesa str>) -> String {
+    ///
+    /// `pass_key` is the Lipa Na M-Pesa Online passkey of the shortcode, there is no sandbox default
+    pub fn encode_password(business_short_code: &str, pass_key: &str) -> String {
         let timestamp = chrono::Local::now().format("%Y%m%d%H%M%S").to_string();
-        encode_block(
-            format!(
-                "{}{}{}",
-                business_short_code,
-                pass_key.unwrap_or(DEFAULT_PASSKEY),
-                timestamp
-            )
-            .as_bytes(),
-        )
+        encode_block(format!("{}{}{}", business_short_code, pass_key, timestamp).as_bytes())
     }
 
     /// Creates a new `MpesaExpressQuery` from a `MpesaExpressQueryRequest`
-    pub fn from_request(
-        client: &'mpesa Mpesa,
-        request: MpesaExpressQueryRequest<'mpesa>,
-        pass_key: Option<&'mpesa str>,
-    ) -> MpesaExpressQuery<'mpesa> {
+    pub fn from_request(client: &'mpesa Mpesa, request: &'mpesa MpesaExpressQueryRequest) -> MpesaExpressQuery<'mpesa> {
         MpesaExpressQuery {
             client,
-            business_short_code: request.business_short_code,
-            checkout_request_id: request.checkout_request_id,
-            pass_key,
+            business_short_code: &request.business_short_code,
+            checkout_request_id: &request.checkout_request_id,
+            pass_key: request.pass_key.as_deref(),
+            retry_policy: None,
         }
     }
 
@@ -155,12 +214,54 @@ impl<'mpesa> MpesaExpressQuery<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<MpesaExpressQueryResponse> {
-        self.client
-            .send::<MpesaExpressQueryRequest, _>(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: EXPRESS_QUERY_URL,
-                body: self.into(),
-            })
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client
+            .call_with(&MpesaExpressQueryRequest::from(self), retry_policy)
             .await
     }
+
+    /// Re-queries the status of the STK push on the given `schedule` until it reaches a final state.
+    ///
+    /// `500.001.1001` responses saying the transaction is still being processed are treated as pending, other
+    /// errors sharing that code, e.g an unknown merchant or a locked subscriber, end the polling. Each query is
+    /// cut short at the deadline.
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::PollDeadlineExceeded` if the push is still pending at the deadline, or the
+    /// `MpesaError` of the first query that fails for any other reason
+    pub async fn poll(self, schedule: PollSchedule) -> MpesaResult<StkPushOutcome> {
+        let deadline = Instant::now() + schedule.deadline;
+        let mut delay = schedule.initial_delay;
+        let mut interval = schedule.interval;
+
+        loop {
+            if Instant::now() + delay > deadline {
+                return Err(MpesaError::PollDeadlineExceeded(self.checkout_request_id.to_owned()));
+            }
+            tokio::time::sleep(delay).await;
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let Ok(response) = tokio::time::timeout(remaining, self.clone().send()).await else {
+                return Err(MpesaError::PollDeadlineExceeded(self.checkout_request_id.to_owned()));
+            };
+            match response {
+                Ok(response) => return Ok(response.outcome()),
+                // Returned while the customer has not yet responded to the prompt
+                Err(MpesaError::Service(e))
+                    if e.code() == DarajaErrorCode::ServerError
+                        && e.error_message.to_lowercase().contains(PENDING_ERROR_MESSAGE) =>
+                {
+                    log::debug!(
+                        "STK push {} is still pending: {}",
+                        self.checkout_request_id,
+                        e.error_message
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+
+            delay = interval;
+            interval = schedule.next_interval(interval);
+        }
+    }
 }
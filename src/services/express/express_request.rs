@@ -1,5 +1,8 @@
 #![doc = include_str!("../../../docs/client/express.md")]
 
+use std::fmt;
+use std::time::Duration;
+
 use chrono::DateTime;
 use chrono::prelude::Local;
 use derive_builder::Builder;
@@ -16,26 +19,24 @@ cfg_if::cfg_if! {
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use super::{DEFAULT_PASSKEY, serialize_utc_to_string};
+use super::{MpesaExpressQuery, PollSchedule, StkPushOutcome, pass_key, serialize_utc_to_string};
+use crate::callbacks::StkCallback;
 use crate::client::Mpesa;
-use crate::constants::CommandId;
+use crate::constants::{CommandId, MpesaResponseCode};
 use crate::errors::{MpesaError, MpesaResult};
+use crate::redact;
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 use crate::validator::PhoneNumberValidator;
 const EXPRESS_REQUEST_URL: &str = "mpesa/stkpush/v1/processrequest";
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "PascalCase")]
-pub struct MpesaExpressRequest<'mpesa> {
+/// An owned Mpesa Express/ STK Push request, sent with [`Mpesa::execute`]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct MpesaExpressRequest {
     /// This is the organization's shortcode (Paybill or Buygoods - A 5 to
     /// 6-digit account number) used to identify an organization and receive
     /// the transaction.
-    pub business_short_code: &'mpesa str,
-    /// This is the password used for encrypting the request sent:
-    pub password: String,
-    /// This is the Timestamp of the transaction, normally in the format of
-    /// (YYYYMMDDHHMMSS)
-    #[serde(serialize_with = "serialize_utc_to_string")]
-    pub timestamp: DateTime<Local>,
+    pub business_short_code: String,
     /// This is the transaction type that is used to identify the transaction
     /// when sending the request to M-PESA
     ///
@@ -47,28 +48,110 @@ pub struct MpesaExpressRequest<'mpesa> {
     /// This is the Amount transacted normally a numeric value
     pub amount: u32,
     ///The phone number sending money.
-    pub party_a: &'mpesa str,
+    pub party_a: String,
     /// The organization that receives the funds
     /// This should be the Till Number in the case of `CommandId::CustomerBuyGoodsOnline`
-    pub party_b: &'mpesa str,
+    pub party_b: String,
     /// The Mobile Number to receive the STK Pin Prompt.
     /// This number can be the same as PartyA value above.
     ///
     ///  The parameter expected is a Valid Safaricom Mobile Number that is
     /// M-PESA registered in the format 2547XXXXXXXX
-    pub phone_number: &'mpesa str,
+    pub phone_number: String,
     /// A CallBack URL is a valid secure URL that is used to receive
     /// notifications from M-Pesa API.
     /// It is the endpoint to which the results will be sent by M-Pesa API.
-    #[serde(rename = "CallBackURL")]
     pub call_back_url: Url,
     /// Account Reference: This is an Alpha-Numeric parameter that is defined
     /// by your system as an Identifier of the transaction for
     /// CustomerPayBillOnline
-    pub account_reference: &'mpesa str,
+    pub account_reference: String,
     /// This is any additional information/comment that can be sent along with
     /// the request from your system
-    pub transaction_desc: Option<&'mpesa str>,
+    pub transaction_desc: Option<String>,
+    /// The passkey the password of the request is derived from. Defaults to the passkey of the client, see
+    /// [`Mpesa::set_passkey`]
+    pub pass_key: Option<String>,
+}
+
+impl fmt::Debug for MpesaExpressRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpesaExpressRequest")
+            .field("business_short_code", &self.business_short_code)
+            .field("transaction_type", &self.transaction_type)
+            .field("amount", &self.amount)
+            .field("party_a", &redact::Msisdn(&self.party_a))
+            .field("party_b", &self.party_b)
+            .field("phone_number", &redact::Msisdn(&self.phone_number))
+            .field("call_back_url", &self.call_back_url)
+            .field("account_reference", &self.account_reference)
+            .field("transaction_desc", &self.transaction_desc)
+            .field("pass_key", &redact::secret(&self.pass_key))
+            .finish()
+    }
+}
+
+impl MpesaRequest for MpesaExpressRequest {
+    type Response = MpesaExpressResponse;
+
+    const PATH: &'static str = EXPRESS_REQUEST_URL;
+    const IDEMPOTENT: bool = false;
+
+    fn payload(&self, client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        validate(Some(self.transaction_type), Some(&self.phone_number))?;
+        Ok(MpesaExpressPayload {
+            business_short_code: &self.business_short_code,
+            password: MpesaExpress::encode_password(
+                &self.business_short_code,
+                &pass_key(client, self.pass_key.as_deref())?,
+            ),
+            timestamp: chrono::Local::now(),
+            transaction_type: self.transaction_type,
+            amount: self.amount,
+            party_a: &self.party_a,
+            party_b: &self.party_b,
+            phone_number: &self.phone_number,
+            call_back_url: &self.call_back_url,
+            account_reference: &self.account_reference,
+            transaction_desc: self.transaction_desc.as_deref(),
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct MpesaExpressPayload<'mpesa> {
+    business_short_code: &'mpesa str,
+    password: String,
+    #[serde(serialize_with = "serialize_utc_to_string")]
+    timestamp: DateTime<Local>,
+    transaction_type: CommandId,
+    amount: u32,
+    party_a: &'mpesa str,
+    party_b: &'mpesa str,
+    phone_number: &'mpesa str,
+    #[serde(rename = "CallBackURL")]
+    call_back_url: &'mpesa Url,
+    account_reference: &'mpesa str,
+    transaction_desc: Option<&'mpesa str>,
+}
+
+impl fmt::Debug for MpesaExpressPayload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpesaExpressPayload")
+            .field("business_short_code", &self.business_short_code)
+            .field("password", &redact::Secret)
+            .field("timestamp", &self.timestamp)
+            .field("transaction_type", &self.transaction_type)
+            .field("amount", &self.amount)
+            .field("party_a", &redact::Msisdn(self.party_a))
+            .field("party_b", &self.party_b)
+            .field("phone_number", &redact::Msisdn(self.phone_number))
+            .field("call_back_url", &self.call_back_url)
+            .field("account_reference", &self.account_reference)
+            .field("transaction_desc", &self.transaction_desc)
+            .finish()
+    }
 }
 
 // TODO:: The success response has more fields than this
@@ -88,7 +171,7 @@ pub struct MpesaExpressResponse {
     /// This is a Numeric status code that indicates the status of the
     /// transaction submission. 0 means successful submission and any other
     /// code means an error occurred.
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
     ///Response description is an acknowledgment message from the API that
     /// gives the status of the request submission. It usually maps to a
     /// specific ResponseCode value.
@@ -97,7 +180,7 @@ pub struct MpesaExpressResponse {
     pub response_description: String,
 }
 
-#[derive(Builder, Debug, Clone)]
+#[derive(Builder, Clone)]
 #[builder(build_fn(error = "MpesaError", validate = "Self::validate"))]
 pub struct MpesaExpress<'mpesa> {
     #[builder(pattern = "immutable")]
@@ -142,52 +225,75 @@ pub struct MpesaExpress<'mpesa> {
     /// The password for encrypting the request is obtained by base64 encoding
     /// BusinessShortCode, Passkey and Timestamp.
     /// The timestamp format is YYYYMMDDHHmmss
-    #[builder(setter(into, strip_option), default = "Some(DEFAULT_PASSKEY)")]
+    /// Defaults to the passkey of the client, see [`Mpesa::set_passkey`]
+    #[builder(setter(into, strip_option), default)]
     pass_key: Option<&'mpesa str>,
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    #[builder(setter(strip_option), default)]
+    retry_policy: Option<RetryPolicy>,
 }
 
-impl<'mpesa> From<MpesaExpress<'mpesa>> for MpesaExpressRequest<'mpesa> {
-    fn from(express: MpesaExpress<'mpesa>) -> MpesaExpressRequest<'mpesa> {
-        let timestamp = chrono::Local::now();
-
-        let encoded_password = MpesaExpress::encode_password(express.business_short_code, express.pass_key);
+impl fmt::Debug for MpesaExpress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpesaExpress")
+            .field("client", &self.client)
+            .field("business_short_code", &self.business_short_code)
+            .field("transaction_type", &self.transaction_type)
+            .field("amount", &self.amount)
+            .field("party_a", &redact::Msisdn(self.party_a))
+            .field("party_b", &self.party_b)
+            .field("phone_number", &redact::Msisdn(self.phone_number))
+            .field("callback_url", &self.callback_url)
+            .field("account_ref", &self.account_ref)
+            .field("transaction_desc", &self.transaction_desc)
+            .field("pass_key", &redact::secret(&self.pass_key))
+            .field("retry_policy", &self.retry_policy)
+            .finish()
+    }
+}
 
+impl From<MpesaExpress<'_>> for MpesaExpressRequest {
+    fn from(express: MpesaExpress<'_>) -> MpesaExpressRequest {
         MpesaExpressRequest {
-            business_short_code: express.business_short_code,
-            password: encoded_password,
-            timestamp,
+            business_short_code: express.business_short_code.to_owned(),
             transaction_type: express.transaction_type,
             amount: express.amount,
-            party_a: express.party_a,
-            party_b: express.party_b,
-            phone_number: express.phone_number,
+            party_a: express.party_a.to_owned(),
+            party_b: express.party_b.to_owned(),
+            phone_number: express.phone_number.to_owned(),
             call_back_url: express.callback_url,
-            account_reference: express.account_ref,
-            transaction_desc: express.transaction_desc,
+            account_reference: express.account_ref.to_owned(),
+            transaction_desc: express.transaction_desc.map(str::to_owned),
+            pass_key: express.pass_key.map(str::to_owned),
         }
     }
 }
 
+/// Validates an express request, returning a `MpesaError` if validation fails
+///
+/// Express requests can only be of type `BusinessBuyGoods`, `CustomerPayBillOnline` or
+/// `CustomerBuyGoodsOnline`
+fn validate(transaction_type: Option<CommandId>, phone_number: Option<&str>) -> MpesaResult<()> {
+    if transaction_type != Some(CommandId::BusinessBuyGoods)
+        && transaction_type != Some(CommandId::CustomerPayBillOnline)
+        && transaction_type != Some(CommandId::CustomerBuyGoodsOnline)
+    {
+        return Err(MpesaError::Message(
+            "Invalid transaction type. Expected BusinessBuyGoods or CustomerPayBillOnline or CustomerBuyGoodsOnline",
+        ));
+    }
+
+    if let Some(phone_number) = phone_number {
+        phone_number.validate()?;
+    }
+
+    Ok(())
+}
+
 impl MpesaExpressBuilder<'_> {
     /// Validates the request, returning a `MpesaError` if validation fails
-    ///
-    /// Express requests can only be of type `BusinessBuyGoods` or
-    /// `CustomerPayBillOnline`
     fn validate(&self) -> MpesaResult<()> {
-        if self.transaction_type != Some(CommandId::BusinessBuyGoods)
-            && self.transaction_type != Some(CommandId::CustomerPayBillOnline)
-            && self.transaction_type != Some(CommandId::CustomerBuyGoodsOnline)
-        {
-            return Err(MpesaError::Message(
-                "Invalid transaction type. Expected BusinessBuyGoods or CustomerPayBillOnline or CustomerBuyGoodsOnline",
-            ));
-        }
-
-        if let Some(phone_number) = self.phone_number {
-            phone_number.validate()?;
-        }
-
-        Ok(())
+        validate(self.transaction_type, self.phone_number)
     }
 }
 
@@ -201,37 +307,28 @@ impl<'mpesa> MpesaExpress<'mpesa> {
     /// The password for encrypting the request is obtained by base64 encoding
     /// BusinessShortCode, Passkey and Timestamp.
     /// The timestamp format is YYYYMMDDHHmmss
-    pub fn encode_password(business_short_code: &str, pass_key: Option<&'mpesa str>) -> String {
+    ///
+    /// `pass_key` is the Lipa Na M-Pesa Online passkey of the shortcode, there is no sandbox default
+    pub fn encode_password(business_short_code: &str, pass_key: &str) -> String {
         let timestamp = chrono::Local::now().format("%Y%m%d%H%M%S").to_string();
-        encode_block(
-            format!(
-                "{}{}{}",
-                business_short_code,
-                pass_key.unwrap_or(DEFAULT_PASSKEY),
-                timestamp
-            )
-            .as_bytes(),
-        )
+        encode_block(format!("{}{}{}", business_short_code, pass_key, timestamp).as_bytes())
     }
 
     /// Creates a new `MpesaExpress` from a `MpesaExpressRequest`
-    pub fn from_request(
-        client: &'mpesa Mpesa,
-        request: MpesaExpressRequest<'mpesa>,
-        pass_key: Option<&'mpesa str>,
-    ) -> MpesaExpress<'mpesa> {
+    pub fn from_request(client: &'mpesa Mpesa, request: &'mpesa MpesaExpressRequest) -> MpesaExpress<'mpesa> {
         MpesaExpress {
             client,
-            business_short_code: request.business_short_code,
+            business_short_code: &request.business_short_code,
             transaction_type: request.transaction_type,
             amount: request.amount,
-            party_a: request.party_a,
-            party_b: request.party_b,
-            phone_number: request.phone_number,
-            callback_url: request.call_back_url,
-            account_ref: request.account_reference,
-            transaction_desc: request.transaction_desc,
-            pass_key,
+            party_a: &request.party_a,
+            party_b: &request.party_b,
+            phone_number: &request.phone_number,
+            callback_url: request.call_back_url.clone(),
+            account_ref: &request.account_reference,
+            transaction_desc: request.transaction_desc.as_deref(),
+            pass_key: request.pass_key.as_deref(),
+            retry_policy: None,
         }
     }
 
@@ -244,12 +341,39 @@ impl<'mpesa> MpesaExpress<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure
     pub async fn send(self) -> MpesaResult<MpesaExpressResponse> {
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&MpesaExpressRequest::from(self), retry_policy).await
+    }
+
+    /// Sends the request and waits for its callback, correlated by `CheckoutRequestID` through [`Mpesa::callbacks`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` on failure, or a `MpesaError::CallbackTimeout` if the callback does not
+    /// arrive within `timeout`
+    pub async fn send_and_wait(self, timeout: Duration) -> MpesaResult<StkCallback> {
         self.client
-            .send::<MpesaExpressRequest, _>(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: EXPRESS_REQUEST_URL,
-                body: self.into(),
-            })
+            .callbacks()
+            .send_and_wait(self.send(), |response| &response.checkout_request_id, timeout)
             .await
     }
+
+    /// Sends the STK push and polls its status on the given `schedule` until it reaches a final state
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` if the request fails, see [`MpesaExpressQuery::poll`] for the errors of polling
+    pub async fn send_and_poll(self, schedule: PollSchedule) -> MpesaResult<StkPushOutcome> {
+        let client = self.client;
+        let business_short_code = self.business_short_code;
+        let pass_key = self.pass_key;
+        let response = self.send().await?;
+
+        let mut query = MpesaExpressQuery::builder(client);
+        query
+            .business_short_code(business_short_code)
+            .checkout_request_id(response.checkout_request_id.as_str());
+        if let Some(pass_key) = pass_key {
+            query.pass_key(pass_key);
+        }
+        query.build()?.poll(schedule).await
+    }
 }
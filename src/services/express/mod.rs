@@ -1,13 +1,33 @@
+pub mod express_poll;
 pub mod express_query;
 pub mod express_request;
 
 use chrono::{DateTime, Local};
-pub use express_query::{MpesaExpressQuery, MpesaExpressQueryBuilder, MpesaExpressQueryResponse};
+pub use express_poll::{PollSchedule, StkPushOutcome};
+pub use express_query::{
+    MpesaExpressQuery, MpesaExpressQueryBuilder, MpesaExpressQueryRequest, MpesaExpressQueryResponse,
+};
 pub use express_request::{MpesaExpress, MpesaExpressBuilder, MpesaExpressRequest, MpesaExpressResponse};
 
+use crate::client::Mpesa;
+use crate::errors::{MpesaError, MpesaResult};
+
 /// Source: [test credentials](https://developer.safaricom.co.ke/test_credentials)
 pub static DEFAULT_PASSKEY: &str = "bfb279f9aa9bdbcf158e97dd71a467cd2e0c893059b10f78e6b72ada1ed2c919";
 
+/// The passkey a request password is derived from: the passkey of the request, else of the client, else
+/// [`DEFAULT_PASSKEY`] in sandbox
+///
+/// # Errors
+/// Returns a `MpesaError::MissingCredential` if no passkey is set in production
+fn pass_key(client: &Mpesa, pass_key: Option<&str>) -> MpesaResult<String> {
+    match pass_key.map(str::to_owned).or_else(|| client.passkey()) {
+        Some(pass_key) => Ok(pass_key),
+        None if client.is_production() => Err(MpesaError::MissingCredential("passkey")),
+        None => Ok(DEFAULT_PASSKEY.to_owned()),
+    }
+}
+
 /// Helper function to serialize a `DateTime<Local>` to a string
 fn serialize_utc_to_string<S>(date: &DateTime<Local>, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -5,6 +5,11 @@
 //! Some of the builder methods for certain services are optional with default values standing in
 //! their place when the builder gets consumed
 //!
+//! Every service also has an owned request type e.g [`B2cRequest`], which does not borrow the client.
+//! Owned requests can be moved into tasks, queued or (de)serialized, and are sent with
+//! [`Mpesa::execute`](crate::Mpesa::execute). The builders produce them through their `build` method.
+//! All of them implement [`MpesaRequest`](crate::MpesaRequest), which ties a request to its endpoint.
+//!
 //! Here are the currently supported services:
 //! 1. [Account Balance](https://developer.safaricom.co.ke/APIs/AccountBalance)
 //! 2. [B2B](https://developer.safaricom.co.ke/APIs/BusinessPayBill)
@@ -49,27 +54,27 @@ mod transaction_reversal;
 mod transaction_status;
 
 #[cfg(feature = "account_balance")]
-pub use account_balance::{AccountBalanceBuilder, AccountBalanceResponse};
+pub use account_balance::{AccountBalanceBuilder, AccountBalanceRequest, AccountBalanceResponse};
 #[cfg(feature = "b2b")]
-pub use b2b::{B2bBuilder, B2bResponse};
+pub use b2b::{B2bBuilder, B2bRequest, B2bResponse};
 #[cfg(feature = "b2c")]
-pub use b2c::{B2cBuilder, B2cResponse};
+pub use b2c::{B2cBuilder, B2cRequest, B2cResponse};
 #[cfg(feature = "bill_manager")]
 pub use bill_manager::*;
 #[cfg(feature = "c2b_register")]
-pub use c2b_register::{C2bRegisterBuilder, C2bRegisterResponse};
+pub use c2b_register::{C2bRegisterBuilder, C2bRegisterRequest, C2bRegisterResponse};
 #[cfg(feature = "c2b_simulate")]
-pub use c2b_simulate::{C2bSimulateBuilder, C2bSimulateResponse};
+pub use c2b_simulate::{C2bSimulateBuilder, C2bSimulateRequest, C2bSimulateResponse};
 #[cfg(feature = "dynamic_qr")]
 pub use dynamic_qr::{DynamicQR, DynamicQRBuilder, DynamicQRRequest, DynamicQRResponse};
 #[cfg(feature = "express")]
 pub use express::{
-    MpesaExpress, MpesaExpressBuilder, MpesaExpressQuery, MpesaExpressQueryBuilder, MpesaExpressQueryResponse,
-    MpesaExpressRequest, MpesaExpressResponse,
+    MpesaExpress, MpesaExpressBuilder, MpesaExpressQuery, MpesaExpressQueryBuilder, MpesaExpressQueryRequest,
+    MpesaExpressQueryResponse, MpesaExpressRequest, MpesaExpressResponse, PollSchedule, StkPushOutcome,
 };
 #[cfg(feature = "transaction_reversal")]
 pub use transaction_reversal::{
     TransactionReversal, TransactionReversalBuilder, TransactionReversalRequest, TransactionReversalResponse,
 };
 #[cfg(feature = "transaction_status")]
-pub use transaction_status::{TransactionStatusBuilder, TransactionStatusResponse};
+pub use transaction_status::{TransactionStatusBuilder, TransactionStatusRequest, TransactionStatusResponse};
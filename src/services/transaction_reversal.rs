@@ -1,46 +1,107 @@
 #![doc = include_str!("../../docs/client/transaction_reversal.md")]
 
+use std::fmt;
+use std::time::Duration;
+
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use crate::{CommandId, IdentifierTypes, Mpesa, MpesaError, MpesaResult};
+use crate::callbacks::ResultCallback;
+use crate::redact;
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
+use crate::{CommandId, IdentifierTypes, Mpesa, MpesaError, MpesaResponseCode, MpesaResult};
 
 const TRANSACTION_REVERSAL_URL: &str = "mpesa/reversal/v1/request";
 
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "PascalCase")]
-pub struct TransactionReversalRequest<'mpesa> {
+/// An owned Transaction Reversal request, sent with [`Mpesa::execute`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionReversalRequest {
     /// The name of the initiator to initiate the request.
-    pub initiator: &'mpesa str,
-    /// Encrypted Credential of user getting transaction reversed.
-    pub security_credential: String,
-    /// Unique command for each transaction type.
-    #[serde(rename = "CommandID")]
-    pub command_id: CommandId,
+    pub initiator: String,
     /// This is the Mpesa Transaction ID of the transaction which you wish to
-    #[serde(rename = "TransactionID")]
-    pub transaction_id: &'mpesa str,
+    /// reverse.
+    pub transaction_id: String,
     /// The organization that receives the transaction.
-    pub receiver_party: &'mpesa str,
+    pub receiver_party: String,
     /// Type of organization that receives the transaction.
-    #[serde(rename(serialize = "RecieverIdentifierType"))]
     pub receiver_identifier_type: IdentifierTypes,
     /// The path that stores information about the transaction.
-    #[serde(rename = "ResultURL")]
     pub result_url: Url,
     /// The path that stores information about the time-out transaction.
-    #[serde(rename = "QueueTimeOutURL")]
     pub queue_timeout_url: Url,
     /// Comments that are sent along with the transaction.
-    pub remarks: &'mpesa str,
+    pub remarks: String,
     /// Comments that are sent along with the transaction.
-    pub occasion: Option<&'mpesa str>,
+    pub occasion: Option<String>,
     /// The amount transacted in the transaction is to be reversed, down to the
     /// cent.
     pub amount: u32,
 }
 
+impl MpesaRequest for TransactionReversalRequest {
+    type Response = TransactionReversalResponse;
+
+    const PATH: &'static str = TRANSACTION_REVERSAL_URL;
+    const IDEMPOTENT: bool = false;
+
+    fn payload(&self, client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(TransactionReversalPayload {
+            initiator: &self.initiator,
+            security_credential: client.gen_security_credentials(&self.initiator)?,
+            command_id: CommandId::TransactionReversal,
+            transaction_id: &self.transaction_id,
+            receiver_party: &self.receiver_party,
+            receiver_identifier_type: self.receiver_identifier_type,
+            result_url: &self.result_url,
+            queue_timeout_url: &self.queue_timeout_url,
+            remarks: &self.remarks,
+            occasion: self.occasion.as_deref(),
+            amount: self.amount,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct TransactionReversalPayload<'mpesa> {
+    initiator: &'mpesa str,
+    security_credential: String,
+    #[serde(rename = "CommandID")]
+    command_id: CommandId,
+    #[serde(rename = "TransactionID")]
+    transaction_id: &'mpesa str,
+    receiver_party: &'mpesa str,
+    #[serde(rename(serialize = "RecieverIdentifierType"))]
+    receiver_identifier_type: IdentifierTypes,
+    #[serde(rename = "ResultURL")]
+    result_url: &'mpesa Url,
+    #[serde(rename = "QueueTimeOutURL")]
+    queue_timeout_url: &'mpesa Url,
+    remarks: &'mpesa str,
+    occasion: Option<&'mpesa str>,
+    amount: u32,
+}
+
+impl fmt::Debug for TransactionReversalPayload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransactionReversalPayload")
+            .field("initiator", &self.initiator)
+            .field("security_credential", &redact::Secret)
+            .field("command_id", &self.command_id)
+            .field("transaction_id", &self.transaction_id)
+            .field("receiver_party", &self.receiver_party)
+            .field("receiver_identifier_type", &self.receiver_identifier_type)
+            .field("result_url", &self.result_url)
+            .field("queue_timeout_url", &self.queue_timeout_url)
+            .field("remarks", &self.remarks)
+            .field("occasion", &self.occasion)
+            .field("amount", &self.amount)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct TransactionReversalResponse {
@@ -53,7 +114,7 @@ pub struct TransactionReversalResponse {
     /// Response Description message
     pub response_description: String,
     /// Response Code
-    pub response_code: String,
+    pub response_code: MpesaResponseCode,
 }
 
 #[derive(Builder, Debug)]
@@ -87,26 +148,24 @@ pub struct TransactionReversal<'mpesa> {
     /// The amount transacted in the transaction is to be reversed, down to the
     /// cent.
     amount: u32,
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    #[builder(setter(strip_option), default)]
+    retry_policy: Option<RetryPolicy>,
 }
 
-impl<'mpesa> TryFrom<TransactionReversal<'mpesa>> for TransactionReversalRequest<'mpesa> {
-    type Error = MpesaError;
-    fn try_from(value: TransactionReversal<'mpesa>) -> Result<TransactionReversalRequest<'mpesa>, Self::Error> {
-        let credentials = value.client.gen_security_credentials()?;
-
-        Ok(TransactionReversalRequest {
-            initiator: value.initiator,
-            security_credential: credentials,
-            command_id: CommandId::TransactionReversal,
-            transaction_id: value.transaction_id,
-            receiver_party: value.receiver_party,
+impl From<TransactionReversal<'_>> for TransactionReversalRequest {
+    fn from(value: TransactionReversal<'_>) -> TransactionReversalRequest {
+        TransactionReversalRequest {
+            initiator: value.initiator.to_owned(),
+            transaction_id: value.transaction_id.to_owned(),
+            receiver_party: value.receiver_party.to_owned(),
             receiver_identifier_type: value.receiver_identifier_type,
             result_url: value.result_url,
             queue_timeout_url: value.timeout_url,
-            remarks: value.remarks,
-            occasion: value.occasion,
+            remarks: value.remarks.to_owned(),
+            occasion: value.occasion.map(str::to_owned),
             amount: value.amount,
-        })
+        }
     }
 }
 
@@ -119,19 +178,20 @@ impl<'mpesa> TransactionReversal<'mpesa> {
     /// Creates a new `TransactionReversal` from a `TransactionReversalRequest`
     pub fn from_request(
         client: &'mpesa Mpesa,
-        request: TransactionReversalRequest<'mpesa>,
+        request: &'mpesa TransactionReversalRequest,
     ) -> TransactionReversal<'mpesa> {
         TransactionReversal {
             client,
-            initiator: request.initiator,
-            transaction_id: request.transaction_id,
-            receiver_party: request.receiver_party,
-            result_url: request.result_url,
-            timeout_url: request.queue_timeout_url,
-            remarks: request.remarks,
-            occasion: request.occasion,
+            initiator: &request.initiator,
+            transaction_id: &request.transaction_id,
+            receiver_party: &request.receiver_party,
+            result_url: request.result_url.clone(),
+            timeout_url: request.queue_timeout_url.clone(),
+            remarks: &request.remarks,
+            occasion: request.occasion.as_deref(),
             amount: request.amount,
             receiver_identifier_type: request.receiver_identifier_type,
+            retry_policy: None,
         }
     }
 
@@ -155,12 +215,21 @@ impl<'mpesa> TransactionReversal<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure.
     pub async fn send(self) -> MpesaResult<TransactionReversalResponse> {
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client
+            .call_with(&TransactionReversalRequest::from(self), retry_policy)
+            .await
+    }
+
+    /// Sends the request and waits for its result, correlated by `ConversationID` through [`Mpesa::callbacks`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` on failure, or a `MpesaError::CallbackTimeout` if the result does not
+    /// arrive within `timeout`
+    pub async fn send_and_wait(self, timeout: Duration) -> MpesaResult<ResultCallback> {
         self.client
-            .send::<TransactionReversalRequest, _>(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: TRANSACTION_REVERSAL_URL,
-                body: self.try_into()?,
-            })
+            .callbacks()
+            .send_and_wait(self.send(), |response| &response.conversation_id, timeout)
             .await
     }
 }
@@ -1,17 +1,63 @@
 #![doc = include_str!("../../docs/client/transaction_status.md")]
 
+use std::fmt;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 
+use crate::callbacks::ResultCallback;
+use crate::redact;
+use crate::request::MpesaRequest;
+use crate::retry::RetryPolicy;
 use crate::{CommandId, IdentifierTypes, Mpesa, MpesaError, MpesaResult};
 
 const TRANSACTION_STATUS_URL: &str = "mpesa/transactionstatus/v1/query";
 
-#[derive(Debug, Serialize)]
+/// An owned Transaction Status request, sent with [`Mpesa::execute`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionStatusRequest {
+    /// The credential/ username used to authenticate the transaction request
+    pub initiator: String,
+    pub command_id: CommandId,
+    /// The Mpesa Transaction ID of the transaction whose status is queried
+    pub transaction_id: String,
+    /// The shortcode that received the transaction
+    pub party_a: String,
+    pub identifier_type: IdentifierTypes,
+    pub result_url: String,
+    pub timeout_url: String,
+    pub remarks: String,
+    pub occasion: String,
+}
+
+impl MpesaRequest for TransactionStatusRequest {
+    type Response = TransactionStatusResponse;
+
+    const PATH: &'static str = TRANSACTION_STATUS_URL;
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(TransactionStatusPayload {
+            initiator: &self.initiator,
+            security_credentials: client.gen_security_credentials(&self.initiator)?,
+            command_id: self.command_id,
+            transaction_id: &self.transaction_id,
+            party_a: &self.party_a,
+            identifier_type: self.identifier_type,
+            result_url: &self.result_url,
+            timeout_url: &self.timeout_url,
+            remarks: &self.remarks,
+            occasion: &self.occasion,
+        })
+    }
+}
+
+#[derive(Serialize)]
 pub struct TransactionStatusPayload<'mpesa> {
     #[serde(rename(serialize = "Initiator"))]
     initiator: &'mpesa str,
     #[serde(rename(serialize = "SecurityCredential"))]
-    security_credentials: &'mpesa str,
+    security_credentials: String,
     #[serde(rename(serialize = "CommandID"))]
     command_id: CommandId,
     #[serde(rename(serialize = "TransactionID"))]
@@ -30,6 +76,23 @@ pub struct TransactionStatusPayload<'mpesa> {
     occasion: &'mpesa str,
 }
 
+impl fmt::Debug for TransactionStatusPayload<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransactionStatusPayload")
+            .field("initiator", &self.initiator)
+            .field("security_credentials", &redact::Secret)
+            .field("command_id", &self.command_id)
+            .field("transaction_id", &self.transaction_id)
+            .field("party_a", &self.party_a)
+            .field("identifier_type", &self.identifier_type)
+            .field("result_url", &self.result_url)
+            .field("timeout_url", &self.timeout_url)
+            .field("remarks", &self.remarks)
+            .field("occasion", &self.occasion)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionStatusResponse {
     #[serde(rename(deserialize = "ConversationID"))]
@@ -52,6 +115,7 @@ pub struct TransactionStatusBuilder<'mpesa> {
     timeout_url: Option<&'mpesa str>,
     remarks: Option<&'mpesa str>,
     occasion: Option<&'mpesa str>,
+    retry_policy: Option<RetryPolicy>,
 }
 
 impl<'mpesa> TransactionStatusBuilder<'mpesa> {
@@ -68,9 +132,16 @@ impl<'mpesa> TransactionStatusBuilder<'mpesa> {
             timeout_url: None,
             remarks: None,
             occasion: None,
+            retry_policy: None,
         }
     }
 
+    /// Overrides the [`RetryPolicy`] of the client for this request
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> TransactionStatusBuilder<'mpesa> {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
     /// Adds `CommandId`. Defaults to `CommandId::TransactionStatus` if no value explicitly passed
     ///
     /// # Errors
@@ -155,29 +226,49 @@ impl<'mpesa> TransactionStatusBuilder<'mpesa> {
     /// # Errors
     /// Returns a `MpesaError` on failure.
     pub async fn send(self) -> MpesaResult<TransactionStatusResponse> {
-        let credentials = self.client.gen_security_credentials()?;
+        let (client, retry_policy) = (self.client, self.retry_policy);
+        client.call_with(&self.build()?, retry_policy).await
+    }
 
-        let payload = TransactionStatusPayload {
-            initiator: self.initiator,
-            security_credentials: &credentials,
+    /// Builds an owned [`TransactionStatusRequest`], which can be sent later with [`Mpesa::execute`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError::Message` if a required field is missing
+    pub fn build(self) -> MpesaResult<TransactionStatusRequest> {
+        Ok(TransactionStatusRequest {
+            initiator: self.initiator.to_owned(),
             command_id: self.command_id.unwrap_or(CommandId::TransactionStatusQuery),
             transaction_id: self
                 .transaction_id
-                .ok_or(MpesaError::Message("transaction_id is required"))?,
-            party_a: self.party_a.ok_or(MpesaError::Message("party_a is required"))?,
+                .ok_or(MpesaError::Message("transaction_id is required"))?
+                .to_owned(),
+            party_a: self
+                .party_a
+                .ok_or(MpesaError::Message("party_a is required"))?
+                .to_owned(),
             identifier_type: self.identifier_type.unwrap_or(IdentifierTypes::ShortCode),
-            result_url: self.result_url.ok_or(MpesaError::Message("result_url is required"))?,
-            timeout_url: self.timeout_url.ok_or(MpesaError::Message("timeout_url is required"))?,
-            remarks: self.remarks.unwrap_or(stringify!(None)),
-            occasion: self.occasion.unwrap_or(stringify!(None)),
-        };
+            result_url: self
+                .result_url
+                .ok_or(MpesaError::Message("result_url is required"))?
+                .to_owned(),
+            timeout_url: self
+                .timeout_url
+                .ok_or(MpesaError::Message("timeout_url is required"))?
+                .to_owned(),
+            remarks: self.remarks.unwrap_or(stringify!(None)).to_owned(),
+            occasion: self.occasion.unwrap_or(stringify!(None)).to_owned(),
+        })
+    }
 
+    /// Sends the request and waits for its result, correlated by `ConversationID` through [`Mpesa::callbacks`]
+    ///
+    /// # Errors
+    /// Returns a `MpesaError` on failure, or a `MpesaError::CallbackTimeout` if the result does not
+    /// arrive within `timeout`
+    pub async fn send_and_wait(self, timeout: Duration) -> MpesaResult<ResultCallback> {
         self.client
-            .send(crate::client::Request {
-                method: reqwest::Method::POST,
-                path: TRANSACTION_STATUS_URL,
-                body: payload,
-            })
+            .callbacks()
+            .send_and_wait(self.send(), |response| &response.conversation_id, timeout)
             .await
     }
 }
@@ -0,0 +1,290 @@
+//!# Access token stores
+//!
+//! The OAuth access token fetched by a [`Mpesa`](crate::Mpesa) client is kept in a [`TokenStore`], so that it can
+//! be shared beyond a single process e.g between horizontally scaled workers.
+//!
+//! The crate ships with:
+//! 1. [`InMemoryTokenStore`], the default, which keeps tokens in memory and shares them between clones of a store
+//! 2. [`FileTokenStore`], which keeps tokens in a JSON file so that they survive restarts
+//!
+//! Anything else e.g Redis or a database can back a custom [`TokenStore`].
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+pub use async_trait::async_trait;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{MpesaError, MpesaResult};
+
+/// An OAuth access token along with the time it expires
+#[derive(Debug, Clone)]
+pub struct AccessToken {
+    /// The token used as the Bearer-Auth-Token
+    pub token: SecretString,
+    /// Unix timestamp, in seconds, at which the token expires
+    pub expires_at: i64,
+}
+
+impl AccessToken {
+    pub fn new<S: Into<String>>(token: S, expires_at: i64) -> AccessToken {
+        AccessToken {
+            token: token.into().into(),
+            expires_at,
+        }
+    }
+
+    /// Checks if the token is still valid `margin` from now
+    pub fn is_valid_for(&self, margin: Duration) -> bool {
+        let margin = i64::try_from(margin.as_secs()).unwrap_or(i64::MAX);
+        chrono::Utc::now().timestamp().saturating_add(margin) < self.expires_at
+            && !self.token.expose_secret().is_empty()
+    }
+}
+
+/// Storage for the access tokens fetched by a client, keyed by the consumer key and API environment
+/// the token was issued for.
+///
+/// Stores are consulted whenever a client has no valid token of its own, before a new token is requested
+/// from the API. Expired tokens may be returned by `get`, the client checks the expiry itself.
+///
+/// # Example
+///
+/// ```rust
+/// use mpesa::MpesaResult;
+/// use mpesa::token_store::{AccessToken, TokenStore, async_trait};
+///
+/// #[derive(Debug)]
+/// struct NoopTokenStore;
+///
+/// #[async_trait]
+/// impl TokenStore for NoopTokenStore {
+///     async fn get(&self, _key: &str) -> MpesaResult<Option<AccessToken>> {
+///         Ok(None)
+///     }
+///
+///     async fn put(&self, _key: &str, _token: AccessToken) -> MpesaResult<()> {
+///         Ok(())
+///     }
+///
+///     async fn invalidate(&self, _key: &str) -> MpesaResult<()> {
+///         Ok(())
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait TokenStore: Debug + Send + Sync {
+    /// Gets the token stored under `key`, if any
+    async fn get(&self, key: &str) -> MpesaResult<Option<AccessToken>>;
+
+    /// Stores `token` under `key`, replacing any previous token
+    async fn put(&self, key: &str, token: AccessToken) -> MpesaResult<()>;
+
+    /// Removes the token stored under `key`, e.g after the API rejected it
+    async fn invalidate(&self, key: &str) -> MpesaResult<()>;
+}
+
+/// Lets one store be shared by several clients, e.g the clients of a [`MpesaRegistry`](crate::MpesaRegistry)
+#[async_trait]
+impl<T: TokenStore + ?Sized> TokenStore for Arc<T> {
+    async fn get(&self, key: &str) -> MpesaResult<Option<AccessToken>> {
+        (**self).get(key).await
+    }
+
+    async fn put(&self, key: &str, token: AccessToken) -> MpesaResult<()> {
+        (**self).put(key, token).await
+    }
+
+    async fn invalidate(&self, key: &str) -> MpesaResult<()> {
+        (**self).invalidate(key).await
+    }
+}
+
+/// Keeps access tokens in memory.
+///
+/// Clones of the store share the same tokens
+#[derive(Clone, Default)]
+pub struct InMemoryTokenStore {
+    tokens: Arc<RwLock<HashMap<String, AccessToken>>>,
+}
+
+impl Debug for InMemoryTokenStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The keys hold consumer keys, only their count is shown
+        f.debug_struct("InMemoryTokenStore")
+            .field("tokens", &self.tokens.read().unwrap().len())
+            .finish()
+    }
+}
+
+impl InMemoryTokenStore {
+    pub fn new() -> InMemoryTokenStore {
+        InMemoryTokenStore::default()
+    }
+}
+
+#[async_trait]
+impl TokenStore for InMemoryTokenStore {
+    async fn get(&self, key: &str) -> MpesaResult<Option<AccessToken>> {
+        Ok(self.tokens.read().unwrap().get(key).cloned())
+    }
+
+    async fn put(&self, key: &str, token: AccessToken) -> MpesaResult<()> {
+        self.tokens.write().unwrap().insert(key.to_owned(), token);
+        Ok(())
+    }
+
+    async fn invalidate(&self, key: &str) -> MpesaResult<()> {
+        self.tokens.write().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredToken {
+    access_token: String,
+    expires_at: i64,
+}
+
+/// Keeps access tokens in a JSON file.
+///
+/// The file is replaced atomically on every write, so it is never read half written. Writes are only serialized
+/// within a process: the file is not locked, and processes sharing it may overwrite each other's tokens.
+/// It holds live credentials, on unix it is created readable and writable by its owner only.
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    path: PathBuf,
+    /// Serializes the read-modify-write cycles of this process
+    lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl FileTokenStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> FileTokenStore {
+        FileTokenStore {
+            path: path.into(),
+            lock: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    async fn read(&self) -> MpesaResult<HashMap<String, StoredToken>> {
+        match tokio::fs::read(&self.path).await {
+            Ok(contents) if contents.is_empty() => Ok(HashMap::new()),
+            Ok(contents) => serde_json::from_slice(&contents).map_err(|e| MpesaError::TokenStoreError(e.to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(MpesaError::TokenStoreError(e.to_string())),
+        }
+    }
+
+    async fn write(&self, tokens: &HashMap<String, StoredToken>) -> MpesaResult<()> {
+        let contents = serde_json::to_vec(tokens)?;
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(format!(".{}.tmp", std::process::id()));
+        let tmp = PathBuf::from(tmp);
+
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let io_error = |e: std::io::Error| MpesaError::TokenStoreError(e.to_string());
+        let mut file = options.open(&tmp).await.map_err(io_error)?;
+        tokio::io::AsyncWriteExt::write_all(&mut file, &contents)
+            .await
+            .map_err(io_error)?;
+        file.sync_all().await.map_err(io_error)?;
+        tokio::fs::rename(&tmp, &self.path).await.map_err(io_error)
+    }
+}
+
+#[async_trait]
+impl TokenStore for FileTokenStore {
+    async fn get(&self, key: &str) -> MpesaResult<Option<AccessToken>> {
+        let _guard = self.lock.lock().await;
+        Ok(self
+            .read()
+            .await?
+            .remove(key)
+            .map(|stored| AccessToken::new(stored.access_token, stored.expires_at)))
+    }
+
+    async fn put(&self, key: &str, token: AccessToken) -> MpesaResult<()> {
+        let _guard = self.lock.lock().await;
+        let mut tokens = self.read().await?;
+        tokens.insert(
+            key.to_owned(),
+            StoredToken {
+                access_token: token.token.expose_secret().to_owned(),
+                expires_at: token.expires_at,
+            },
+        );
+        self.write(&tokens).await
+    }
+
+    async fn invalidate(&self, key: &str) -> MpesaResult<()> {
+        let _guard = self.lock.lock().await;
+        let mut tokens = self.read().await?;
+        if tokens.remove(key).is_some() {
+            self.write(&tokens).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expires_in(secs: i64) -> i64 {
+        chrono::Utc::now().timestamp() + secs
+    }
+
+    #[test]
+    fn test_access_token_validity() {
+        let token = AccessToken::new("token", expires_in(120));
+        assert!(token.is_valid_for(Duration::from_secs(60)));
+        assert!(!token.is_valid_for(Duration::from_secs(180)));
+        assert!(!AccessToken::new("", expires_in(120)).is_valid_for(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_is_shared_between_clones() {
+        let store = InMemoryTokenStore::new();
+        let shared = store.clone();
+
+        store
+            .put("key", AccessToken::new("token", expires_in(3600)))
+            .await
+            .unwrap();
+        let token = shared.get("key").await.unwrap().unwrap();
+        assert_eq!(token.token.expose_secret(), "token");
+
+        shared.invalidate("key").await.unwrap();
+        assert!(store.get("key").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_file_store_round_trip() {
+        let path = std::env::temp_dir().join(format!("mpesa-token-store-{}.json", std::process::id()));
+        let store = FileTokenStore::new(&path);
+        assert!(store.get("key").await.unwrap().is_none());
+
+        let expires_at = expires_in(3600);
+        store.put("key", AccessToken::new("token", expires_at)).await.unwrap();
+        store.put("other", AccessToken::new("other", expires_at)).await.unwrap();
+
+        // A different store on the same file, e.g after a restart, sees the tokens
+        let token = FileTokenStore::new(&path).get("key").await.unwrap().unwrap();
+        assert_eq!(token.token.expose_secret(), "token");
+        assert_eq!(token.expires_at, expires_at);
+
+        store.invalidate("key").await.unwrap();
+        assert!(store.get("key").await.unwrap().is_none());
+        assert!(store.get("other").await.unwrap().is_some());
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use mpesa::MpesaError;
+use mpesa::{MpesaError, MpesaResponseCode};
 use serde_json::json;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
@@ -34,7 +34,7 @@ async fn account_balance_success() {
         response.response_description,
         "Accept the service request successfully."
     );
-    assert_eq!(response.response_code, "0");
+    assert_eq!(response.response_code, MpesaResponseCode::Success);
 }
 
 #[tokio::test]
@@ -129,3 +129,32 @@ async fn account_balance_fails_if_queue_timeout_url_is_not_provided() {
         panic!("Expected error");
     }
 }
+
+#[tokio::test]
+async fn account_balance_is_retried_with_the_request_retry_policy() {
+    use std::time::Duration;
+
+    use mpesa::RetryPolicy;
+
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/accountbalance/v1/query"))
+        .respond_with(ResponseTemplate::new(503).set_body_string("Service Unavailable"))
+        .expect(3)
+        .mount(&server)
+        .await;
+    let err = client
+        .account_balance("testapi496")
+        .result_url("https://testdomain.com/ok")
+        .timeout_url("https://testdomain.com/err")
+        .party_a("600496")
+        .retry_policy(RetryPolicy {
+            max_attempts: 3,
+            initial_interval: Duration::from_millis(10),
+            ..Default::default()
+        })
+        .send()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MpesaError::TransientError));
+}
@@ -0,0 +1,212 @@
+use std::time::Duration;
+
+use mpesa::{InMemoryTokenStore, Mpesa};
+use serde_json::json;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::helpers::TestEnvironment;
+
+async fn mount_auth(server: &MockServer, expires_in: &str, expected_requests: u64) {
+    Mock::given(method("GET"))
+        .and(path("/oauth/v1/generate"))
+        .and(query_param("grant_type", "client_credentials"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({
+                    "access_token": "dummy_access_token",
+                    "expires_in": expires_in
+                }))
+                .set_delay(Duration::from_millis(200)),
+        )
+        .expect(expected_requests)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn concurrent_callers_share_a_single_token_refresh() {
+    let server = MockServer::start().await;
+    mount_auth(&server, "3600", 1).await;
+    let client = Mpesa::new("consumer_key", "consumer_secret", TestEnvironment::new(&server).await);
+
+    let tasks: Vec<_> = (0..10)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move { client.is_connected().await })
+        })
+        .collect();
+    for task in tasks {
+        assert!(task.await.unwrap());
+    }
+    assert!(client.has_cached_auth());
+}
+
+#[tokio::test]
+async fn token_is_refreshed_within_the_refresh_margin() {
+    let server = MockServer::start().await;
+    mount_auth(&server, "3600", 2).await;
+    let client = Mpesa::new("consumer_key", "consumer_secret", TestEnvironment::new(&server).await);
+
+    assert!(client.is_connected().await);
+    assert!(client.is_connected().await);
+
+    // The cached token now expires within the margin and is refreshed ahead of time
+    client.set_auth_refresh_margin(Duration::from_secs(3600));
+    assert!(!client.has_cached_auth());
+    assert!(client.is_connected().await);
+}
+
+#[tokio::test]
+async fn clients_sharing_a_token_store_share_the_token() {
+    let server = MockServer::start().await;
+    mount_auth(&server, "3600", 1).await;
+    let store = InMemoryTokenStore::new();
+
+    let first = Mpesa::with_token_store(
+        "consumer_key",
+        "consumer_secret",
+        TestEnvironment::new(&server).await,
+        store.clone(),
+    );
+    assert!(first.is_connected().await);
+
+    // A separate client, e.g in another worker, picks up the stored token instead of fetching its own
+    let second = Mpesa::with_token_store(
+        "consumer_key",
+        "consumer_secret",
+        TestEnvironment::new(&server).await,
+        store,
+    );
+    assert!(!second.has_cached_auth());
+    assert!(second.is_connected().await);
+    assert!(second.has_cached_auth());
+}
+
+#[tokio::test]
+async fn builder_configures_the_http_client() {
+    use mpesa::RetryPolicy;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use wiremock::matchers::header;
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/oauth/v1/generate"))
+        .and(header("user-agent", "payments-service/1.0"))
+        .and(header("x-request-source", "payments"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "dummy_access_token",
+            "expires_in": "3600"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let mut headers = HeaderMap::new();
+    headers.insert("x-request-source", HeaderValue::from_static("payments"));
+
+    let client = Mpesa::builder("consumer_key", "consumer_secret", TestEnvironment::new(&server).await)
+        .user_agent("payments-service/1.0")
+        .default_headers(headers)
+        .timeout(Duration::from_secs(5))
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    assert!(client.is_connected().await);
+}
+
+#[cfg(feature = "c2b_simulate")]
+mod replay {
+    use mpesa::{InMemoryTokenStore, Mpesa, MpesaError, MpesaResponseCode, TokenStore};
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::helpers::TestEnvironment;
+
+    async fn mount_tokens(server: &MockServer, tokens: &[&str]) {
+        for token in tokens {
+            Mock::given(method("GET"))
+                .and(path("/oauth/v1/generate"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "access_token": token,
+                    "expires_in": "3600"
+                })))
+                .up_to_n_times(1)
+                .expect(1)
+                .mount(server)
+                .await;
+        }
+    }
+
+    fn invalid_access_token() -> ResponseTemplate {
+        ResponseTemplate::new(401).set_body_json(json!({
+            "requestId": "11728-2929992-1",
+            "errorCode": "404.001.03",
+            "errorMessage": "Invalid Access Token"
+        }))
+    }
+
+    async fn simulate(client: &Mpesa) -> Result<mpesa::services::C2bSimulateResponse, MpesaError> {
+        client
+            .c2b_simulate()
+            .amount(1000)
+            .bill_ref_number("2")
+            .msisdn("254700000000")
+            .short_code("600496")
+            .send()
+            .await
+    }
+
+    #[tokio::test]
+    async fn rejected_token_is_invalidated_and_the_request_replayed() {
+        let server = MockServer::start().await;
+        mount_tokens(&server, &["revoked_token", "fresh_token"]).await;
+        Mock::given(method("POST"))
+            .and(path("/mpesa/c2b/v1/simulate"))
+            .and(header("Authorization", "Bearer revoked_token"))
+            .respond_with(invalid_access_token())
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/mpesa/c2b/v1/simulate"))
+            .and(header("Authorization", "Bearer fresh_token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "OriginatorCoversationID": "29464-48063588-1",
+                "ResponseDescription": "Accept the service request successfully.",
+                "ResponseCode": "0"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let store = InMemoryTokenStore::new();
+        let client = Mpesa::with_token_store(
+            "consumer_key",
+            "consumer_secret",
+            TestEnvironment::new(&server).await,
+            store.clone(),
+        );
+
+        let response = simulate(&client).await.unwrap();
+        assert_eq!(response.response_code, MpesaResponseCode::Success);
+
+        let stored = store.get(&format!("{}|consumer_key", server.uri())).await.unwrap();
+        assert!(stored.is_some());
+    }
+
+    #[tokio::test]
+    async fn replay_with_a_rejected_token_is_unauthorized() {
+        let server = MockServer::start().await;
+        mount_tokens(&server, &["revoked_token", "fresh_token"]).await;
+        Mock::given(method("POST"))
+            .and(path("/mpesa/c2b/v1/simulate"))
+            .respond_with(invalid_access_token())
+            .expect(2)
+            .mount(&server)
+            .await;
+        let client = Mpesa::new("consumer_key", "consumer_secret", TestEnvironment::new(&server).await);
+
+        let err = simulate(&client).await.unwrap_err();
+        assert!(matches!(err, MpesaError::Unauthorized(Some(res)) if res.error_code == "404.001.03"));
+    }
+}
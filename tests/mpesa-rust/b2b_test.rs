@@ -1,4 +1,4 @@
-use mpesa::MpesaError;
+use mpesa::{MpesaError, MpesaResponseCode};
 use serde_json::json;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
@@ -37,7 +37,7 @@ async fn b2b_success() {
         response.response_description,
         "Accept the service request successfully."
     );
-    assert_eq!(response.response_code, "0");
+    assert_eq!(response.response_code, MpesaResponseCode::Success);
 }
 
 #[tokio::test]
@@ -1,9 +1,12 @@
-use mpesa::MpesaError;
+use mpesa::callbacks::ResultCallback;
+use mpesa::services::B2cRequest;
+use mpesa::{Mpesa, MpesaError, MpesaResponseCode};
 use serde_json::json;
-use wiremock::matchers::{method, path};
-use wiremock::{Mock, ResponseTemplate};
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
 
 use crate::get_mpesa_client;
+use crate::helpers::TestEnvironment;
 
 #[tokio::test]
 async fn b2c_success() {
@@ -37,7 +40,51 @@ async fn b2c_success() {
         response.response_description,
         "Accept the service request successfully."
     );
-    assert_eq!(response.response_code, "0");
+    assert_eq!(response.response_code, MpesaResponseCode::Success);
+}
+
+#[tokio::test]
+async fn b2c_owned_request_can_be_queued_and_executed_from_a_task() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v3/paymentrequest"))
+        .and(body_partial_json(json!({
+            "InitiatorName": "testapi496",
+            "CommandID": "BusinessPayment",
+            "Amount": 1000.0,
+            "PartyA": "600496",
+            "PartyB": "254708374149",
+            "Remarks": "None",
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "OriginatorConversationID": "29464-48063588-1",
+            "ConversationID": "AG_20230206_201056794190723278ff",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let request = client
+        .b2c("testapi496")
+        .party_a("600496")
+        .originator_conversation_id("29464-48063588-1")
+        .party_b("254708374149")
+        .result_url("https://testdomain.com/ok")
+        .timeout_url("https://testdomain.com/err")
+        .amount(1000)
+        .build()
+        .unwrap();
+    // e.g persisted as a job payload and picked up by a worker
+    let job = serde_json::to_string(&request).unwrap();
+    let request: B2cRequest = serde_json::from_str(&job).unwrap();
+
+    let response = tokio::spawn(async move { client.execute(request).await })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.conversation_id, "AG_20230206_201056794190723278ff");
 }
 
 #[tokio::test]
@@ -209,3 +256,196 @@ async fn b2c_fails_if_no_queue_timeout_is_provided() {
         panic!("Expected error");
     }
 }
+
+#[tokio::test]
+async fn b2c_send_and_wait_resolves_with_the_result() {
+    let (client, server) = get_mpesa_client!();
+    let sample_response_body = json!({
+        "OriginatorConversationID": "29464-48063588-1",
+        "ConversationID": "AG_20230206_201056794190723278ff",
+        "ResponseDescription": "Accept the service request successfully.",
+        "ResponseCode": "0"
+    });
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v3/paymentrequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_response_body))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    // The result URL endpoint feeds the registry of the client
+    let registry = client.callbacks().clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let result = ResultCallback::from_json(
+            r#"{
+                "Result": {
+                    "ResultType": 0,
+                    "ResultCode": 0,
+                    "ResultDesc": "The service request is processed successfully.",
+                    "OriginatorConversationID": "29464-48063588-1",
+                    "ConversationID": "AG_20230206_201056794190723278ff",
+                    "TransactionID": "NLJ41HAY6Q"
+                }
+            }"#,
+        )
+        .unwrap();
+        registry.deliver_result(result);
+    });
+
+    let result = client
+        .b2c("testapi496")
+        .party_a("600496")
+        .originator_conversation_id("29464-48063588-1")
+        .party_b("254708374149")
+        .result_url("https://testdomain.com/ok")
+        .timeout_url("https://testdomain.com/err")
+        .amount(1000)
+        .send_and_wait(std::time::Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert!(result.is_success());
+    assert_eq!(result.transaction_id.as_deref(), Some("NLJ41HAY6Q"));
+}
+
+#[tokio::test]
+async fn b2c_send_and_wait_times_out() {
+    let (client, server) = get_mpesa_client!();
+    let sample_response_body = json!({
+        "OriginatorConversationID": "29464-48063588-1",
+        "ConversationID": "AG_20230206_201056794190723278ff",
+        "ResponseDescription": "Accept the service request successfully.",
+        "ResponseCode": "0"
+    });
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v3/paymentrequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_response_body))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let err = client
+        .b2c("testapi496")
+        .party_a("600496")
+        .originator_conversation_id("29464-48063588-1")
+        .party_b("254708374149")
+        .result_url("https://testdomain.com/ok")
+        .timeout_url("https://testdomain.com/err")
+        .amount(1000)
+        .send_and_wait(std::time::Duration::from_millis(50))
+        .await
+        .unwrap_err();
+    let MpesaError::CallbackTimeout(id) = err else {
+        panic!("Expected MpesaError::CallbackTimeout, but found {err}");
+    };
+    assert_eq!(id, "AG_20230206_201056794190723278ff");
+}
+
+#[tokio::test]
+async fn b2c_is_not_retried_when_it_may_have_been_processed() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v3/paymentrequest"))
+        .respond_with(ResponseTemplate::new(503).set_body_string("Service Unavailable"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let err = client
+        .b2c("testapi496")
+        .party_a("600496")
+        .originator_conversation_id("29464-48063588-1")
+        .party_b("254708374149")
+        .result_url("https://testdomain.com/ok")
+        .timeout_url("https://testdomain.com/err")
+        .amount(1000)
+        .send()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MpesaError::TransientError));
+}
+
+#[tokio::test]
+async fn b2c_debug_output_masks_the_recipient() {
+    let (client, _server) = get_mpesa_client!();
+    let builder = client
+        .b2c("testapi496")
+        .party_a("600496")
+        .originator_conversation_id("29464-48063588-1")
+        .party_b("254708374149")
+        .result_url("https://testdomain.com/ok")
+        .timeout_url("https://testdomain.com/err")
+        .amount(1000);
+    let builder_debug = format!("{builder:?}");
+    let request_debug = format!("{:?}", builder.build().unwrap());
+    for debug in [builder_debug, request_debug] {
+        assert!(debug.contains(r#""254******149""#), "{debug}");
+        assert!(!debug.contains("254708374149"), "{debug}");
+    }
+}
+
+#[tokio::test]
+async fn b2c_uses_the_credential_of_its_initiator() {
+    let (client, server) = get_mpesa_client!();
+    client.set_initiator_security_credential("payouts_api", "EsJocK7+NjqZPC3I3EO+TbvS+xVb9TymWwaKABoaZr");
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v3/paymentrequest"))
+        .and(body_partial_json(json!({
+            "InitiatorName": "payouts_api",
+            "SecurityCredential": "EsJocK7+NjqZPC3I3EO+TbvS+xVb9TymWwaKABoaZr",
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "OriginatorConversationID": "29464-48063588-1",
+            "ConversationID": "AG_20230206_201056794190723278ff",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    for initiator_name in ["payouts_api", "testapi496"] {
+        let result = client
+            .b2c(initiator_name)
+            .party_a("600496")
+            .originator_conversation_id("29464-48063588-1")
+            .party_b("254708374149")
+            .result_url("https://testdomain.com/ok")
+            .timeout_url("https://testdomain.com/err")
+            .amount(1000)
+            .send()
+            .await;
+        match initiator_name {
+            "payouts_api" => assert_eq!(result.unwrap().conversation_id, "AG_20230206_201056794190723278ff"),
+            _ => assert!(matches!(result, Err(MpesaError::UnknownInitiator(ref name)) if name == "testapi496")),
+        }
+    }
+}
+
+#[tokio::test]
+async fn b2c_requires_an_initiator_password_in_production() {
+    let server = MockServer::start().await;
+    let client = Mpesa::new(
+        "consumer_key",
+        "consumer_secret",
+        TestEnvironment::new(&server).await.production(),
+    );
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v3/paymentrequest"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let err = client
+        .b2c("testapi496")
+        .party_a("600496")
+        .originator_conversation_id("29464-48063588-1")
+        .party_b("254708374149")
+        .result_url("https://testdomain.com/ok")
+        .timeout_url("https://testdomain.com/err")
+        .amount(1000)
+        .send()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MpesaError::MissingCredential("initiator password")));
+}
@@ -28,17 +28,17 @@ async fn bulk_invoice_success() {
         .bulk_invoice()
         .invoices(vec![Invoice {
             amount: 1000.0,
-            account_reference: "John Doe",
-            billed_full_name: "John Doe",
-            billed_period: "August 2021",
-            billed_phone_number: "0712345678",
+            account_reference: "John Doe".to_owned(),
+            billed_full_name: "John Doe".to_owned(),
+            billed_period: "August 2021".to_owned(),
+            billed_phone_number: "0712345678".to_owned(),
             due_date: Utc::now(),
-            external_reference: "INV2345",
+            external_reference: "INV2345".to_owned(),
             invoice_items: Some(vec![InvoiceItem {
                 amount: 1000.0,
-                item_name: "An item",
+                item_name: "An item".to_owned(),
             }]),
-            invoice_name: "Invoice 001",
+            invoice_name: "Invoice 001".to_owned(),
         }])
         .send()
         .await
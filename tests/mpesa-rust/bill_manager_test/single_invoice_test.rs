@@ -35,7 +35,7 @@ async fn single_invoice_success() {
         .external_reference("INV2345")
         .invoice_items(vec![InvoiceItem {
             amount: 1000.0,
-            item_name: "An item",
+            item_name: "An item".to_owned(),
         }])
         .invoice_name("Invoice 001")
         .send()
@@ -0,0 +1,251 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use mpesa::callbacks::server::{CallbackHandler, CallbackServer, ResultKind, async_trait};
+use mpesa::callbacks::{
+    C2bConfirmationRequest, C2bRejectionCode, C2bValidationRequest, C2bValidationResponse, ResultCallback, StkCallback,
+};
+use serde_json::{Value, json};
+use tokio::sync::oneshot;
+
+#[derive(Debug, Default)]
+struct Received {
+    stk: Vec<StkCallback>,
+    confirmations: Vec<C2bConfirmationRequest>,
+    results: Vec<(ResultKind, ResultCallback)>,
+    timeouts: Vec<(ResultKind, Value)>,
+}
+
+#[derive(Clone, Default)]
+struct RecordingHandler {
+    received: Arc<Mutex<Received>>,
+}
+
+#[async_trait]
+impl CallbackHandler for RecordingHandler {
+    async fn on_stk_callback(&self, callback: StkCallback) {
+        self.received.lock().unwrap().stk.push(callback);
+    }
+
+    async fn on_c2b_validation(&self, request: C2bValidationRequest) -> C2bValidationResponse {
+        if request.bill_ref_number.is_empty() {
+            C2bValidationResponse::Reject(C2bRejectionCode::InvalidAccountNumber)
+        } else {
+            C2bValidationResponse::Accept
+        }
+    }
+
+    async fn on_c2b_confirmation(&self, request: C2bConfirmationRequest) {
+        self.received.lock().unwrap().confirmations.push(request);
+    }
+
+    async fn on_result(&self, kind: ResultKind, result: ResultCallback) {
+        self.received.lock().unwrap().results.push((kind, result));
+    }
+
+    async fn on_timeout(&self, kind: ResultKind, body: Value) {
+        self.received.lock().unwrap().timeouts.push((kind, body));
+    }
+}
+
+async fn start_server(handler: RecordingHandler) -> (SocketAddr, oneshot::Sender<()>) {
+    let server = CallbackServer::bind("127.0.0.1:0", handler).await.unwrap();
+    let addr = server.local_addr().unwrap();
+    let (shutdown, signal) = oneshot::channel();
+    tokio::spawn(server.run_until(async {
+        signal.await.ok();
+    }));
+    (addr, shutdown)
+}
+
+async fn post(addr: SocketAddr, path: &str, body: &Value) -> (u16, Value) {
+    let response = reqwest::Client::new()
+        .post(format!("http://{addr}{path}"))
+        .json(body)
+        .send()
+        .await
+        .unwrap();
+    (response.status().as_u16(), response.json().await.unwrap())
+}
+
+fn c2b_request(bill_ref_number: &str) -> Value {
+    json!({
+        "TransactionType": "Pay Bill",
+        "TransID": "RKTQDM7W6S",
+        "TransTime": "20191122063845",
+        "TransAmount": "10",
+        "BusinessShortCode": "600638",
+        "BillRefNumber": bill_ref_number,
+        "InvoiceNumber": "",
+        "OrgAccountBalance": "49197.00",
+        "ThirdPartyTransID": "",
+        "MSISDN": "25470****149",
+        "FirstName": "John",
+        "MiddleName": "",
+        "LastName": "Doe"
+    })
+}
+
+#[tokio::test]
+async fn callbacks_server_routes_stk_callback() {
+    let handler = RecordingHandler::default();
+    let (addr, _shutdown) = start_server(handler.clone()).await;
+
+    let body = json!({
+        "Body": {
+            "stkCallback": {
+                "MerchantRequestID": "29115-34620561-1",
+                "CheckoutRequestID": "ws_CO_191220191020363925",
+                "ResultCode": 1032,
+                "ResultDesc": "Request cancelled by user."
+            }
+        }
+    });
+    let (status, ack) = post(addr, "/stk/callback", &body).await;
+
+    assert_eq!(status, 200);
+    assert_eq!(ack, json!({ "ResultCode": "0", "ResultDesc": "Accepted" }));
+    let received = handler.received.lock().unwrap();
+    assert_eq!(received.stk.len(), 1);
+    assert_eq!(received.stk[0].checkout_request_id, "ws_CO_191220191020363925");
+    assert!(!received.stk[0].is_success());
+}
+
+#[tokio::test]
+async fn callbacks_server_replies_to_c2b_validation() {
+    let (addr, _shutdown) = start_server(RecordingHandler::default()).await;
+
+    let (status, reply) = post(addr, "/c2b/validation", &c2b_request("invoice008")).await;
+    assert_eq!(status, 200);
+    assert_eq!(reply, json!({ "ResultCode": "0", "ResultDesc": "Accepted" }));
+
+    let (status, reply) = post(addr, "/c2b/validation", &c2b_request("")).await;
+    assert_eq!(status, 200);
+    assert_eq!(reply, json!({ "ResultCode": "C2B00012", "ResultDesc": "Rejected" }));
+}
+
+#[tokio::test]
+async fn callbacks_server_acknowledges_c2b_confirmation() {
+    let handler = RecordingHandler::default();
+    let (addr, _shutdown) = start_server(handler.clone()).await;
+
+    let (status, reply) = post(addr, "/c2b/confirmation", &c2b_request("invoice008")).await;
+
+    assert_eq!(status, 200);
+    assert_eq!(reply, json!({ "ResultCode": "0", "ResultDesc": "Success" }));
+    let received = handler.received.lock().unwrap();
+    assert_eq!(received.confirmations.len(), 1);
+    assert_eq!(received.confirmations[0].org_account_balance, "49197.00");
+}
+
+#[tokio::test]
+async fn callbacks_server_routes_results_and_timeouts() {
+    let handler = RecordingHandler::default();
+    let (addr, _shutdown) = start_server(handler.clone()).await;
+
+    let body = json!({
+        "Result": {
+            "ResultType": 0,
+            "ResultCode": 0,
+            "ResultDesc": "The service request is processed successfully.",
+            "OriginatorConversationID": "10571-7910404-1",
+            "ConversationID": "AG_20191219_00004e48cf7e3533f581",
+            "TransactionID": "NLJ41HAY6Q",
+            "ResultParameters": {
+                "ResultParameter": [{ "Key": "TransactionAmount", "Value": 10 }]
+            }
+        }
+    });
+    let (status, ack) = post(addr, "/b2c/result", &body).await;
+    assert_eq!(status, 200);
+    assert_eq!(ack, json!({ "ResultCode": "0", "ResultDesc": "Accepted" }));
+
+    let (status, _) = post(addr, "/balance/timeout/", &json!({})).await;
+    assert_eq!(status, 200);
+
+    let received = handler.received.lock().unwrap();
+    assert_eq!(received.results.len(), 1);
+    assert_eq!(received.results[0].0, ResultKind::B2c);
+    assert_eq!(
+        received.results[0].1.conversation_id,
+        "AG_20191219_00004e48cf7e3533f581"
+    );
+    assert_eq!(received.timeouts.len(), 1);
+    assert_eq!(received.timeouts[0].0, ResultKind::AccountBalance);
+}
+
+#[tokio::test]
+async fn callbacks_server_rejects_unknown_paths_and_invalid_payloads() {
+    let handler = RecordingHandler::default();
+    let (addr, _shutdown) = start_server(handler.clone()).await;
+
+    let (status, _) = post(addr, "/unknown", &json!({})).await;
+    assert_eq!(status, 404);
+
+    let (status, _) = post(addr, "/b2b/result", &json!({ "Result": "invalid" })).await;
+    assert_eq!(status, 400);
+
+    let response = reqwest::get(format!("http://{addr}/stk/callback")).await.unwrap();
+    assert_eq!(response.status().as_u16(), 405);
+
+    assert!(handler.received.lock().unwrap().results.is_empty());
+}
+
+#[tokio::test]
+async fn callbacks_server_feeds_the_client_registry() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let (client, server) = crate::get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpush/v1/processrequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MerchantRequestID": "29115-34620561-1",
+            "CheckoutRequestID": "ws_CO_191220191020363925",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0",
+            "CustomerMessage": "Success. Request accepted for processing"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let callbacks = CallbackServer::bind("127.0.0.1:0", ())
+        .await
+        .unwrap()
+        .with_registry(client.callbacks().clone());
+    let addr = callbacks.local_addr().unwrap();
+    tokio::spawn(callbacks.run());
+
+    // The callback arrives before `send_and_wait` starts waiting, and is buffered
+    let body = json!({
+        "Body": {
+            "stkCallback": {
+                "MerchantRequestID": "29115-34620561-1",
+                "CheckoutRequestID": "ws_CO_191220191020363925",
+                "ResultCode": 0,
+                "ResultDesc": "The service request is processed successfully."
+            }
+        }
+    });
+    let (status, _) = post(addr, "/stk/callback", &body).await;
+    assert_eq!(status, 200);
+
+    let callback = client
+        .express_request()
+        .business_short_code("174379")
+        .transaction_type(mpesa::CommandId::BusinessBuyGoods)
+        .party_a("254708374149")
+        .party_b("174379")
+        .account_ref("test")
+        .phone_number("254708374149")
+        .amount(500)
+        .try_callback_url(format!("http://{addr}/stk/callback").as_str())
+        .unwrap()
+        .build()
+        .unwrap()
+        .send_and_wait(std::time::Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert!(callback.is_success());
+}
@@ -57,10 +57,10 @@ async fn dynamic_qr_code_test_using_struct_initialization() {
 
     let request = DynamicQRRequest {
         amount: 2000,
-        credit_party_identifier: "17408",
-        merchant_name: "SafaricomLTD",
-        ref_no: "rf38f04",
-        size: "300",
+        credit_party_identifier: "17408".to_owned(),
+        merchant_name: "SafaricomLTD".to_owned(),
+        ref_no: "rf38f04".to_owned(),
+        size: "300".to_owned(),
         transaction_type: "bg".try_into().unwrap(),
     };
 
@@ -71,7 +71,7 @@ async fn dynamic_qr_code_test_using_struct_initialization() {
         .mount(&server)
         .await;
 
-    let response = DynamicQR::from_request(&client, request);
+    let response = DynamicQR::from_request(&client, &request);
     let response = response.send().await.unwrap();
 
     assert_eq!(
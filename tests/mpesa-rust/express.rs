@@ -1,10 +1,13 @@
-use mpesa::CommandId;
-use mpesa::services::{MpesaExpress, MpesaExpressRequest};
+use std::time::Duration;
+
+use mpesa::services::{MpesaExpress, MpesaExpressRequest, PollSchedule, StkPushOutcome};
+use mpesa::{CommandId, Mpesa, MpesaError, MpesaResponseCode};
 use serde_json::json;
-use wiremock::matchers::{method, path};
-use wiremock::{Mock, ResponseTemplate};
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
 
 use crate::get_mpesa_client;
+use crate::helpers::TestEnvironment;
 
 #[tokio::test]
 async fn stk_push_success() {
@@ -98,20 +101,17 @@ async fn express_request_test_using_struct_initialization() {
         "CustomerMessage": "Success. Request accepted for processing"
     });
 
-    let password = MpesaExpress::encode_password("174379", None);
-
     let request = MpesaExpressRequest {
-        business_short_code: "174379",
+        business_short_code: "174379".to_owned(),
         transaction_type: CommandId::BusinessBuyGoods,
         amount: 500,
-        party_a: "254708374149",
-        party_b: "174379",
-        phone_number: "254708374149",
-        password,
-        timestamp: chrono::Local::now(),
+        party_a: "254708374149".to_owned(),
+        party_b: "174379".to_owned(),
+        phone_number: "254708374149".to_owned(),
         call_back_url: "https://test.example.com/api".try_into().unwrap(),
-        account_reference: "test",
+        account_reference: "test".to_owned(),
         transaction_desc: None,
+        pass_key: None,
     };
 
     Mock::given(method("POST"))
@@ -121,7 +121,7 @@ async fn express_request_test_using_struct_initialization() {
         .mount(&server)
         .await;
 
-    let request = MpesaExpress::from_request(&client, request, None);
+    let request = MpesaExpress::from_request(&client, &request);
 
     let response = request.send().await.unwrap();
 
@@ -134,6 +134,39 @@ async fn express_request_test_using_struct_initialization() {
     assert_eq!(response.customer_message, "Success. Request accepted for processing");
 }
 
+#[tokio::test]
+async fn express_request_is_validated_when_executed() {
+    let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpush/v1/processrequest"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let request = MpesaExpressRequest {
+        business_short_code: "174379".to_owned(),
+        transaction_type: CommandId::BusinessPayment,
+        amount: 500,
+        party_a: "254708374149".to_owned(),
+        party_b: "174379".to_owned(),
+        phone_number: "254708374149".to_owned(),
+        call_back_url: "https://test.example.com/api".try_into().unwrap(),
+        account_reference: "test".to_owned(),
+        transaction_desc: None,
+        pass_key: None,
+    };
+    let err = client.execute(request.clone()).await.unwrap_err();
+    assert!(matches!(err, MpesaError::Message(msg) if msg.starts_with("Invalid transaction type")));
+
+    let request = MpesaExpressRequest {
+        transaction_type: CommandId::CustomerPayBillOnline,
+        phone_number: "0712".to_owned(),
+        ..request
+    };
+    assert!(client.execute(request).await.is_err());
+}
+
 #[tokio::test]
 async fn test_express_query_transaction() {
     //    Arrange
@@ -173,5 +206,323 @@ async fn test_express_query_transaction() {
     );
     assert_eq!(response.result_desc, "The service request is processed successfully.");
 
-    assert_eq!(response.response_code, "0");
+    assert_eq!(response.response_code, MpesaResponseCode::Success);
+}
+
+fn pending_query_response() -> ResponseTemplate {
+    ResponseTemplate::new(500).set_body_json(json!({
+        "requestId": "8777-4411477-1",
+        "errorCode": "500.001.1001",
+        "errorMessage": "The transaction is being processed"
+    }))
+}
+
+fn fast_schedule() -> PollSchedule {
+    PollSchedule::fixed(Duration::from_millis(10), Duration::from_secs(5))
+}
+
+#[tokio::test]
+async fn express_query_poll_resolves_once_the_push_completes() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(pending_query_response())
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "CheckoutRequestID": "ws_CO_DMZ_12321_23423476",
+            "MerchantRequestID": "16813-1590513-1",
+            "ResponseCode": "0",
+            "ResponseDescription": "The service request has been accepted successsfully",
+            "ResultCode": "1032",
+            "ResultDesc": "Request cancelled by user",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let outcome = client
+        .express_query()
+        .checkout_request_id("ws_CO_DMZ_12321_23423476")
+        .business_short_code("174379")
+        .build()
+        .unwrap()
+        .poll(fast_schedule())
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, StkPushOutcome::CancelledByUser);
+}
+
+#[tokio::test]
+async fn express_query_poll_stops_at_the_deadline() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(pending_query_response())
+        .mount(&server)
+        .await;
+
+    let err = client
+        .express_query()
+        .checkout_request_id("ws_CO_DMZ_12321_23423476")
+        .business_short_code("174379")
+        .build()
+        .unwrap()
+        .poll(PollSchedule::fixed(
+            Duration::from_millis(20),
+            Duration::from_millis(100),
+        ))
+        .await
+        .unwrap_err();
+
+    let MpesaError::PollDeadlineExceeded(checkout_request_id) = err else {
+        panic!("Expected MpesaError::PollDeadlineExceeded, but found {err}");
+    };
+    assert_eq!(checkout_request_id, "ws_CO_DMZ_12321_23423476");
+}
+
+#[tokio::test]
+async fn express_query_poll_fails_on_other_errors() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "requestId": "8777-4411477-1",
+            "errorCode": "400.002.02",
+            "errorMessage": "Bad Request - Invalid CheckoutRequestID"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let err = client
+        .express_query()
+        .checkout_request_id("invalid")
+        .business_short_code("174379")
+        .build()
+        .unwrap()
+        .poll(fast_schedule())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, MpesaError::Service(e) if e.error_code == "400.002.02"));
+}
+
+#[tokio::test]
+async fn express_query_poll_fails_on_server_errors_other_than_pending() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(ResponseTemplate::new(500).set_body_json(json!({
+            "requestId": "8777-4411477-1",
+            "errorCode": "500.001.1001",
+            "errorMessage": "Unable to lock subscriber, a transaction is already in process for the current subscriber"
+        })))
+        .mount(&server)
+        .await;
+
+    let err = client
+        .express_query()
+        .checkout_request_id("ws_CO_DMZ_12321_23423476")
+        .business_short_code("174379")
+        .build()
+        .unwrap()
+        .poll(fast_schedule())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, MpesaError::Service(e) if e.error_message.starts_with("Unable to lock subscriber")));
+}
+
+#[tokio::test]
+async fn express_query_poll_cuts_slow_queries_at_the_deadline() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(pending_query_response().set_delay(Duration::from_secs(5)))
+        .mount(&server)
+        .await;
+
+    let started = std::time::Instant::now();
+    let err = client
+        .express_query()
+        .checkout_request_id("ws_CO_DMZ_12321_23423476")
+        .business_short_code("174379")
+        .build()
+        .unwrap()
+        .poll(PollSchedule::fixed(
+            Duration::from_millis(20),
+            Duration::from_millis(200),
+        ))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, MpesaError::PollDeadlineExceeded(_)));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn express_send_and_poll_resolves_to_success() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpush/v1/processrequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MerchantRequestID": "16813-1590513-1",
+            "CheckoutRequestID": "ws_CO_DMZ_12321_23423476",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0",
+            "CustomerMessage": "Success. Request accepted for processing"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(pending_query_response())
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .and(body_partial_json(
+            json!({ "CheckoutRequestID": "ws_CO_DMZ_12321_23423476" }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "CheckoutRequestID": "ws_CO_DMZ_12321_23423476",
+            "MerchantRequestID": "16813-1590513-1",
+            "ResponseCode": "0",
+            "ResponseDescription": "The service request has been accepted successsfully",
+            "ResultCode": "0",
+            "ResultDesc": "The service request is processed successfully.",
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let outcome = client
+        .express_request()
+        .business_short_code("174379")
+        .transaction_type(mpesa::CommandId::CustomerPayBillOnline)
+        .party_a("254708374149")
+        .party_b("174379")
+        .account_ref("test")
+        .phone_number("254708374149")
+        .amount(500)
+        .try_callback_url("https://test.example.com/api")
+        .unwrap()
+        .build()
+        .unwrap()
+        .send_and_poll(fast_schedule())
+        .await
+        .unwrap();
+
+    assert!(outcome.is_success());
+}
+
+#[tokio::test]
+async fn stk_push_requires_a_passkey_in_production() {
+    let server = MockServer::start().await;
+    let client = Mpesa::new(
+        "consumer_key",
+        "consumer_secret",
+        TestEnvironment::new(&server).await.production(),
+    );
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpush/v1/processrequest"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpushquery/v1/query"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let err = client
+        .express_request()
+        .business_short_code("174379")
+        .transaction_type(CommandId::BusinessBuyGoods)
+        .party_a("254708374149")
+        .party_b("174379")
+        .account_ref("test")
+        .phone_number("254708374149")
+        .amount(500)
+        .try_callback_url("https://test.example.com/api")
+        .unwrap()
+        .build()
+        .unwrap()
+        .send()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MpesaError::MissingCredential("passkey")));
+
+    let err = client
+        .express_query()
+        .business_short_code("174379")
+        .checkout_request_id("ws_CO_DMZ_12321_23423476")
+        .build()
+        .unwrap()
+        .send()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MpesaError::MissingCredential("passkey")));
+}
+
+#[tokio::test]
+async fn stk_push_uses_the_passkey_of_the_client() {
+    let server = MockServer::start().await;
+    let client = Mpesa::builder(
+        "consumer_key",
+        "consumer_secret",
+        TestEnvironment::new(&server).await.production(),
+    )
+    .passkey("bfb279f9aa9bdbcf")
+    .build()
+    .unwrap();
+    Mock::given(method("GET"))
+        .and(path("/oauth/v1/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "dummy_access_token",
+            "expires_in": "3600"
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/mpesa/stkpush/v1/processrequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "MerchantRequestID": "16813-1590513-1",
+            "CheckoutRequestID": "ws_CO_DMZ_12321_23423476",
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0",
+            "CustomerMessage": "Success. Request accepted for processing"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let response = client
+        .express_request()
+        .business_short_code("174379")
+        .transaction_type(CommandId::BusinessBuyGoods)
+        .party_a("254708374149")
+        .party_b("174379")
+        .account_ref("test")
+        .phone_number("254708374149")
+        .amount(500)
+        .try_callback_url("https://test.example.com/api")
+        .unwrap()
+        .build()
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.checkout_request_id, "ws_CO_DMZ_12321_23423476");
 }
@@ -4,12 +4,22 @@ use wiremock::MockServer;
 #[derive(Debug, Clone)]
 pub struct TestEnvironment {
     pub server_url: String,
+    pub is_production: bool,
 }
 
 impl TestEnvironment {
     pub async fn new(server: &MockServer) -> Self {
         TestEnvironment {
             server_url: server.uri(),
+            is_production: false,
+        }
+    }
+
+    /// Makes clients behave as they do against production
+    pub fn production(self) -> Self {
+        TestEnvironment {
+            is_production: true,
+            ..self
         }
     }
 }
@@ -22,6 +32,10 @@ impl ApiEnvironment for TestEnvironment {
     fn get_certificate(&self) -> &str {
         include_str!("../../src/certificates/sandbox")
     }
+
+    fn is_production(&self) -> bool {
+        self.is_production
+    }
 }
 
 #[macro_export]
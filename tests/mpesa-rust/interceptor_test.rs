@@ -0,0 +1,258 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use mpesa::interceptor::{IncomingResponse, Interceptor, OutgoingRequest, async_trait};
+use mpesa::{Mpesa, MpesaError, MpesaRequest, MpesaResult, RetryPolicy};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use wiremock::matchers::{header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::helpers::TestEnvironment;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct PullTransactionsRequest {
+    short_code: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PullTransactionsResponse {
+    response_code: String,
+}
+
+impl MpesaRequest for PullTransactionsRequest {
+    type Response = PullTransactionsResponse;
+
+    const PATH: &'static str = "pulltransactions/v1/query";
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(self)
+    }
+}
+
+/// Records what it sees and tags requests with a header
+#[derive(Debug, Default, Clone)]
+struct Recorder {
+    requests: Arc<Mutex<Vec<(String, serde_json::Value)>>>,
+    responses: Arc<Mutex<Vec<(u16, String)>>>,
+}
+
+#[async_trait]
+impl Interceptor for Recorder {
+    async fn before_send(&self, request: &mut OutgoingRequest<'_>) -> MpesaResult<()> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push((request.path.to_owned(), request.body.clone()));
+        request
+            .headers
+            .insert("x-request-source", "payments-service".parse().unwrap());
+        Ok(())
+    }
+
+    async fn after_receive(&self, response: &IncomingResponse<'_>) {
+        self.responses
+            .lock()
+            .unwrap()
+            .push((response.status.as_u16(), response.body.to_owned()));
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct CustomerLookupRequest {
+    phone_number: String,
+}
+
+impl MpesaRequest for CustomerLookupRequest {
+    type Response = serde_json::Value;
+
+    const PATH: &'static str = "customer/v1/lookup";
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(self)
+    }
+}
+
+/// Records the `Debug` output of requests and responses
+#[derive(Debug, Default, Clone)]
+struct DebugRecorder {
+    output: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl Interceptor for DebugRecorder {
+    async fn before_send(&self, request: &mut OutgoingRequest<'_>) -> MpesaResult<()> {
+        self.output.lock().unwrap().push(format!("{request:?}"));
+        Ok(())
+    }
+
+    async fn after_receive(&self, response: &IncomingResponse<'_>) {
+        self.output.lock().unwrap().push(format!("{response:?}"));
+    }
+}
+
+/// Rejects requests for the blocked short code
+#[derive(Debug)]
+struct Blocklist;
+
+#[async_trait]
+impl Interceptor for Blocklist {
+    async fn before_send(&self, request: &mut OutgoingRequest<'_>) -> MpesaResult<()> {
+        if request.body["ShortCode"] == "000000" {
+            return Err(MpesaError::Rejected("short code is blocked".to_owned()));
+        }
+        Ok(())
+    }
+}
+
+async fn mount_auth(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/oauth/v1/generate"))
+        .and(query_param("grant_type", "client_credentials"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "dummy_access_token",
+            "expires_in": "3600"
+        })))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn interceptors_observe_requests_and_responses() {
+    let server = MockServer::start().await;
+    mount_auth(&server).await;
+    Mock::given(method("POST"))
+        .and(path("/pulltransactions/v1/query"))
+        .and(header("x-request-source", "payments-service"))
+        .and(header("Authorization", "Bearer dummy_access_token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ResponseCode": "1000" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let recorder = Recorder::default();
+    let client = Mpesa::builder("consumer_key", "consumer_secret", TestEnvironment::new(&server).await)
+        .interceptor(recorder.clone())
+        .build()
+        .unwrap();
+    let response = client
+        .call(&PullTransactionsRequest {
+            short_code: "600000".to_owned(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.response_code, "1000");
+
+    assert_eq!(
+        *recorder.requests.lock().unwrap(),
+        vec![("pulltransactions/v1/query".to_owned(), json!({ "ShortCode": "600000" }))]
+    );
+    assert_eq!(
+        *recorder.responses.lock().unwrap(),
+        vec![(200, r#"{"ResponseCode":"1000"}"#.to_owned())]
+    );
+}
+
+#[tokio::test]
+async fn interceptors_see_every_attempt_of_a_request() {
+    let server = MockServer::start().await;
+    mount_auth(&server).await;
+    Mock::given(method("POST"))
+        .and(path("/pulltransactions/v1/query"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/pulltransactions/v1/query"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ResponseCode": "1000" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let recorder = Recorder::default();
+    let client = Mpesa::builder("consumer_key", "consumer_secret", TestEnvironment::new(&server).await)
+        .interceptor(recorder.clone())
+        .retry_policy(RetryPolicy {
+            initial_interval: Duration::from_millis(1),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    client
+        .call(&PullTransactionsRequest {
+            short_code: "600000".to_owned(),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(recorder.requests.lock().unwrap().len(), 2);
+    let statuses: Vec<u16> = recorder.responses.lock().unwrap().iter().map(|(s, _)| *s).collect();
+    assert_eq!(statuses, vec![503, 200]);
+}
+
+#[tokio::test]
+async fn interceptors_can_reject_requests() {
+    let server = MockServer::start().await;
+    mount_auth(&server).await;
+    Mock::given(method("POST"))
+        .and(path("/pulltransactions/v1/query"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ResponseCode": "1000" })))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let recorder = Recorder::default();
+    let client = Mpesa::builder("consumer_key", "consumer_secret", TestEnvironment::new(&server).await)
+        .interceptor(Blocklist)
+        .interceptor(recorder.clone())
+        .build()
+        .unwrap();
+    let err = client
+        .call(&PullTransactionsRequest {
+            short_code: "000000".to_owned(),
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MpesaError::Rejected(ref msg) if msg == "short code is blocked"));
+    // interceptors registered after the rejecting one are not called
+    assert!(recorder.requests.lock().unwrap().is_empty());
+    assert!(recorder.responses.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn interceptor_types_mask_phone_numbers_in_debug_output() {
+    let server = MockServer::start().await;
+    mount_auth(&server).await;
+    Mock::given(method("POST"))
+        .and(path("/customer/v1/lookup"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "PhoneNumber": "254708374149" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let recorder = DebugRecorder::default();
+    let client = Mpesa::builder("consumer_key", "consumer_secret", TestEnvironment::new(&server).await)
+        .interceptor(recorder.clone())
+        .build()
+        .unwrap();
+    client
+        .call(&CustomerLookupRequest {
+            phone_number: "254708374149".to_owned(),
+        })
+        .await
+        .unwrap();
+
+    let output = recorder.output.lock().unwrap();
+    assert_eq!(output.len(), 2);
+    for debug in output.iter() {
+        assert!(!debug.contains("254708374149"), "{debug}");
+        assert!(debug.contains("254******149"), "{debug}");
+    }
+}
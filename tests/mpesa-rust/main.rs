@@ -2,6 +2,8 @@
 #[cfg(feature = "account_balance")]
 mod account_balance_test;
 #[cfg(test)]
+mod auth_test;
+#[cfg(test)]
 #[cfg(feature = "b2b")]
 mod b2b_test;
 #[cfg(test)]
@@ -16,6 +18,9 @@ mod c2b_register_test;
 #[cfg(test)]
 #[cfg(feature = "c2b_simulate")]
 mod c2b_simulate_test;
+#[cfg(test)]
+#[cfg(all(feature = "callbacks_server", feature = "express", feature = "c2b_register"))]
+mod callbacks_server_test;
 
 #[cfg(test)]
 #[cfg(feature = "dynamic_qr")]
@@ -25,6 +30,21 @@ mod dynamic_qr_tests;
 mod express;
 mod helpers;
 #[cfg(test)]
+mod interceptor_test;
+#[cfg(test)]
+mod metrics_test;
+#[cfg(test)]
+#[cfg(feature = "b2c")]
+mod registry_test;
+#[cfg(test)]
+mod request_test;
+#[cfg(test)]
+#[cfg(all(feature = "tower", feature = "c2b_register"))]
+mod tower_test;
+#[cfg(test)]
+#[cfg(feature = "tracing")]
+mod tracing_test;
+#[cfg(test)]
 #[cfg(feature = "transaction_reversal")]
 mod transaction_reversal_test;
 #[cfg(test)]
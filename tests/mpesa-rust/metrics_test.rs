@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use mpesa::metrics::{AUTH_ENDPOINT, InMemoryMetricsRecorder};
+use mpesa::{Mpesa, MpesaError, MpesaRequest, MpesaResult, RetryPolicy};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::helpers::TestEnvironment;
+
+const ENDPOINT: &str = "pulltransactions/v1/query";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct PullTransactionsRequest {
+    short_code: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PullTransactionsResponse {
+    response_code: String,
+}
+
+impl MpesaRequest for PullTransactionsRequest {
+    type Response = PullTransactionsResponse;
+
+    const PATH: &'static str = ENDPOINT;
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(self)
+    }
+}
+
+fn pull_transactions() -> PullTransactionsRequest {
+    PullTransactionsRequest {
+        short_code: "600000".to_owned(),
+    }
+}
+
+async fn mount_auth(server: &MockServer, response: ResponseTemplate) {
+    Mock::given(method("GET"))
+        .and(path("/oauth/v1/generate"))
+        .respond_with(response)
+        .mount(server)
+        .await;
+}
+
+async fn client(server: &MockServer, metrics: &InMemoryMetricsRecorder, retry_policy: RetryPolicy) -> Mpesa {
+    Mpesa::builder("consumer_key", "consumer_secret", TestEnvironment::new(server).await)
+        .metrics(metrics.clone())
+        .retry_policy(retry_policy)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn requests_and_retries_are_recorded() {
+    let server = MockServer::start().await;
+    mount_auth(
+        &server,
+        ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "dummy_access_token",
+            "expires_in": "3600"
+        })),
+    )
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/pulltransactions/v1/query"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/pulltransactions/v1/query"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ResponseCode": "1000" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let metrics = InMemoryMetricsRecorder::new();
+    let retry_policy = RetryPolicy {
+        initial_interval: Duration::from_millis(1),
+        ..Default::default()
+    };
+    let client = client(&server, &metrics, retry_policy).await;
+    let response = client.call(&pull_transactions()).await.unwrap();
+    assert_eq!(response.response_code, "1000");
+
+    assert_eq!(metrics.requests(ENDPOINT), 2);
+    assert_eq!(metrics.latencies(ENDPOINT).len(), 2);
+    assert_eq!(metrics.retries(ENDPOINT), 1);
+    assert_eq!(metrics.auth_refreshes(), 1);
+    assert_eq!(metrics.auth_failures(), 0);
+    assert_eq!(metrics.throttled(ENDPOINT), 0);
+}
+
+#[tokio::test]
+async fn error_codes_and_throttling_are_recorded() {
+    let server = MockServer::start().await;
+    mount_auth(
+        &server,
+        ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "dummy_access_token",
+            "expires_in": "3600"
+        })),
+    )
+    .await;
+    Mock::given(method("POST"))
+        .and(path("/pulltransactions/v1/query"))
+        .respond_with(ResponseTemplate::new(429).set_body_json(json!({
+            "requestId": "11728-2929992-1",
+            "errorCode": "500.003.03",
+            "errorMessage": "Error Occurred: Quota Violation"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let metrics = InMemoryMetricsRecorder::new();
+    let client = client(&server, &metrics, RetryPolicy::none()).await;
+    let err = client.call(&pull_transactions()).await.unwrap_err();
+    assert!(matches!(err, MpesaError::Service(e) if e.error_code == "500.003.03"));
+
+    assert_eq!(metrics.requests(ENDPOINT), 1);
+    assert_eq!(metrics.errors(ENDPOINT, "500.003.03"), 1);
+    assert_eq!(metrics.throttled(ENDPOINT), 1);
+    assert_eq!(metrics.retries(ENDPOINT), 0);
+}
+
+#[tokio::test]
+async fn failed_auth_refreshes_are_recorded() {
+    let server = MockServer::start().await;
+    mount_auth(
+        &server,
+        ResponseTemplate::new(400).set_body_json(json!({
+            "requestId": "11728-2929992-1",
+            "errorCode": "400.008.02",
+            "errorMessage": "Invalid grant type passed"
+        })),
+    )
+    .await;
+
+    let metrics = InMemoryMetricsRecorder::new();
+    let client = client(&server, &metrics, RetryPolicy::none()).await;
+    client.call(&pull_transactions()).await.unwrap_err();
+
+    assert_eq!(metrics.auth_refreshes(), 1);
+    assert_eq!(metrics.auth_failures(), 1);
+    assert_eq!(metrics.retries(AUTH_ENDPOINT), 0);
+    assert_eq!(metrics.requests(ENDPOINT), 0);
+}
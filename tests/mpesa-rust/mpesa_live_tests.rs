@@ -409,7 +409,7 @@ impl From<&MpesaConfig> for Mpesa {
             config.consumer_secret(),
             config.get_environment(),
         );
-        client.set_initiator_password(config.initiator_password());
+        client.set_initiator(config.initiator_name(), config.initiator_password());
         client
     }
 }
@@ -0,0 +1,98 @@
+use mpesa::{Environment, Mpesa, MpesaConfig, MpesaError, MpesaRegistry};
+use serde_json::json;
+use wiremock::matchers::{basic_auth, bearer_token, body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::helpers::TestEnvironment;
+
+fn config(consumer_key: &str, party_a: &str) -> MpesaConfig {
+    MpesaConfig {
+        consumer_key: consumer_key.to_owned(),
+        consumer_secret: format!("{consumer_key}_secret"),
+        environment: Environment::Sandbox,
+        initiator_name: Some("testapi496".to_owned()),
+        initiator_password: None,
+        business_short_code: None,
+        party_a: Some(party_a.to_owned()),
+        passkey: None,
+        express_callback_url: None,
+        c2b_confirmation_url: None,
+        c2b_validation_url: None,
+        result_url: None,
+        timeout_url: None,
+    }
+}
+
+async fn mount_tenant(server: &MockServer, consumer_key: &str, party_a: &str, payments: u64) {
+    let token = format!("{consumer_key}_token");
+    Mock::given(method("GET"))
+        .and(path("/oauth/v1/generate"))
+        .and(basic_auth(consumer_key, format!("{consumer_key}_secret")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": token,
+            "expires_in": "3600"
+        })))
+        .expect(1)
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v3/paymentrequest"))
+        .and(bearer_token(token))
+        .and(body_partial_json(json!({ "PartyA": party_a })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "OriginatorConversationID": "29464-48063588-1",
+            "ConversationID": format!("AG_20230206_{party_a}"),
+            "ResponseDescription": "Accept the service request successfully.",
+            "ResponseCode": "0"
+        })))
+        .expect(payments)
+        .mount(server)
+        .await;
+}
+
+async fn pay(registry: &MpesaRegistry, shortcode: &str) -> Result<String, MpesaError> {
+    let response = registry
+        .for_shortcode(shortcode)?
+        .b2c("testapi496")
+        .party_a(shortcode)
+        .originator_conversation_id("29464-48063588-1")
+        .party_b("254708374149")
+        .result_url("https://testdomain.com/ok")
+        .timeout_url("https://testdomain.com/err")
+        .amount(1000)
+        .send()
+        .await?;
+    Ok(response.conversation_id)
+}
+
+#[tokio::test]
+async fn registry_routes_requests_to_the_client_of_each_tenant() {
+    let server = MockServer::start().await;
+    mount_tenant(&server, "acme", "600000", 2).await;
+    mount_tenant(&server, "globex", "600001", 1).await;
+
+    let environment = TestEnvironment::new(&server).await;
+    let mut registry = MpesaRegistry::new();
+    for (tenant, party_a) in [("acme", "600000"), ("globex", "600001")] {
+        let environment = environment.clone();
+        registry.register_with(tenant, config(tenant, party_a), move |config| {
+            Mpesa::builder(
+                config.consumer_key.as_str(),
+                config.consumer_secret.as_str(),
+                environment.clone(),
+            )
+        });
+    }
+
+    assert_eq!(pay(&registry, "600000").await.unwrap(), "AG_20230206_600000");
+    assert_eq!(pay(&registry, "600001").await.unwrap(), "AG_20230206_600001");
+    // the token of the first request is reused
+    assert_eq!(pay(&registry, "600000").await.unwrap(), "AG_20230206_600000");
+
+    let err = pay(&registry, "600002").await.unwrap_err();
+    assert!(matches!(err, MpesaError::UnknownTenant(ref shortcode) if shortcode == "600002"));
+    assert_eq!(
+        err.to_string(),
+        "No client is registered for tenant or shortcode `600002`"
+    );
+}
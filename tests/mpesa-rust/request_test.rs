@@ -0,0 +1,82 @@
+use mpesa::{Mpesa, MpesaError, MpesaRequest, MpesaResult};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use wiremock::matchers::{body_json, header, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::get_mpesa_client;
+
+/// An endpoint the crate does not cover
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct PullTransactionsRequest {
+    short_code: String,
+    offset_value: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PullTransactionsResponse {
+    response_code: String,
+    response_message: String,
+}
+
+impl MpesaRequest for PullTransactionsRequest {
+    type Response = PullTransactionsResponse;
+
+    const PATH: &'static str = "pulltransactions/v1/query";
+    const IDEMPOTENT: bool = true;
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        if self.short_code.is_empty() {
+            return Err(MpesaError::Message("short_code is required"));
+        }
+        Ok(self)
+    }
+}
+
+#[tokio::test]
+async fn custom_requests_are_sent_through_the_client() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/pulltransactions/v1/query"))
+        .and(header("Authorization", "Bearer dummy_access_token"))
+        .and(body_json(json!({ "ShortCode": "600000", "OffsetValue": "0" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ResponseCode": "1000",
+            "ResponseMessage": "Success"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let response = client
+        .call(&PullTransactionsRequest {
+            short_code: "600000".to_owned(),
+            offset_value: "0".to_owned(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.response_code, "1000");
+    assert_eq!(response.response_message, "Success");
+}
+
+#[tokio::test]
+async fn custom_requests_are_not_sent_if_the_payload_fails() {
+    let (client, server) = get_mpesa_client!(expected_auth_requests = 0);
+    Mock::given(method("POST"))
+        .and(path("/pulltransactions/v1/query"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let err = client
+        .call(&PullTransactionsRequest {
+            short_code: "".to_owned(),
+            offset_value: "0".to_owned(),
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, MpesaError::Message("short_code is required")));
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use mpesa::services::C2bRegisterRequest;
+use mpesa::{MpesaError, MpesaResponseCode, ResponseType};
+use serde_json::json;
+use tower::{BoxError, ServiceBuilder, ServiceExt};
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::get_mpesa_client;
+
+fn c2b_register_request() -> C2bRegisterRequest {
+    C2bRegisterRequest {
+        short_code: "600496".to_owned(),
+        response_type: ResponseType::Completed,
+        confirmation_url: "https://testdomain.com/true".to_owned(),
+        validation_url: "https://testdomain.com/valid".to_owned(),
+    }
+}
+
+#[tokio::test]
+async fn client_can_be_wrapped_in_tower_layers() {
+    let (client, server) = get_mpesa_client!();
+    let sample_response_body = json!({
+        "OriginatorCoversationID": "29464-48063588-1",
+        "ResponseDescription": "Accept the service request successfully.",
+        "ResponseCode": "0"
+    });
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .and(body_partial_json(json!({ "ShortCode": "600496" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_response_body))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let service = ServiceBuilder::new()
+        .concurrency_limit(1)
+        .timeout(Duration::from_secs(5))
+        .service(client);
+    let response = service.oneshot(c2b_register_request()).await.unwrap();
+    assert_eq!(response.originator_conversation_id, "29464-48063588-1");
+    assert_eq!(response.response_code, MpesaResponseCode::Success);
+}
+
+#[tokio::test]
+async fn tower_timeout_layer_cancels_slow_requests() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+        .mount(&server)
+        .await;
+
+    let service = ServiceBuilder::new()
+        .timeout(Duration::from_millis(100))
+        .service(client);
+    let err: BoxError = service.oneshot(c2b_register_request()).await.unwrap_err();
+    assert!(
+        err.is::<tower::timeout::error::Elapsed>(),
+        "expected a timeout, found {err}"
+    );
+}
+
+#[tokio::test]
+async fn tower_service_returns_mpesa_errors() {
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/c2b/v1/registerurl"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "requestId": "11728-2929992-1",
+            "errorCode": "400.002.02",
+            "errorMessage": "Bad Request - Invalid ShortCode"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let err = client.oneshot(c2b_register_request()).await.unwrap_err();
+    let MpesaError::Service(res) = err else {
+        panic!("Expected MpesaError::Service, but found {err}");
+    };
+    assert_eq!(res.error_code, "400.002.02");
+}
@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use mpesa::{Mpesa, MpesaRequest, MpesaResult};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::get_mpesa_client;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct PayoutRequest {
+    phone_number: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PayoutResponse {
+    #[serde(rename = "ConversationID")]
+    conversation_id: String,
+}
+
+impl MpesaRequest for PayoutRequest {
+    type Response = PayoutResponse;
+
+    const PATH: &'static str = "mpesa/b2c/v3/paymentrequest";
+
+    fn payload(&self, _client: &Mpesa) -> MpesaResult<impl Serialize + Send + Sync> {
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Fields(BTreeMap<String, String>);
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name().to_owned(), format!("{value:?}"));
+    }
+}
+
+/// Collects the fields of every span and event
+#[derive(Debug, Clone, Default)]
+struct Recorder {
+    spans: Arc<Mutex<BTreeMap<u64, (&'static str, Fields)>>>,
+    events: Arc<Mutex<Vec<Fields>>>,
+}
+
+impl Recorder {
+    fn span(&self, name: &str) -> BTreeMap<String, String> {
+        let spans = self.spans.lock().unwrap();
+        let (_, fields) = spans.values().find(|(n, _)| *n == name).expect("span was not recorded");
+        fields.0.clone()
+    }
+
+    fn all_values(&self) -> Vec<String> {
+        let spans = self.spans.lock().unwrap();
+        let events = self.events.lock().unwrap();
+        spans
+            .values()
+            .map(|(_, fields)| fields)
+            .chain(events.iter())
+            .flat_map(|fields| fields.0.values().cloned())
+            .collect()
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Recorder {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, S>) {
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        self.spans
+            .lock()
+            .unwrap()
+            .insert(id.into_u64(), (attrs.metadata().name(), fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        if let Some((_, fields)) = self.spans.lock().unwrap().get_mut(&id.into_u64()) {
+            values.record(fields);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        self.events.lock().unwrap().push(fields);
+    }
+}
+
+#[tokio::test]
+async fn requests_are_traced_in_a_span() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v3/paymentrequest"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ConversationID": "AG_20191219_00005797af5d7d75f652",
+            "OriginatorConversationID": "16740-34861180-1",
+            "ResponseCode": "0",
+            "ResponseDescription": "Accept the service request successfully."
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let response = client
+        .call(&PayoutRequest {
+            phone_number: "254708374149".to_owned(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.conversation_id, "AG_20191219_00005797af5d7d75f652");
+
+    let span = recorder.span("mpesa.request");
+    assert_eq!(span["endpoint"], "mpesa/b2c/v3/paymentrequest");
+    assert_eq!(span["method"], "POST");
+    assert_eq!(span["attempt"], "1");
+    assert_eq!(span["http.status"], "200");
+    assert_eq!(span["conversation_id"], "AG_20191219_00005797af5d7d75f652");
+    assert!(span.contains_key("latency_ms"));
+    assert!(!span.contains_key("error_code"));
+}
+
+#[tokio::test]
+async fn traced_errors_are_redacted() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(recorder.clone()));
+    let (client, server) = get_mpesa_client!();
+    Mock::given(method("POST"))
+        .and(path("/mpesa/b2c/v3/paymentrequest"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "requestId": "11728-2929992-1",
+            "errorCode": "400.002.02",
+            "errorMessage": "Bad Request - Invalid PartyB 254708374149"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    client
+        .call(&PayoutRequest {
+            phone_number: "254708374149".to_owned(),
+        })
+        .await
+        .unwrap_err();
+
+    let span = recorder.span("mpesa.request");
+    assert_eq!(span["http.status"], "400");
+    assert_eq!(span["error_code"], "400.002.02");
+    assert!(
+        span["error"].contains("Invalid PartyB 254******149"),
+        "{}",
+        span["error"]
+    );
+    for value in recorder.all_values() {
+        assert!(!value.contains("254708374149"), "MSISDN was not redacted: {value}");
+    }
+}
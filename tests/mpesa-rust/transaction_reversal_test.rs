@@ -65,20 +65,18 @@ async fn transaction_reversal_test_using_struct_initialization() {
         .await;
 
     let payload = TransactionReversalRequest {
-        initiator: "testapi496",
-        security_credential: "testapi496".to_string(),
-        command_id: mpesa::CommandId::TransactionReversal,
-        transaction_id: "OEI2AK4Q16",
-        receiver_party: "600111",
+        initiator: "testapi496".to_owned(),
+        transaction_id: "OEI2AK4Q16".to_owned(),
+        receiver_party: "600111".to_owned(),
         receiver_identifier_type: IdentifierTypes::ShortCode,
         result_url: "https://testdomain.com/ok".parse().unwrap(),
         queue_timeout_url: "https://testdomain.com/err".parse().unwrap(),
-        remarks: "wrong recipient",
+        remarks: "wrong recipient".to_owned(),
         occasion: None,
         amount: 100,
     };
 
-    let response = TransactionReversal::from_request(&client, payload)
+    let response = TransactionReversal::from_request(&client, &payload)
         .send()
         .await
         .unwrap();